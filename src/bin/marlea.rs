@@ -0,0 +1,227 @@
+//! Command line frontend to the engine for running networks stored in files without writing any Rust.
+//! Built with the `cli` feature.
+
+use std::path::Path;
+use std::process::exit;
+use marlea_engine::{BuildError, Builder};
+
+const USAGE: &str = "usage:
+    marlea simulate <network.csv> [--init <solution.csv>] [--trials <n>] [--seed <n>] [--runtime <seconds>] [--out <results.csv>]
+    marlea validate <network.csv> [--init <solution.csv>]
+    marlea timeline <network.csv> --out <timeline.csv> [--init <solution.csv>] [--trials <n>] [--seed <n>]
+
+simulate prints the average stable count of each species, or writes them to --out.
+validate lists the reactions which can never fire and exits with status 1 if there are any.
+timeline writes the solution after every step of each trial to a file named by the trial id followed by --out,
+such as 0timeline.csv, running 1 trial unless --trials is given.";
+
+/// A parsed command line
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Command {
+    name: String,
+    network: String,
+    init: Option<String>,
+    trials: Option<usize>,
+    seed: Option<u64>,
+    runtime: Option<u64>,
+    out: Option<String>,
+}
+
+impl Command {
+    /// parses the arguments following the program name, returning a message describing the first problem found
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut args = args.iter();
+        let name = match args.next().map(|name| name.as_str()) {
+            Some(name @ ("simulate" | "validate" | "timeline")) => name.to_string(),
+            Some(name) => return Err(format!("unknown command {}", name)),
+            None => return Err("missing command".to_string()),
+        };
+
+        let mut network = None;
+        let mut command = Command { name, network: String::new(), init: None, trials: None, seed: None, runtime: None, out: None };
+        while let Some(arg) = args.next() {
+            if !arg.starts_with("--") {
+                if network.replace(arg.clone()).is_some() {
+                    return Err(format!("unexpected argument {}", arg));
+                }
+                continue;
+            }
+            let value = args.next().ok_or_else(|| format!("missing value for {}", arg))?;
+            let number = |value: &str| -> Result<u64, String> {
+                return value.parse().map_err(|_| format!("{} expects a whole number, found {}", arg, value));
+            };
+            match arg.as_str() {
+                "--init" => command.init = Some(value.clone()),
+                "--trials" => command.trials = Some(number(value)? as usize),
+                "--seed" => command.seed = Some(number(value)?),
+                "--runtime" => command.runtime = Some(number(value)?),
+                "--out" => command.out = Some(value.clone()),
+                _ => return Err(format!("unknown option {}", arg)),
+            }
+        }
+
+        command.network = network.ok_or("missing network file")?;
+        if command.name == "timeline" && command.out.is_none() {
+            return Err("timeline needs a file to write to with --out".to_string());
+        }
+        if let Some(out) = &command.out {
+            if Path::new(out).extension().and_then(|extension| extension.to_str()) != Some("csv") {
+                return Err(format!("--out expects a .csv file, found {}", out));
+            }
+            // the trial id is put in front of the whole path, so a timeline can only go to the working directory
+            if command.name == "timeline" && Path::new(out).parent().is_some_and(|parent| !parent.as_os_str().is_empty()) {
+                return Err(format!("timeline --out expects a file name without a directory, found {}", out));
+            }
+        }
+        return Ok(command);
+    }
+
+    fn builder(&self) -> Result<Builder, BuildError> {
+        let mut builder = Builder::try_from_files(self.network.clone(), self.init.clone())?;
+        if let Some(trials) = self.trials {
+            builder = builder.trials(trials);
+        }
+        if let Some(seed) = self.seed {
+            builder = builder.seed(seed);
+        }
+        if let Some(runtime) = self.runtime {
+            builder = builder.runtime(runtime);
+        }
+        return Ok(builder);
+    }
+
+    /// runs the command and returns the exit status, printing the error of a command which could not run
+    fn run(&self) -> i32 {
+        return match self.execute() {
+            Ok(status) => status,
+            Err(error) => {
+                eprintln!("error: {}", error);
+                1
+            }
+        };
+    }
+
+    fn execute(&self) -> Result<i32, BuildError> {
+        let engine = match self.name.as_str() {
+            "simulate" => {
+                let mut builder = self.builder()?;
+                if let Some(out) = &self.out {
+                    builder = builder.out_path(out.clone());
+                }
+                builder.try_build()?
+            }
+            "validate" => {
+                let builder = self.builder()?;
+                let network = builder.get_network();
+                println!("{} reactions, {} species", network.get_reactions().len(), network.get_solution().species_counts.len());
+                let unreachable = network.unreachable_reactions();
+                for reaction in &unreachable {
+                    println!("reaction {} can never fire", reaction);
+                }
+                return Ok(match unreachable.is_empty() {
+                    true => 0,
+                    false => 1,
+                });
+            }
+            _ => {
+                let builder = self.builder()?.trials(self.trials.unwrap_or(1));
+                builder.out_timeline(self.out.clone().unwrap()).try_build()?
+            }
+        };
+        engine.run();
+        return Ok(0);
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}", USAGE);
+        return;
+    }
+    match Command::parse(&args) {
+        Ok(command) => exit(command.run()),
+        Err(message) => {
+            eprintln!("error: {}\n\n{}", message, USAGE);
+            exit(2);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<Command, String> {
+        return Command::parse(&args.split_whitespace().map(|arg| arg.to_string()).collect::<Vec<String>>());
+    }
+
+    #[test]
+    fn test_parse() {
+        let command = parse("simulate network.csv --trials 1000 --seed 42 --out results.csv").unwrap();
+        assert_eq!((command.network.as_str(), command.trials, command.seed), ("network.csv", Some(1000), Some(42)));
+        assert_eq!(command.out.as_deref(), Some("results.csv"));
+
+        assert_eq!(parse("simulate network.csv --trials many").unwrap_err(), "--trials expects a whole number, found many");
+        assert_eq!(parse("validate").unwrap_err(), "missing network file");
+        assert!(parse("timeline network.csv").is_err());
+        assert!(parse("plot network.csv").is_err());
+
+        assert_eq!(parse("simulate network.csv --out results.json").unwrap_err(), "--out expects a .csv file, found results.json");
+        assert_eq!(parse("simulate network.csv --out results").unwrap_err(), "--out expects a .csv file, found results");
+        assert!(parse("timeline network.csv --out runs/timeline.csv").is_err());
+        assert!(parse("simulate network.csv --out runs/results.csv").is_ok());
+    }
+
+    /// writes a network where `a + b => c` uses up all 5 b, so every trial ends at 5 a, 0 b and 5 c, and returns its path
+    fn fixture(name: &str) -> (String, String) {
+        let network = std::env::temp_dir().join(format!("marlea_cli_test_{}_{}_network.csv", std::process::id(), name));
+        let init = std::env::temp_dir().join(format!("marlea_cli_test_{}_{}_init.csv", std::process::id(), name));
+        std::fs::write(&network, "a + b => c,1\n").unwrap();
+        std::fs::write(&init, "a,10\nb,5\n").unwrap();
+        return (network.to_str().unwrap().to_string(), init.to_str().unwrap().to_string());
+    }
+
+    #[test]
+    fn test_simulate() {
+        let (network, init) = fixture("simulate");
+        let out = std::env::temp_dir().join(format!("marlea_cli_test_{}_results.csv", std::process::id()));
+        let command = parse(&format!("simulate {} --init {} --trials 4 --seed 42 --out {}", network, init, out.to_str().unwrap())).unwrap();
+        assert_eq!(command.run(), 0);
+
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), "a,5\nb,0\nc,5\n");
+        for file in [network, init, out.to_str().unwrap().to_string()] {
+            std::fs::remove_file(file).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_timeline() {
+        let (network, init) = fixture("timeline");
+        let out = format!("marlea_cli_test_{}_timeline.csv", std::process::id());
+        let command = parse(&format!("timeline {} --init {} --seed 42 --out {}", network, init, out)).unwrap();
+        assert_eq!(command.run(), 0);
+
+        // a single trial is run, its timeline starts with the species names and counts down to the stable solution
+        let timeline = std::fs::read_to_string(format!("0{}", out)).unwrap();
+        let rows: Vec<&str> = timeline.lines().collect();
+        assert_eq!(rows[0], "a,b,c");
+        assert_eq!(rows.last(), Some(&"5,0,5"));
+        let b: Vec<u64> = rows[1..].iter().map(|row| row.split(',').nth(1).unwrap().parse().unwrap()).collect();
+        assert!(b.windows(2).all(|pair| pair[0] >= pair[1]));
+        assert!(!std::path::Path::new(&format!("1{}", out)).exists());
+        for file in [network, init, format!("0{}", out)] {
+            std::fs::remove_file(file).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_missing_file_is_an_error() {
+        let command = parse("simulate marlea_test_missing_network.csv").unwrap();
+        let Err(BuildError::UnreadableFile { path, .. }) = command.builder() else {
+            panic!("read a network file which does not exist");
+        };
+        assert_eq!(path, "marlea_test_missing_network.csv");
+        assert_eq!(command.run(), 1);
+    }
+}
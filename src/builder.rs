@@ -0,0 +1,725 @@
+use std::sync::{Arc, Condvar, Mutex, OnceLock, atomic::AtomicBool, mpsc::{sync_channel, SyncSender}};
+use std::time::Duration;
+use threadpool::ThreadPool;
+use super::{Alarm, MarleaEngine, MarleaResult, PauseState, Progress};
+use super::error::BuildError;
+use super::log::{self, Level};
+use super::report::{Backpressure, ResponseMode, ResultChannel, ResultSink, SpeciesOrder, SpeciesPattern};
+use super::statistics::Binning;
+use super::supported_file_type::SupportedFileType;
+use super::trial::{observer::StepObserver, passage::{Comparison, PassageTarget}, random::RngBackend, schedule::{Perturbation, ScheduledEvent}, stability::{DetectorFactory, StabilityDetector}, reaction_network::{ReactionNetwork, reaction::term::solution::{Count, Species}}, strata::Stratum};
+
+/// Collects the settings for a `MarleaEngine` before it is constructed. 
+/// Every setting is optional and falls back to the same defaults used by `MarleaEngine::new`. 
+/// Setters do not panic on invalid values, the first one is kept and returned by `try_build` instead.
+/// 
+/// # Example
+/// ```no_run
+/// use marlea_engine::{Builder, trial::random::RngBackend};
+/// 
+/// let engine = Builder::from_files("network.csv".to_string(), Some("init.csv".to_string()))
+///     .trials(1000)
+///     .rng(RngBackend::Xoshiro256PlusPlus)
+///     .build();
+/// let report = engine.run();
+/// ```
+pub struct Builder {
+    prime_network: ReactionNetwork,
+    out_path: Option<String>,
+    out_timeline: Option<String>,
+    num_trials: Option<usize>,
+    max_runtime: Option<u64>,
+    max_semi_stable_steps: Option<i32>,
+    rng_backend: RngBackend,
+    antithetic: bool,
+    strata: Vec<Stratum>,
+    memory_limit: Option<usize>,
+    thread_pool: Option<ThreadPool>,
+    seed: Option<u64>,
+    on_progress: Option<Box<dyn Fn(Progress) + Send + Sync>>,
+    on_alarm: Option<Box<dyn Fn(Alarm) + Send + Sync>>,
+    result_channels: Vec<ResultChannel>,
+    backpressure: Backpressure,
+    report_every: usize,
+    report_interval: Duration,
+    #[cfg(feature = "tokio")]
+    result_capacity: usize,
+    observer: Option<Arc<dyn StepObserver>>,
+    histogram_binning: Option<Binning>,
+    percentiles: Option<Vec<f64>>,
+    target_precision: Option<(String, f64)>,
+    trajectory_interval: Option<i32>,
+    recorded_trajectories: Option<(i32, Option<Vec<usize>>)>,
+    passage_targets: Vec<PassageTarget>,
+    correlated_species: Option<Vec<String>>,
+    bootstrap_resamples: Option<usize>,
+    tracked_species: Option<Vec<String>>,
+    included_species: Vec<SpeciesPattern>,
+    excluded_species: Vec<SpeciesPattern>,
+    species_order: SpeciesOrder,
+    response: ResponseMode,
+    watched_trial: Option<usize>,
+    stop_targets: Vec<PassageTarget>,
+    events: Vec<ScheduledEvent>,
+    alarms: Vec<PassageTarget>,
+    max_steps: Option<i32>,
+    trial_time_limit: Option<Duration>,
+    stability_detector: Option<DetectorFactory>,
+    stall_diagnostics: bool,
+    sparse_solutions: bool,
+    cache_dir: Option<String>,
+    invalid_setting: Option<BuildError>,
+}
+
+impl Builder {
+    /// Starts a builder around an already constructed reaction network
+    pub fn new(prime_network: ReactionNetwork) -> Self {
+        Self {
+            prime_network,
+            out_path: None,
+            out_timeline: None,
+            num_trials: None,
+            max_runtime: None,
+            max_semi_stable_steps: None,
+            rng_backend: RngBackend::default(),
+            antithetic: false,
+            strata: Vec::new(),
+            memory_limit: None,
+            thread_pool: None,
+            seed: None,
+            on_progress: None,
+            on_alarm: None,
+            result_channels: Vec::new(),
+            backpressure: Backpressure::Block,
+            report_every: 1,
+            report_interval: Duration::ZERO,
+            #[cfg(feature = "tokio")]
+            result_capacity: 128,
+            observer: None,
+            histogram_binning: None,
+            percentiles: None,
+            target_precision: None,
+            trajectory_interval: None,
+            recorded_trajectories: None,
+            passage_targets: Vec::new(),
+            correlated_species: None,
+            bootstrap_resamples: None,
+            tracked_species: None,
+            included_species: Vec::new(),
+            excluded_species: Vec::new(),
+            species_order: SpeciesOrder::Name,
+            response: ResponseMode::PerTrial,
+            watched_trial: None,
+            stop_targets: Vec::new(),
+            events: Vec::new(),
+            alarms: Vec::new(),
+            max_steps: None,
+            trial_time_limit: None,
+            stability_detector: None,
+            stall_diagnostics: false,
+            sparse_solutions: false,
+            cache_dir: None,
+            invalid_setting: None,
+        }
+    }
+
+    /// Starts a builder by parsing reactions and an optional initial solution from files, 
+    /// panics if a file is missing or malformed, see `try_from_files`
+    pub fn from_files(input_path: String, init_path: Option<String>) -> Self {
+        return Self::try_from_files(input_path, init_path).unwrap_or_else(|error| panic!("{}", error));
+    }
+
+    /// The same as `from_files` but returns an error naming the file which could not be read rather than panicking
+    pub fn try_from_files(input_path: String, init_path: Option<String>) -> Result<Self, BuildError> {
+        let reactions = SupportedFileType::try_from_path(input_path.clone())
+            .and_then(|file| file.parse_reactions())
+            .map_err(|reason| BuildError::UnreadableFile { path: input_path, reason })?;
+        let solution = MarleaEngine::solution_from(init_path.clone(), &reactions)
+            .map_err(|reason| BuildError::UnreadableFile { path: init_path.unwrap_or_default(), reason })?;
+        return Ok(Self::new(ReactionNetwork::new(reactions, solution)));
+    }
+
+    /// returns the network the engine will simulate
+    pub fn get_network(&self) -> &ReactionNetwork {
+        return &self.prime_network;
+    }
+
+    /// Sets a file to write the averaged stable solution to
+    pub fn out_path(mut self, out_path: String) -> Self {
+        self.out_path = Some(out_path);
+        return self;
+    }
+
+    /// Sets a file to write per trial timelines to
+    pub fn out_timeline(mut self, out_timeline: String) -> Self {
+        self.out_timeline = Some(out_timeline);
+        return self;
+    }
+
+    /// Sets the number of trials used to make the predicted average
+    pub fn trials(mut self, num_trials: usize) -> Self {
+        self.num_trials = Some(num_trials);
+        return self;
+    }
+
+    /// Sets the maximum time in seconds the engine may run for
+    pub fn runtime(mut self, max_runtime: u64) -> Self {
+        self.max_runtime = Some(max_runtime);
+        return self;
+    }
+
+    /// Sets how many steps a trial may stay semi stable before it is considered stable
+    pub fn max_semi_stable_steps(mut self, max_semi_stable_steps: i32) -> Self {
+        self.max_semi_stable_steps = Some(max_semi_stable_steps);
+        return self;
+    }
+
+    /// Cuts every trial off after `max_steps` steps, so one which never stabilizes can not keep a run from finishing. 
+    /// Cut off trials still count towards the results and are flagged with `Termination::StepLimit` in their summary.
+    pub fn max_steps(mut self, max_steps: usize) -> Self {
+        match i32::try_from(max_steps) {
+            Ok(max_steps) if max_steps > 0 => self.max_steps = Some(max_steps),
+            _ => return self.reject(BuildError::InvalidSetting { setting: "max_steps", reason: format!("max steps must be between 1 and {}, found {}", i32::MAX, max_steps) }),
+        }
+        return self;
+    }
+
+    /// Abandons any trial still running after `time_limit` while the others carry on. 
+    /// Abandoned trials are flagged with `Termination::TimedOut` in their summary and left out of every statistic.
+    pub fn trial_time_limit(mut self, time_limit: Duration) -> Self {
+        self.trial_time_limit = Some(time_limit);
+        return self;
+    }
+
+    /// Replaces the null adjacent heuristic deciding when a trial is stable, `detector` is called once per trial. 
+    /// `max_semi_stable_steps` only applies to the default heuristic.
+    /// 
+    /// # Example
+    /// ```no_run
+    /// use marlea_engine::{Builder, trial::stability::MovingWindow};
+    /// 
+    /// let engine = Builder::from_files("network.csv".to_string(), None)
+    ///     .stability_detector(|| Box::new(MovingWindow::new(1000, 0.01)))
+    ///     .build();
+    /// ```
+    pub fn stability_detector(mut self, detector: impl Fn() -> Box<dyn StabilityDetector> + Send + Sync + 'static) -> Self {
+        self.stability_detector = Some(Arc::new(detector));
+        return self;
+    }
+
+    /// Reports which reactions were blocked in the stable solution of each trial and which reactants they were missing, 
+    /// to help find out why a circuit halted in an unexpected state
+    pub fn stall_diagnostics(mut self, stall_diagnostics: bool) -> Self {
+        self.stall_diagnostics = stall_diagnostics;
+        return self;
+    }
+
+    /// Keeps only the species with a count other than 0 in the solutions recorded for each trial. 
+    /// Saves memory and time for networks with many flag species which are 0 most of the time, 
+    /// species which are 0 at the end of every trial are then left out of the report.
+    pub fn sparse_solutions(mut self, sparse_solutions: bool) -> Self {
+        self.sparse_solutions = sparse_solutions;
+        return self;
+    }
+
+    /// Keeps the trials of every run in the given directory, in a checkpoint file named after `MarleaEngine::fingerprint`. 
+    /// A later run of an engine with the same fingerprint reads the trials back and returns the same report without running any. 
+    /// Only runs with a master seed and without per step outputs (trajectories, first passages, alarms, timelines, 
+    /// stability detectors and time limits) are cached, since those can not be rebuilt from the stored trials.
+    pub fn cache_dir(mut self, cache_dir: String) -> Self {
+        self.cache_dir = Some(cache_dir);
+        return self;
+    }
+
+    /// Sets the pseudo random number generator used by each trial
+    pub fn rng(mut self, rng_backend: RngBackend) -> Self {
+        self.rng_backend = rng_backend;
+        return self;
+    }
+
+    /// Runs trials in antithetic pairs, the second trial of each pair mirrors every reaction choice of the first.
+    /// The resulting negative correlation between partners lowers the variance of the average for the same number of trials.
+    /// An odd trial count is rounded up to complete the last pair.
+    pub fn antithetic(mut self, antithetic: bool) -> Self {
+        self.antithetic = antithetic;
+        return self;
+    }
+
+    /// Randomizes initial conditions by stratum. 
+    /// Trials are allocated to strata in proportion to their weights, each stratum is averaged on its own 
+    /// and the results are combined using the stratum weights, 
+    /// so rare but heavily weighted regions are always covered within the trial budget.
+    pub fn strata(mut self, strata: Vec<Stratum>) -> Self {
+        if let Some(species) = strata.iter().flat_map(|stratum| stratum.get_ranges()).map(|(species, _)| species).find(|species| !self.has_species(species)) {
+            return self.reject(BuildError::InvalidSetting { setting: "strata", reason: format!("can not draw species {} which is not in the network", species) });
+        }
+        self.strata = strata;
+        return self;
+    }
+
+    /// Runs trials on the given number of threads instead of one per core
+    pub fn threads(mut self, threads: usize) -> Self {
+        if threads < 1 {
+            return self.reject(BuildError::InvalidSetting { setting: "threads", reason: format!("the engine needs at least 1 thread, found {}", threads) });
+        }
+        self.thread_pool = Some(threadpool::Builder::new().num_threads(threads).thread_name("compute_thread".into()).build());
+        return self;
+    }
+
+    /// Runs trials on an existing pool, which may be shared with other engines or other work, instead of a pool of its own
+    pub fn thread_pool(mut self, thread_pool: ThreadPool) -> Self {
+        self.thread_pool = Some(thread_pool);
+        return self;
+    }
+
+    /// Sets a ceiling in bytes on the memory used by live trials. 
+    /// Rather than queueing every trial up front the engine then only keeps as many alive as fit under the ceiling,
+    /// and refuses to start if a single trial would not fit. The size of a trial includes the trajectory it records 
+    /// with the step limit in samples, so recording trajectories under a memory limit needs `max_steps`, and its timeline buffers.
+    pub fn memory_limit(mut self, bytes: usize) -> Self {
+        self.memory_limit = Some(bytes);
+        return self;
+    }
+
+    /// Sets a master seed every trial seed is derived from, making runs reproducible. 
+    /// Without one each trial is seeded from system entropy.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        return self;
+    }
+
+    /// Sets a callback which is passed a `Progress` snapshot every time a trial finishes
+    pub fn on_progress(mut self, on_progress: impl Fn(Progress) + Send + Sync + 'static) -> Self {
+        self.on_progress = Some(Box::new(on_progress));
+        return self;
+    }
+
+    /// Sets a callback which is passed every `Alarm` as soon as a trial raises it, see `alarm`
+    pub fn on_alarm(mut self, on_alarm: impl Fn(Alarm) + Send + Sync + 'static) -> Self {
+        self.on_alarm = Some(Box::new(on_alarm));
+        return self;
+    }
+
+    /// Adds a channel the engine sends a running average to each time a trial finishes, and the final report once it is done. 
+    /// The engine waits whenever the channel is full, so its capacity is how far the simulation may run ahead of the receiver. 
+    /// Can be called more than once to subscribe several receivers, such as a logger and a GUI, which each get every result
+    pub fn results(mut self, result_sender: SyncSender<MarleaResult>) -> Self {
+        self.result_channels.push(ResultChannel::Std(result_sender));
+        return self;
+    }
+
+    /// Adds a callback the engine passes each result to instead of sending it down a channel, for hosts which can't 
+    /// keep a thread receiving results, or to forward them down any other channel such as one from crossbeam or flume. 
+    /// The callback is called on the thread running the engine, which waits for it to return
+    pub fn on_result(mut self, on_result: impl FnMut(MarleaResult) + Send + 'static) -> Self {
+        self.result_channels.push(ResultChannel::Callback(Mutex::new(Box::new(on_result))));
+        return self;
+    }
+
+    /// Sets what the engine does with running averages while a results channel is full, `Backpressure::Block` by default. 
+    /// The policy applies to each subscriber on its own, though under `Block` one slow receiver holds up every other. 
+    /// A callback set with `on_result` is never full, so there the policy has no effect
+    pub fn backpressure(mut self, backpressure: Backpressure) -> Self {
+        self.backpressure = backpressure;
+        return self;
+    }
+
+    /// Only sends a running average and progress over the results channels once every `trials` finished trials 
+    /// instead of after each one. The summaries of single trials and the final report are still sent in full
+    pub fn report_every(mut self, trials: usize) -> Self {
+        if trials < 1 {
+            return self.reject(BuildError::InvalidSetting { setting: "report_every", reason: format!("averages must be reported at least every 1 trial, found {}", trials) });
+        }
+        self.report_every = trials;
+        return self;
+    }
+
+    /// Sends a running average and progress over the results channels at most once per interval. 
+    /// Together with `report_every` both have to have passed since the last report
+    pub fn report_interval(mut self, interval: Duration) -> Self {
+        self.report_interval = interval;
+        return self;
+    }
+
+    /// Sets the capacity of the channel `run_async` returns, 128 by default
+    #[cfg(feature = "tokio")]
+    pub fn result_capacity(mut self, capacity: usize) -> Self {
+        if capacity == 0 {
+            return self.reject(BuildError::InvalidSetting { setting: "result_capacity", reason: "result channel capacity must be at least 1".to_string() });
+        }
+        self.result_capacity = capacity;
+        return self;
+    }
+
+    /// Sets an observer every trial reports fired reactions and its stable solution to
+    pub fn observer(mut self, observer: Box<dyn StepObserver>) -> Self {
+        self.observer = Some(Arc::from(observer));
+        return self;
+    }
+
+    /// Collects a histogram of the stable count of each species across trials into the final report
+    pub fn histograms(mut self, binning: Binning) -> Self {
+        if matches!(binning, Binning::Width(0) | Binning::Bins(0)) {
+            return self.reject(BuildError::InvalidSetting { setting: "histograms", reason: format!("histograms need a positive bin width or count, found {:?}", binning) });
+        }
+        self.histogram_binning = Some(binning);
+        return self;
+    }
+
+    /// Reports the min, median, max and the given percentiles of the stable count of each species across trials. 
+    /// Percentiles are given between 0 and 100.
+    pub fn percentiles(mut self, percentiles: Vec<f64>) -> Self {
+        if let Some(p) = percentiles.iter().find(|p| !(0.0..=100.0).contains(*p)) {
+            return self.reject(BuildError::InvalidSetting { setting: "percentiles", reason: format!("percentile {} is outside of 0 to 100", p) });
+        }
+        self.percentiles = Some(percentiles);
+        return self;
+    }
+
+    /// Stops launching trials once the 95% confidence interval of the named species' mean is narrower than 
+    /// `relative_ci` times the mean on either side. The trial count set with `trials` becomes the upper bound.
+    pub fn target_precision(mut self, species: &str, relative_ci: f64) -> Self {
+        if relative_ci.is_nan() || relative_ci <= 0.0 {
+            return self.reject(BuildError::InvalidSetting { setting: "target_precision", reason: format!("target precision must be positive, found {}", relative_ci) });
+        }
+        self.target_precision = Some((species.to_string(), relative_ci));
+        return self;
+    }
+
+    /// Samples every trial's solution every `interval` steps and reports the mean trajectory across trials with 95% error bands
+    pub fn trajectory(mut self, interval: i32) -> Self {
+        if interval < 1 {
+            return self.reject(BuildError::InvalidSetting { setting: "trajectory", reason: format!("trajectory interval must be at least 1 step, found {}", interval) });
+        }
+        self.trajectory_interval = Some(interval);
+        return self;
+    }
+
+    /// Records the first step at which each trial satisfies `species <comparison> count` and reports the distribution. 
+    /// May be called more than once to watch several targets.
+    pub fn first_passage(mut self, species: &str, comparison: Comparison, count: Count) -> Self {
+        self.passage_targets.push(PassageTarget::new(species, comparison, count));
+        return self;
+    }
+
+    /// Raises an alarm the first step each trial satisfies `species <comparison> count`, such as a leak species exceeding 10, 
+    /// so design failures show up while the run is still going. Alarms are passed to the `on_alarm` callback and sent to the 
+    /// results channel as soon as a trial raises them, and listed in the report. May be called more than once.
+    pub fn alarm(mut self, species: &str, comparison: Comparison, count: Count) -> Self {
+        self.alarms.push(PassageTarget::new(species, comparison, count));
+        return self;
+    }
+
+    /// Reports the correlation matrix of the stable counts of the given species across trials
+    pub fn correlations(mut self, species: &[&str]) -> Self {
+        self.correlated_species = Some(species.iter().map(|name| name.to_string()).collect());
+        return self;
+    }
+
+    /// Reports bootstrap standard errors and confidence intervals of each species' mean from the given number of resamples, 
+    /// which hold up better than the normal approximation when only a few trials are run
+    pub fn bootstrap(mut self, resamples: usize) -> Self {
+        if resamples < 2 {
+            return self.reject(BuildError::InvalidSetting { setting: "bootstrap", reason: format!("bootstrapping needs at least 2 resamples, found {}", resamples) });
+        }
+        self.bootstrap_resamples = Some(resamples);
+        return self;
+    }
+
+    /// Keeps the solution of every trial every `every_n_steps` steps and returns the samples with the final report, 
+    /// saving a second simulation through `get_timeline`
+    pub fn record_trajectories(mut self, every_n_steps: i32) -> Self {
+        if every_n_steps < 1 {
+            return self.reject(BuildError::InvalidSetting { setting: "record_trajectories", reason: format!("trajectory interval must be at least 1 step, found {}", every_n_steps) });
+        }
+        self.recorded_trajectories = Some((every_n_steps, None));
+        return self;
+    }
+
+    /// Like `record_trajectories` but only keeps the trials with the given ids
+    pub fn record_trajectories_of(mut self, every_n_steps: i32, trial_ids: &[usize]) -> Self {
+        self = self.record_trajectories(every_n_steps);
+        if self.recorded_trajectories.is_some() {
+            self.recorded_trajectories = Some((every_n_steps, Some(trial_ids.to_vec())));
+        }
+        return self;
+    }
+
+    /// Limits trajectories and timelines to the given species, 
+    /// leaving out bookkeeping species which would otherwise dominate their size
+    pub fn tracked_species(mut self, species: &[&str]) -> Self {
+        for name in species {
+            if !self.has_species(name) {
+                return self.reject(BuildError::InvalidSetting { setting: "tracked_species", reason: format!("can not track species {} which is not in the network", name) });
+            }
+        }
+        self.tracked_species = Some(species.iter().map(|name| name.to_string()).collect());
+        return self;
+    }
+
+    /// Limits the averages of the report, the running averages and the output file to species matching any of the patterns. 
+    /// May be called more than once to add patterns. Statistics, histograms and trial solutions still cover every species
+    pub fn include_species(mut self, patterns: &[SpeciesPattern]) -> Self {
+        self.included_species.extend_from_slice(patterns);
+        return self;
+    }
+
+    /// Leaves species matching any of the patterns out of the averages, such as bookkeeping species with `SpeciesPattern::Prefix`. 
+    /// Applied after `include_species`
+    pub fn exclude_species(mut self, patterns: &[SpeciesPattern]) -> Self {
+        self.excluded_species.extend_from_slice(patterns);
+        return self;
+    }
+
+    /// Sets how the averages of the report, the running averages and the output file are sorted, by name by default
+    pub fn order_averages(mut self, order: SpeciesOrder) -> Self {
+        self.species_order = order;
+        return self;
+    }
+
+    /// Holds the counts of the given species at their initial value in every trial, 
+    /// modelling buffers or fuel which are kept at a constant concentration
+    pub fn clamp(mut self, species: &[&str]) -> Self {
+        if let Some(name) = species.iter().find(|name| !self.has_species(name)) {
+            return self.reject(BuildError::InvalidSetting { setting: "clamp", reason: format!("can not clamp species {} which is not in the network", name) });
+        }
+        for name in species {
+            self.prime_network.clamp(name);
+        }
+        return self;
+    }
+
+    /// Changes the count of a species after the given step of every trial, such as `Perturbation::Add(100)` of `fuel` at step 5000, 
+    /// to watch how the network responds. A trial which settles before the step waits for the event and carries on after it. 
+    /// Trials only count steps and have no notion of simulated time, so events are placed by step. May be called more than once, 
+    /// events at the same step are applied in the order they were added. `try_build` fails if the step is negative.
+    pub fn schedule(mut self, step: i32, species: &str, perturbation: Perturbation) -> Self {
+        if step < 0 {
+            return self.reject(BuildError::InvalidSetting { setting: "schedule", reason: format!("can not schedule an event at step {}", step) });
+        }
+        if !self.has_species(species) {
+            return self.reject(BuildError::InvalidSetting { setting: "schedule", reason: format!("can not schedule an event for species {} which is not in the network", species) });
+        }
+        self.events.push(ScheduledEvent::new(step, species, perturbation));
+        return self;
+    }
+
+    /// Changes the count of a species after `first_step` and again every `period` steps of every trial, 
+    /// such as adding 50 `fuel` every 1000 steps to replenish it and keep an oscillator running like in a chemostat. 
+    /// Trials topped up this way may never settle, so a step limit has to be set with `max_steps`. 
+    /// `try_build` fails if the first step is negative or the period is below 1.
+    pub fn schedule_every(mut self, first_step: i32, period: i32, species: &str, perturbation: Perturbation) -> Self {
+        if first_step < 0 {
+            return self.reject(BuildError::InvalidSetting { setting: "schedule_every", reason: format!("can not schedule an event at step {}", first_step) });
+        }
+        if !self.has_species(species) {
+            return self.reject(BuildError::InvalidSetting { setting: "schedule_every", reason: format!("can not schedule an event for species {} which is not in the network", species) });
+        }
+        if period < 1 {
+            return self.reject(BuildError::InvalidSetting { setting: "schedule_every", reason: format!("a repeating event needs a period of at least 1 step, found {}", period) });
+        }
+        self.events.push(ScheduledEvent::repeating(first_step, period, species, perturbation));
+        return self;
+    }
+
+    /// Drives the named species with a measured input waveform read from a CSV file of `time, count` rows. 
+    /// Trials only count steps, so each time is multiplied by `steps_per_time_unit` to find the step its count is set at. 
+    /// The species is clamped so reactions do not change it and holds each count until the next row, or the last one for the rest of the trial. 
+    /// Rows at time 0 or before set the initial count. `try_build` fails if the species is not in the network, 
+    /// the file can not be read or holds no rows, or `steps_per_time_unit` is not a positive number.
+    pub fn input_signal(mut self, species: &str, path: String, steps_per_time_unit: f64) -> Self {
+        if !(steps_per_time_unit.is_finite() && steps_per_time_unit > 0.0) {
+            return self.reject(BuildError::InvalidSetting { 
+                setting: "input_signal", 
+                reason: format!("steps per time unit must be a positive number, found {}", steps_per_time_unit),
+            });
+        }
+        if !self.has_species(species) {
+            return self.reject(BuildError::InvalidSetting { 
+                setting: "input_signal", 
+                reason: format!("can not drive species {} which is not in the network", species),
+            });
+        }
+        let signal = match SupportedFileType::try_from_path(path.clone()).and_then(|file| file.parse_signal()) {
+            Ok(signal) if signal.is_empty() => Err("the input signal holds no rows".to_string()),
+            parsed => parsed,
+        };
+        let signal = match signal {
+            Ok(signal) => signal,
+            Err(reason) => return self.reject(BuildError::UnreadableFile { path, reason }),
+        };
+        self.prime_network.clamp(species);
+        for (time, count) in signal {
+            let step = (time * steps_per_time_unit).round().max(0.0) as i32;
+            self.events.push(ScheduledEvent::new(step, species, Perturbation::Set(count)));
+        }
+        return self;
+    }
+
+    /// Stops each trial once the named species reaches `count` from its initial count, rising or falling. 
+    /// The target is also watched as a first passage target, so the report holds the steps trials took to reach it 
+    /// and the fraction which stabilized before getting there. 
+    /// May be called more than once, a trial then stops at the first target it reaches.
+    pub fn until_species(mut self, species: &str, count: Count) -> Self {
+        let initial = match self.prime_network.get_solution().species_counts.get(&Species::Name(species.to_string())) {
+            Some(Species::Count(initial)) => *initial,
+            _ => return self.reject(BuildError::InvalidSetting { setting: "until_species", reason: format!("can not stop at species {} which is not in the network", species) }),
+        };
+        let comparison = match initial.cmp(&count) {
+            std::cmp::Ordering::Less => Comparison::GreaterOrEqual,
+            std::cmp::Ordering::Equal => Comparison::Equal,
+            std::cmp::Ordering::Greater => Comparison::LessOrEqual,
+        };
+        let target = PassageTarget::new(species, comparison, count);
+        self.passage_targets.push(target.clone());
+        self.stop_targets.push(target);
+        return self;
+    }
+
+    /// Watches for the named species dying out, recording the step its count first hits 0 as a first passage target. 
+    /// The fraction of trials reaching it is the extinction probability, see `FinalReport::extinction_probability`. 
+    /// If `stop` is true trials also end at extinction instead of running on until they are stable.
+    pub fn extinction(mut self, species: &str, stop: bool) -> Self {
+        if !self.has_species(species) {
+            return self.reject(BuildError::InvalidSetting { setting: "extinction", reason: format!("can not watch species {} for extinction which is not in the network", species) });
+        }
+        let target = PassageTarget::new(species, Comparison::Equal, 0);
+        self.passage_targets.push(target.clone());
+        if stop {
+            self.stop_targets.push(target);
+        }
+        return self;
+    }
+
+    /// Streams the species counts of the trial with the given id over the results channel while it runs, 
+    /// so a frontend can animate it live. Only tracked species are streamed if any were set.
+    pub fn watch(mut self, trial_id: usize) -> Self {
+        self.watched_trial = Some(trial_id);
+        return self;
+    }
+
+    /// Sets which results are sent over the results channels while the run is in progress, `ResponseMode::PerTrial` by default. 
+    /// `try_build` fails if the interval of `ResponseMode::EveryStep` is below 1 step
+    pub fn response(mut self, response: ResponseMode) -> Self {
+        if let ResponseMode::EveryStep { every_n } = response {
+            if every_n < 1 {
+                return self.reject(BuildError::InvalidSetting { setting: "response", reason: format!("counts must be sent at least every 1 step, found {}", every_n) });
+            }
+        }
+        self.response = response;
+        return self;
+    }
+
+    /// returns whether the solution of the network holds the named species
+    fn has_species(&self, name: &str) -> bool {
+        return self.prime_network.get_solution().species_counts.contains_key(&Species::Name(name.to_string()));
+    }
+
+    /// keeps the first invalid setting to be returned by `try_build`, leaving the builder otherwise unchanged
+    fn reject(mut self, error: BuildError) -> Self {
+        self.invalid_setting.get_or_insert(error);
+        return self;
+    }
+
+    /// Constructs the engine, panics if the settings are invalid, see `try_build`
+    pub fn build(self) -> MarleaEngine {
+        return self.try_build().unwrap_or_else(|error| panic!("{}", error));
+    }
+
+    /// Checks the network and settings before constructing the engine so a bad configuration fails here rather than mid run. 
+    /// Problems a run can still go ahead with, such as species missing from the solution or reactions which can never fire, are logged as warnings.
+    pub fn try_build(self) -> Result<MarleaEngine, BuildError> {
+        if let Some(error) = self.invalid_setting {
+            return Err(error);
+        }
+        if self.prime_network.get_reactions().is_empty() {
+            return Err(BuildError::EmptyNetwork);
+        }
+        if self.num_trials == Some(0) {
+            return Err(BuildError::NoTrials);
+        }
+        if let (Some(interval), Some((recorded_interval, _))) = (self.trajectory_interval, &self.recorded_trajectories) {
+            if interval != *recorded_interval {
+                return Err(BuildError::TrajectoryIntervalMismatch { mean: interval, recorded: *recorded_interval });
+            }
+        }
+        if self.target_precision.is_some() && !self.strata.is_empty() {
+            return Err(BuildError::PrecisionWithStrata);
+        }
+        if self.memory_limit.is_some() && self.max_steps.is_none() && (self.trajectory_interval.is_some() || self.recorded_trajectories.is_some()) {
+            return Err(BuildError::UnboundedRecording);
+        }
+        if self.max_steps.is_none() && self.events.iter().any(|event| event.get_period().is_some()) {
+            return Err(BuildError::UnboundedSchedule);
+        }
+
+        if !self.prime_network.get_initialized_species().is_empty() {
+            log::log(Level::Warn, format_args!("species {} are only named in reactions and start at 0", self.prime_network.get_initialized_species().join(", ")));
+        }
+        for reaction in self.prime_network.get_reactions().iter().filter(|reaction| reaction.get_reaction_rate() == 0.0) {
+            log::log(Level::Warn, format_args!("reaction {} has a rate of 0 and never fires", reaction));
+        }
+        for reaction in self.prime_network.unreachable_reactions() {
+            log::log(Level::Warn, format_args!("reaction {} can never fire from the initial solution", reaction));
+        }
+
+        let computation_threads_channels = sync_channel(0);
+
+        return Ok(MarleaEngine {
+            out_path: self.out_path,
+            out_timeline: self.out_timeline,
+            num_trials: self.num_trials,
+            max_runtime: self.max_runtime,
+            max_semi_stable_steps: self.max_semi_stable_steps,
+            rng_backend: self.rng_backend,
+            antithetic: self.antithetic,
+            strata: self.strata,
+            memory_limit: self.memory_limit,
+            seed: self.seed,
+            on_progress: self.on_progress,
+            on_alarm: self.on_alarm,
+            result_sinks: self.result_channels.into_iter().map(|channel| ResultSink::new(channel, self.backpressure)).collect(),
+            report_every: self.report_every,
+            report_interval: self.report_interval,
+            #[cfg(feature = "tokio")]
+            backpressure: self.backpressure,
+            #[cfg(feature = "tokio")]
+            result_capacity: self.result_capacity,
+            observer: self.observer,
+            histogram_binning: self.histogram_binning,
+            percentiles: self.percentiles,
+            target_precision: self.target_precision,
+            trajectory_interval: self.trajectory_interval,
+            recorded_trajectories: self.recorded_trajectories,
+            passage_targets: self.passage_targets,
+            correlated_species: self.correlated_species,
+            bootstrap_resamples: self.bootstrap_resamples,
+            tracked_species: self.tracked_species,
+            included_species: self.included_species,
+            excluded_species: self.excluded_species,
+            species_order: self.species_order,
+            response: self.response,
+            watched_trial: self.watched_trial,
+            stop_targets: self.stop_targets,
+            events: self.events,
+            alarms: self.alarms,
+            max_steps: self.max_steps,
+            trial_time_limit: self.trial_time_limit,
+            stability_detector: self.stability_detector,
+            stall_diagnostics: self.stall_diagnostics,
+            sparse_solutions: self.sparse_solutions,
+            cache_dir: self.cache_dir,
+            // without a pool one with a thread per core is created once the first run starts
+            computation_threads: self.thread_pool.map(OnceLock::from).unwrap_or_default(),
+            computations_threads_sender: computation_threads_channels.0,
+            computation_threads_reciever: Mutex::new(computation_threads_channels.1),
+            prime_network: self.prime_network,
+            interrupt: Arc::new(AtomicBool::new(false)),
+            pause_state: Mutex::new(PauseState::default()),
+            pause_signal: Condvar::new(),
+            stepped_run: Mutex::new(None),
+            allocation_blocks: Mutex::new(Vec::new()),
+            live_status: Mutex::new(None),
+        });
+    }
+}
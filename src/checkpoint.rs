@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+use super::trial::{results::{Termination, TrialSummary}, reaction_network::reaction::term::solution::{Count, Species, Solution}};
+
+/// The state of a paused run, enough to continue it later with `MarleaEngine::resume`.
+/// 
+/// Trials are fully determined by their seed, so rather than the internal state of each generator 
+/// a checkpoint stores the seed of every trial which was started and the summary of every trial which finished. 
+/// Trials which were still running when the engine paused are restarted from their seed on resume 
+/// and reach exactly the result they would have reached uninterrupted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Checkpoint {
+    /// Fingerprint of the network the checkpoint was taken from, resuming onto a different network is refused
+    pub network_fingerprint: u64,
+    /// Seeds of every trial started so far indexed by trial id
+    pub trial_seeds: Vec<u64>,
+    pub completed_trials: Vec<TrialSummary>,
+}
+
+impl Checkpoint {
+    /// Writes the checkpoint to a CSV file. 
+    /// Each record starts with its kind: `network,<fingerprint>`, `seed,<id>,<seed>` 
+    /// or `trial,<id>,<seed>,<steps>,<reactions fired>` followed by the name and count of every species. 
+    /// Trials cut off at the step limit or abandoned after their time limit are written the same way 
+    /// with the kind `capped` or `timed_out`, and trials which settled into a limit cycle with the kind `oscillating`.
+    /// Each trial record is followed by `firings,<id>` and the name and firing count of every reaction which fired,
+    /// then by `tail,<id>,<tail steps>` and the same for the reactions fired during the stable tail.
+    pub fn write(&self, path: &str) {
+        self.try_write(path).unwrap_or_else(|error| panic!("{}", error));
+    }
+
+    /// The same as `write` but returns a message describing why the file could not be written rather than panicking
+    pub fn try_write(&self, path: &str) -> Result<(), String> {
+        let failed = |error: csv::Error| format!("failed to write checkpoint file {}: {}", path, error);
+        let mut output_file = csv::WriterBuilder::new()
+            .flexible(true)
+            .from_path(path)
+            .map_err(|error| format!("failed to create checkpoint file {}: {}", path, error))?;
+
+        output_file.write_record(["network".to_string(), self.network_fingerprint.to_string()]).map_err(failed)?;
+        for (id, seed) in self.trial_seeds.iter().enumerate() {
+            output_file.write_record(["seed".to_string(), id.to_string(), seed.to_string()]).map_err(failed)?;
+        }
+        for summary in &self.completed_trials {
+            let kind = match summary.termination {
+                Termination::Stable => "trial",
+                Termination::StepLimit => "capped",
+                Termination::TimedOut => "timed_out",
+                Termination::Oscillating => "oscillating",
+            };
+            let mut record = vec![kind.to_string(), summary.id.to_string(), summary.seed.to_string(), summary.steps.to_string(), summary.reactions_fired.to_string()];
+            for (name, count) in summary.solution.iter() {
+                record.push(name.to_string());
+                record.push(count.to_string());
+            }
+            output_file.write_record(record).map_err(failed)?;
+
+            let mut record = vec!["firings".to_string(), summary.id.to_string()];
+            for (reaction, count) in &summary.reaction_firings {
+                record.push(reaction.clone());
+                record.push(count.to_string());
+            }
+            output_file.write_record(record).map_err(failed)?;
+
+            let mut record = vec!["tail".to_string(), summary.id.to_string(), summary.tail_steps.to_string()];
+            for (reaction, count) in &summary.tail_firings {
+                record.push(reaction.clone());
+                record.push(count.to_string());
+            }
+            output_file.write_record(record).map_err(failed)?;
+        }
+        output_file.flush().map_err(|error| format!("failed to write checkpoint file {}: {}", path, error))?;
+        return Ok(());
+    }
+
+    /// Reads a checkpoint previously written by `write`
+    pub fn read(path: &str) -> Self {
+        return Self::try_read(path).unwrap_or_else(|error| panic!("{}", error));
+    }
+
+    /// The same as `read` but returns a message describing why the file could not be read, 
+    /// such as it being truncated or corrupted, rather than panicking
+    pub fn try_read(path: &str) -> Result<Self, String> {
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_path(path)
+            .map_err(|error| format!("failed to open checkpoint file {}: {}", path, error))?;
+
+        let malformed = || format!("malformed checkpoint file {}", path);
+        let parse = |field: Option<&str>| -> Result<u64, String> {
+            return field.and_then(|field| field.trim().parse().ok()).ok_or_else(malformed);
+        };
+        let parse_count = |field: &str| -> Result<Count, String> {
+            return field.trim().parse().map_err(|_| malformed());
+        };
+
+        let mut checkpoint = Checkpoint { network_fingerprint: 0, trial_seeds: Vec::new(), completed_trials: Vec::new() };
+        let mut seeds: Vec<(u64, u64)> = Vec::new();
+        for record in reader.records() {
+            let record = record.map_err(|error| format!("failed to read checkpoint file {}: {}", path, error))?;
+            match record.get(0) {
+                Some("network") => checkpoint.network_fingerprint = parse(record.get(1))?,
+                Some("seed") => seeds.push((parse(record.get(1))?, parse(record.get(2))?)),
+                Some(kind @ ("trial" | "capped" | "timed_out" | "oscillating")) => {
+                    let mut species_counts = HashMap::new();
+                    let mut fields = record.iter().skip(5);
+                    while let (Some(name), Some(count)) = (fields.next(), fields.next()) {
+                        species_counts.insert(Species::Name(name.to_string()), Species::Count(parse_count(count)?));
+                    }
+                    checkpoint.completed_trials.push(TrialSummary {
+                        id: parse(record.get(1))? as usize,
+                        seed: parse(record.get(2))?,
+                        steps: parse(record.get(3))? as i32,
+                        reactions_fired: parse(record.get(4))?,
+                        reaction_firings: Vec::new(),
+                        tail_firings: Vec::new(),
+                        tail_steps: 0,
+                        termination: match kind {
+                            "capped" => Termination::StepLimit,
+                            "timed_out" => Termination::TimedOut,
+                            "oscillating" => Termination::Oscillating,
+                            _ => Termination::Stable,
+                        },
+                        solution: Solution { species_counts },
+                    });
+                }
+                Some(kind @ ("firings" | "tail")) => {
+                    let id = parse(record.get(1))? as usize;
+                    let summary = checkpoint.completed_trials.iter_mut().rev().find(|summary| summary.id == id)
+                        .ok_or_else(malformed)?;
+                    let (firings, skip) = match kind {
+                        "firings" => (&mut summary.reaction_firings, 2),
+                        _ => {
+                            summary.tail_steps = parse(record.get(2))? as i32;
+                            (&mut summary.tail_firings, 3)
+                        }
+                    };
+                    let mut fields = record.iter().skip(skip);
+                    while let (Some(reaction), Some(count)) = (fields.next(), fields.next()) {
+                        firings.push((reaction.to_string(), parse(Some(count))?));
+                    }
+                }
+                _ => return Err(malformed()),
+            }
+        }
+
+        // ids are taken from the file, so they are checked to cover every trial once before indexing by them
+        let mut trial_seeds = vec![None; seeds.len()];
+        for (id, seed) in seeds {
+            match trial_seeds.get_mut(id as usize) {
+                Some(slot @ None) => *slot = Some(seed),
+                _ => return Err(malformed()),
+            }
+        }
+        checkpoint.trial_seeds = trial_seeds.into_iter().collect::<Option<Vec<u64>>>().ok_or_else(malformed)?;
+        return Ok(checkpoint);
+    }
+}
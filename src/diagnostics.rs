@@ -0,0 +1,156 @@
+use std::collections::BTreeSet;
+use super::trial::reaction_network::{ReactionNetwork, reaction::{Reaction, term::solution::{Count, Species, Solution}}};
+
+/// A reaction which could not fire in the solution a trial stopped at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockedReaction {
+    /// The reaction, written `a + 2 b => c`
+    pub reaction: String,
+    /// Name, count in the solution and count needed of every reactant there was too little of, sorted by name
+    pub missing: Vec<(String, Count, u64)>,
+}
+
+/// returns every reaction which is not possible in the solution along with the reactants blocking it, in reaction order
+pub(crate) fn blocked_reactions(reactions: &BTreeSet<Reaction>, solution: &Solution) -> Vec<BlockedReaction> {
+    return reactions.iter()
+        .filter_map(|reaction| {
+            let mut missing: Vec<(String, Count, u64)> = reaction.get_reactants().iter()
+                .filter_map(|reactant| {
+                    // sparse solutions leave out species at 0
+                    let count = match solution.species_counts.get(reactant.get_species_name()) {
+                        Some(Species::Count(count)) => *count,
+                        _ => 0,
+                    };
+                    match count < reactant.get_coefficient() as Count {
+                        true => Some((reactant.get_species_name().to_string(), count, reactant.get_coefficient())),
+                        false => None,
+                    }
+                })
+                .collect();
+            if missing.is_empty() {
+                return None;
+            }
+            missing.sort();
+            Some(BlockedReaction { reaction: reaction.to_string(), missing })
+        })
+        .collect();
+}
+
+/// A problem with a network found without simulating it, see `MarleaEngine::dry_run`. 
+/// Reactions are written `a + 2 b => c`
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Lint {
+    /// A species only named in reactions, it starts at 0
+    Uninitialized(String),
+    /// A species reactions consume but none produce, its count can only fall from where it starts
+    NeverProduced(String),
+    /// A species reactions produce but none consume, its count can only rise. Usually an output of the network
+    NeverConsumed(String),
+    /// One rail of a dual-rail pair such as `x.0` and `x.1` whose other rail the network does not name
+    UnmatchedRail(String),
+    /// A reaction with a rate of 0, it never fires
+    ZeroRate(String),
+    /// A reaction which can never fire from the initial solution, see `ReactionNetwork::unreachable_reactions`
+    Unreachable(String),
+}
+
+impl std::fmt::Display for Lint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Uninitialized(species) => write!(f, "species {} is only named in reactions and starts at 0", species),
+            Self::NeverProduced(species) => write!(f, "species {} is consumed but never produced", species),
+            Self::NeverConsumed(species) => write!(f, "species {} is produced but never consumed", species),
+            Self::UnmatchedRail(species) => write!(f, "species {} has no matching rail", species),
+            Self::ZeroRate(reaction) => write!(f, "reaction {} has a rate of 0 and never fires", reaction),
+            Self::Unreachable(reaction) => write!(f, "reaction {} can never fire from the initial solution", reaction),
+        }
+    }
+}
+
+/// returns every problem found in the network, grouped by kind and sorted by name
+pub(crate) fn lint(network: &ReactionNetwork) -> Vec<Lint> {
+    let mut produced = BTreeSet::new();
+    let mut consumed = BTreeSet::new();
+    let mut named = BTreeSet::new();
+    for reaction in network.get_reactions() {
+        // catalysts are left unchanged so only the net change counts
+        for (species, change) in reaction.get_net_changes() {
+            if let Species::Name(name) = species {
+                match change.signum() {
+                    1 => produced.insert(name.as_str()),
+                    -1 => consumed.insert(name.as_str()),
+                    _ => false,
+                };
+            }
+        }
+        for term in reaction.get_reactants().iter().chain(reaction.get_products()) {
+            if let Species::Name(name) = term.get_species_name() {
+                named.insert(name.as_str());
+            }
+        }
+    }
+
+    let mut lints: Vec<Lint> = network.get_initialized_species().iter().cloned().map(Lint::Uninitialized).collect();
+    lints.extend(consumed.difference(&produced).map(|name| Lint::NeverProduced(name.to_string())));
+    lints.extend(produced.difference(&consumed).map(|name| Lint::NeverConsumed(name.to_string())));
+    // dual-rail compilation names both rails of a signal, a lone rail is usually a typo
+    for name in &named {
+        let partner = match (name.strip_suffix(".0"), name.strip_suffix(".1")) {
+            (Some(base), _) => format!("{}.1", base),
+            (_, Some(base)) => format!("{}.0", base),
+            _ => continue,
+        };
+        if !named.contains(partner.as_str()) {
+            lints.push(Lint::UnmatchedRail(name.to_string()));
+        }
+    }
+    lints.extend(network.get_reactions().iter()
+        .filter(|reaction| reaction.get_reaction_rate() == 0.0)
+        .map(|reaction| Lint::ZeroRate(reaction.to_string())));
+    lints.extend(network.unreachable_reactions().into_iter().map(|reaction| Lint::Unreachable(reaction.to_string())));
+    return lints;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{HashMap, HashSet};
+    use super::super::trial::reaction_network::reaction::term::Term;
+
+    #[test]
+    fn test_blocked_reactions() {
+        let term = |name: &str, coefficient: u8| Term::new(name.to_string(), coefficient);
+        let reactions = BTreeSet::from([
+            Reaction::new(HashSet::from([term("a", 2), term("b", 1)]), HashSet::from([term("c", 1)]), 1.0),
+            Reaction::new(HashSet::from([term("c", 1)]), HashSet::from([term("a", 1)]), 1.0),
+        ]);
+        let solution = Solution { species_counts: HashMap::from([
+            (Species::Name("a".to_string()), Species::Count(1)),
+            (Species::Name("b".to_string()), Species::Count(0)),
+            (Species::Name("c".to_string()), Species::Count(3)),
+        ]) };
+
+        let blocked = blocked_reactions(&reactions, &solution);
+        assert_eq!(blocked.len(), 1);
+        assert_eq!(blocked[0].missing, vec![("a".to_string(), 1, 2), ("b".to_string(), 0, 1)]);
+    }
+
+    #[test]
+    fn test_lint() {
+        let term = |name: &str| Term::new(name.to_string(), 1);
+        let reactions = HashSet::from([
+            Reaction::new(HashSet::from([term("a"), term("x.0")]), HashSet::from([term("b"), term("x.0")]), 1.0),
+            Reaction::new(HashSet::from([term("b")]), HashSet::from([term("c")]), 0.0),
+            Reaction::new(HashSet::from([term("d")]), HashSet::from([term("a")]), 1.0),
+        ]);
+        let network = ReactionNetwork::new(reactions, Solution::from_pairs(&[("a", 1), ("b", 0), ("c", 0), ("x.0", 1)]));
+        assert_eq!(lint(&network), vec![
+            Lint::Uninitialized("d".to_string()),
+            Lint::NeverProduced("d".to_string()),
+            Lint::NeverConsumed("c".to_string()),
+            Lint::UnmatchedRail("x.0".to_string()),
+            Lint::ZeroRate("b => c".to_string()),
+            Lint::Unreachable("d => a".to_string()),
+        ]);
+    }
+}
@@ -0,0 +1,113 @@
+use super::trial::reaction_network::{CountOutOfRange, UnknownSpecies};
+
+/// Reasons a run can fail, returned by the `try_` methods of the engine.
+/// The methods without the prefix panic with the message of the error instead.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MarleaEngineError {
+    /// The network names species the solution does not hold, see `ReactionNetwork::new_strict`
+    UnknownSpecies(UnknownSpecies),
+    /// A reaction would have taken a species count out of range in the trial with the given id.
+    /// The trials still running were stopped, the run returns no report
+    TrialFailed { trial: usize, failure: CountOutOfRange },
+    /// The checkpoint given to `try_resume` was taken from a different reaction network
+    CheckpointMismatch,
+    /// The memory limit in bytes is below the estimated size of a single trial
+    MemoryLimit { limit: usize, trial_size: usize },
+    /// A file of the experiment bundle written by `run_experiment` could not be written
+    ExportFailed(String),
+}
+
+impl std::fmt::Display for MarleaEngineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownSpecies(unknown) => write!(f, "{}", unknown),
+            Self::TrialFailed { trial, failure } => write!(f, "trial {} failed: {}", trial, failure),
+            Self::CheckpointMismatch => write!(f, "checkpoint was taken from a different reaction network"),
+            Self::MemoryLimit { limit, trial_size } =>
+                write!(f, "memory limit of {} bytes is below the estimated {} bytes needed by a single trial", limit, trial_size),
+            Self::ExportFailed(reason) => write!(f, "experiment export failed: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for MarleaEngineError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        return match self {
+            Self::UnknownSpecies(unknown) => Some(unknown),
+            Self::TrialFailed { failure, .. } => Some(failure),
+            _ => None,
+        };
+    }
+}
+
+impl From<UnknownSpecies> for MarleaEngineError {
+    fn from(unknown: UnknownSpecies) -> Self {
+        return Self::UnknownSpecies(unknown);
+    }
+}
+
+/// Problems with the settings of a `Builder`, returned by `Builder::try_build` before any trial is allocated.
+/// `Builder::build` panics with the message of the error instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildError {
+    /// The network holds no reactions so there is nothing to simulate
+    EmptyNetwork,
+    /// The engine was asked to run 0 trials
+    NoTrials,
+    /// The mean trajectory and the recorded trajectories were given different sampling intervals
+    TrajectoryIntervalMismatch { mean: i32, recorded: i32 },
+    /// A target precision was combined with strata, which each need their full allocation of trials
+    PrecisionWithStrata,
+    /// A repeating event was scheduled without a step limit, trials it keeps topping up would run forever
+    UnboundedSchedule,
+    /// Trajectories are recorded under a memory limit without a step limit, so the memory a trial needs has no bound
+    UnboundedRecording,
+    /// A network or initial solution file given to `Builder::try_from_files`, or an input signal file, is missing or malformed
+    UnreadableFile { path: String, reason: String },
+    /// A setter of the builder was given a value outside of its range, named after the setter
+    InvalidSetting { setting: &'static str, reason: String },
+}
+
+impl std::fmt::Display for BuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::EmptyNetwork => write!(f, "the reaction network holds no reactions"),
+            Self::NoTrials => write!(f, "the number of trials must be at least 1"),
+            Self::TrajectoryIntervalMismatch { mean, recorded } =>
+                write!(f, "the mean trajectory and recorded trajectories must share a sampling interval, found {} and {}", mean, recorded),
+            Self::PrecisionWithStrata => write!(f, "a target precision can not be combined with strata since every stratum needs its full allocation of trials"),
+            Self::UnboundedRecording => write!(f, "recording trajectories under a memory limit needs a step limit since a trajectory grows with the length of its trial"),
+            Self::UnboundedSchedule => write!(f, "a repeating event needs a step limit since a trial it keeps topping up may never settle"),
+            Self::UnreadableFile { path, reason } => write!(f, "could not read {}: {}", path, reason),
+            Self::InvalidSetting { setting, reason } => write!(f, "invalid {}: {}", setting, reason),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+/// Reasons two reports can not be combined, returned by `FinalReport::try_merge`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeError {
+    /// The reports were run on different reaction networks or initial solutions
+    NetworkMismatch,
+    /// The reports watched different first passage targets
+    PassageTargetMismatch,
+    /// The reports were built with settings which change the outcome of a trial, such as a different step limit
+    ConfigMismatch,
+    /// The reports were run from the same master seed or share trial seeds, so pooling them would count the same trials twice
+    OverlappingTrials,
+}
+
+impl std::fmt::Display for MergeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NetworkMismatch => write!(f, "the reports were run on different reaction networks"),
+            Self::PassageTargetMismatch => write!(f, "the reports watched different first passage targets"),
+            Self::ConfigMismatch => write!(f, "the reports were built with different engine settings"),
+            Self::OverlappingTrials => write!(f, "the reports share trials run from the same seeds"),
+        }
+    }
+}
+
+impl std::error::Error for MergeError {}
@@ -0,0 +1,171 @@
+use std::collections::HashSet;
+use std::fs;
+use super::{Binning, EngineConfig, FinalReport};
+use std::path::Path;
+use super::MarleaEngine;
+use super::supported_file_type::SupportedFileType;
+
+/// Writes an experiment bundle to `directory`, creating it if needed.
+/// 
+/// # Layout
+/// - `network.csv` the reactions of the prime network in canonical order
+/// - `initial_solution.csv` the initial species counts of the prime network
+/// - `config.toml` every engine setting the results depend on
+/// - `seeds.csv` the id and seed of every trial and whether it completed
+/// - `results.csv` the averaged stable solution
+/// - `trajectories.csv` recorded trajectories in long format, if any were kept
+/// - `timelines/` per trial timelines, if the engine was recording them
+/// - `manifest.toml` the engine version, creation time, network fingerprint and the list of files above
+/// 
+/// Returns a message describing the first file which could not be written.
+pub fn export(engine: &MarleaEngine, directory: &str, trial_seeds: &[u64], report: &FinalReport) -> Result<(), String> {
+    let completed_trials: HashSet<usize> = report.trials.iter().map(|summary| summary.id).collect();
+    let directory = Path::new(directory);
+    fs::create_dir_all(directory).map_err(|error| format!("failed to create experiment directory {}: {}", directory.display(), error))?;
+    let path = |file_name: &str| directory.join(file_name).to_string_lossy().into_owned();
+    let write_error = |file_name: &str, error: &dyn std::fmt::Display| format!("failed to write {}: {}", path(file_name), error);
+
+    let mut files = vec!["network.csv", "initial_solution.csv", "config.toml", "seeds.csv", "results.csv"];
+
+    SupportedFileType::try_from_path(path("network.csv"))?.write_reactions(engine.prime_network.get_reactions())?;
+    SupportedFileType::try_from_path(path("initial_solution.csv"))?.write_initial_solution(engine.prime_network.get_solution())?;
+    fs::write(path("config.toml"), config_toml(&report.config)).map_err(|error| write_error("config.toml", &error))?;
+    SupportedFileType::try_from_path(path("results.csv"))?.write_solution(report.averages.clone())?;
+
+    let mut seed_file = csv::WriterBuilder::new().from_path(path("seeds.csv")).map_err(|error| write_error("seeds.csv", &error))?;
+    seed_file.write_record(["trial", "seed", "completed"]).map_err(|error| write_error("seeds.csv", &error))?;
+    for (id, seed) in trial_seeds.iter().enumerate() {
+        seed_file.write_record([id.to_string(), seed.to_string(), completed_trials.contains(&id).to_string()])
+            .map_err(|error| write_error("seeds.csv", &error))?;
+    }
+    seed_file.flush().map_err(|error| write_error("seeds.csv", &error))?;
+
+    if !report.trajectories.is_empty() {
+        report.write_trajectories(&path("trajectories.csv"))?;
+        files.push("trajectories.csv");
+    }
+
+    // timeline sub files are written next to the configured timeline path, prefixed with the trial id
+    if let Some(timeline_path) = &engine.out_timeline {
+        let timeline_directory = directory.join("timelines");
+        fs::create_dir_all(&timeline_directory).map_err(|error| write_error("timelines", &error))?;
+        for id in 0..trial_seeds.len() {
+            let sub_file = format!("{}{}", id, timeline_path);
+            if Path::new(&sub_file).exists() {
+                let file_name = format!("{}.csv", id);
+                fs::copy(&sub_file, timeline_directory.join(&file_name)).map_err(|error| write_error(&format!("timelines/{}", file_name), &error))?;
+            }
+        }
+        files.push("timelines/");
+    }
+
+    let manifest = format!(
+        "engine = \"{} {}\"\ncreated = \"{}\"\nnetwork_fingerprint = \"{:016x}\"\ntrials_started = {}\ntrials_completed = {}\nfiles = [{}]\n",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION"),
+        chrono::Local::now().to_rfc3339(),
+        engine.prime_network.fingerprint(),
+        trial_seeds.len(),
+        completed_trials.len(),
+        files.iter().map(|file| format!("\"{}\"", file)).collect::<Vec<String>>().join(", "),
+    );
+    fs::write(path("manifest.toml"), manifest).map_err(|error| write_error("manifest.toml", &error))?;
+    return Ok(());
+}
+
+/// Formats the engine settings as TOML, unset options are left out
+fn config_toml(settings: &EngineConfig) -> String {
+    let mut config = String::new();
+
+    config.push_str(&format!("trials = {}\n", settings.trials));
+    if let Some(max_runtime) = settings.max_runtime {
+        config.push_str(&format!("max_runtime = {}\n", max_runtime));
+    }
+    if let Some(max_semi_stable_steps) = settings.max_semi_stable_steps {
+        config.push_str(&format!("max_semi_stable_steps = {}\n", max_semi_stable_steps));
+    }
+    if let Some(max_steps) = settings.max_steps {
+        config.push_str(&format!("max_steps = {}\n", max_steps));
+    }
+    if let Some(time_limit) = settings.trial_time_limit {
+        config.push_str(&format!("trial_time_limit_ms = {}\n", time_limit.as_millis()));
+    }
+    if let Some(memory_limit) = settings.memory_limit {
+        config.push_str(&format!("memory_limit = {}\n", memory_limit));
+    }
+    if let Some(seed) = settings.seed {
+        config.push_str(&format!("seed = {}\n", seed));
+    }
+    config.push_str(&format!("rng = \"{:?}\"\n", settings.rng_backend));
+    config.push_str(&format!("antithetic = {}\n", settings.antithetic));
+    match settings.histogram_binning {
+        Some(Binning::Width(width)) => config.push_str(&format!("histogram_bin_width = {}\n", width)),
+        Some(Binning::Bins(bins)) => config.push_str(&format!("histogram_bins = {}\n", bins)),
+        None => (),
+    }
+    if let Some((interval, selected)) = &settings.recorded_trajectories {
+        config.push_str(&format!("recorded_trajectory_interval = {}\n", interval));
+        if let Some(selected) = selected {
+            let selected: Vec<String> = selected.iter().map(|id| id.to_string()).collect();
+            config.push_str(&format!("recorded_trajectory_trials = [{}]\n", selected.join(", ")));
+        }
+    }
+    if let Some(interval) = settings.trajectory_interval {
+        config.push_str(&format!("trajectory_interval = {}\n", interval));
+    }
+    if !settings.passage_targets.is_empty() {
+        let targets: Vec<String> = settings.passage_targets.iter().map(|target| format!("\"{}\"", target)).collect();
+        config.push_str(&format!("first_passage = [{}]\n", targets.join(", ")));
+    }
+    if !settings.alarms.is_empty() {
+        let thresholds: Vec<String> = settings.alarms.iter().map(|threshold| format!("\"{}\"", threshold)).collect();
+        config.push_str(&format!("alarms = [{}]\n", thresholds.join(", ")));
+    }
+    if settings.stall_diagnostics {
+        config.push_str("stall_diagnostics = true\n");
+    }
+    if settings.sparse_solutions {
+        config.push_str("sparse_solutions = true\n");
+    }
+    if !settings.until.is_empty() {
+        let targets: Vec<String> = settings.until.iter().map(|target| format!("\"{}\"", target)).collect();
+        config.push_str(&format!("until = [{}]\n", targets.join(", ")));
+    }
+    if !settings.events.is_empty() {
+        let events: Vec<String> = settings.events.iter().map(|event| format!("\"{}\"", event)).collect();
+        config.push_str(&format!("events = [{}]\n", events.join(", ")));
+    }
+    if let Some(resamples) = settings.bootstrap_resamples {
+        config.push_str(&format!("bootstrap_resamples = {}\n", resamples));
+    }
+    if let Some(species) = &settings.correlated_species {
+        let species: Vec<String> = species.iter().map(|name| format!("\"{}\"", name)).collect();
+        config.push_str(&format!("correlations = [{}]\n", species.join(", ")));
+    }
+    if let Some(species) = &settings.tracked_species {
+        let species: Vec<String> = species.iter().map(|name| format!("\"{}\"", name)).collect();
+        config.push_str(&format!("tracked_species = [{}]\n", species.join(", ")));
+    }
+    for (key, patterns) in [("include_species", &settings.included_species), ("exclude_species", &settings.excluded_species)] {
+        if !patterns.is_empty() {
+            let patterns: Vec<String> = patterns.iter().map(|pattern| format!("\"{}\"", pattern)).collect();
+            config.push_str(&format!("{} = [{}]\n", key, patterns.join(", ")));
+        }
+    }
+    if let Some((species, relative_ci)) = &settings.target_precision {
+        config.push_str(&format!("target_precision = {{ species = \"{}\", relative_ci = {} }}\n", species, relative_ci));
+    }
+    if let Some(percentiles) = &settings.percentiles {
+        let percentiles: Vec<String> = percentiles.iter().map(|p| p.to_string()).collect();
+        config.push_str(&format!("percentiles = [{}]\n", percentiles.join(", ")));
+    }
+
+    for stratum in &settings.strata {
+        config.push_str(&format!("\n[[strata]]\nweight = {}\n", stratum.get_weight()));
+        for (species, (min, max)) in stratum.get_ranges() {
+            config.push_str(&format!("{} = [{}, {}]\n", species, min, max));
+        }
+    }
+
+    return config;
+}
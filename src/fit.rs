@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+use super::FinalReport;
+
+/// Bound on the log of a fitted rate, so exponentiating a simplex point always gives a finite positive rate
+const MAX_LOG_RATE: f64 = 700.0;
+
+/// Settings for fitting reaction rates so the simulated averages approach target counts.
+///
+/// Rates are searched with the Nelder–Mead simplex method in log space, which keeps them positive
+/// and lets a single step move a rate by a factor rather than by a fixed amount.
+/// Every evaluation runs the full simulation with the engine's settings, so a master seed should be set
+/// to make the objective deterministic, otherwise sampling noise alone can move the simplex.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fit {
+    rates: Vec<(String, f64)>,
+    targets: Vec<(String, f64)>,
+    max_evaluations: usize,
+}
+
+/// The best rates a fit found
+#[derive(Debug, Clone, PartialEq)]
+pub struct FitResult {
+    /// Each fitted reaction with its best rate, in the order they were added
+    pub rates: Vec<(String, f64)>,
+    /// Sum of squared differences between the averages and the targets at the best rates
+    pub distance: f64,
+    /// Number of distinct rate combinations simulated
+    pub evaluations: usize,
+    pub report: FinalReport,
+}
+
+impl Fit {
+    pub fn new() -> Self {
+        return Self { rates: Vec::new(), targets: Vec::new(), max_evaluations: 100 };
+    }
+
+    /// Fits the rate of a reaction, written `a + 2 b => c`, starting from `initial`. 
+    /// Panics if the initial rate is not a positive number, since the search runs over its logarithm
+    pub fn rate(mut self, reaction: &str, initial: f64) -> Self {
+        if !(initial.is_finite() && initial > 0.0) {
+            panic!("initial rate of {} must be a positive number, found {}", reaction, initial);
+        }
+        self.rates.push((reaction.to_string(), initial));
+        return self;
+    }
+
+    /// Adds a species whose average stable count should come out at `count`
+    pub fn target(mut self, species: &str, count: f64) -> Self {
+        self.targets.push((species.to_string(), count));
+        return self;
+    }
+
+    /// Sets how many distinct rate combinations may be simulated before the fit stops, 100 by default
+    pub fn max_evaluations(mut self, max_evaluations: usize) -> Self {
+        self.max_evaluations = max_evaluations;
+        return self;
+    }
+
+    pub(crate) fn get_reactions(&self) -> Vec<String> {
+        return self.rates.iter().map(|(reaction, _)| reaction.clone()).collect();
+    }
+
+    /// returns the squared distance between the averages of a report and the targets
+    pub(crate) fn distance(&self, report: &FinalReport) -> f64 {
+        return self.targets.iter()
+            .map(|(species, target)| {
+                // statistics hold every species, even those left out of the averages
+                let average = report.statistics.iter().find(|(name, _)| name == species).map_or(0.0, |(_, statistics)| statistics.mean);
+                (average - target).powi(2)
+            })
+            .sum();
+    }
+
+    /// Minimizes `simulate` over positive rates and returns the best rates with their report.
+    /// Points the simplex visits more than once share one simulation.
+    /// Log rates are clamped to ±MAX_LOG_RATE, so a simplex pushed toward extreme rates never simulates an infinite or zero rate.
+    pub(crate) fn minimize(&self, simulate: impl Fn(&[f64]) -> FinalReport) -> FitResult {
+        // simulations are keyed by the bits of the log rates, which compare exactly unlike the floats themselves
+        let key = |point: &[f64]| -> Vec<u64> {point.iter().map(|log_rate| log_rate.to_bits()).collect()};
+        let mut evaluated = HashMap::<Vec<u64>, (f64, FinalReport)>::new();
+        let evaluate = |evaluated: &mut HashMap<Vec<u64>, (f64, FinalReport)>, point: &[f64]| -> f64 {
+            let distance = evaluated.entry(key(point))
+                .or_insert_with(|| {
+                    let rates: Vec<f64> = point.iter().map(|log_rate| log_rate.exp()).collect();
+                    let report = simulate(&rates);
+                    (self.distance(&report), report)
+                })
+                .0;
+            return distance;
+        };
+
+        // start from the initial rates and one point per rate scaled up by e^0.5
+        let start: Vec<f64> = self.rates.iter().map(|(_, rate)| rate.ln().clamp(-MAX_LOG_RATE, MAX_LOG_RATE)).collect();
+        let mut simplex: Vec<(Vec<f64>, f64)> = vec![(start.clone(), evaluate(&mut evaluated, &start))];
+        for i in 0..start.len() {
+            let mut vertex = start.clone();
+            vertex[i] = (vertex[i] + 0.5).min(MAX_LOG_RATE);
+            let distance = evaluate(&mut evaluated, &vertex);
+            simplex.push((vertex, distance));
+        }
+
+        let toward = |from: &[f64], to: &[f64], factor: f64| -> Vec<f64> {
+            from.iter().zip(to).map(|(from, to)| (from + factor * (to - from)).clamp(-MAX_LOG_RATE, MAX_LOG_RATE)).collect()
+        };
+        // points already simulated cost nothing, so iterations are bounded separately
+        let mut iterations = 0;
+        while evaluated.len() < self.max_evaluations && iterations < 10 * self.max_evaluations {
+            iterations += 1;
+            simplex.sort_by(|a, b| a.1.total_cmp(&b.1));
+            let best = simplex[0].1;
+            let worst = simplex.last().unwrap().clone();
+            if worst.1 - best < 1e-9 {
+                break;
+            }
+
+            let others = &simplex[..simplex.len() - 1];
+            let centroid: Vec<f64> = (0..start.len())
+                .map(|i| others.iter().map(|(vertex, _)| vertex[i]).sum::<f64>() / others.len() as f64)
+                .collect();
+
+            let reflected = toward(&worst.0, &centroid, 2.0);
+            let reflected_distance = evaluate(&mut evaluated, &reflected);
+            let second_worst = simplex[simplex.len() - 2].1;
+
+            let last = simplex.len() - 1;
+            if reflected_distance < best {
+                let expanded = toward(&worst.0, &centroid, 3.0);
+                let expanded_distance = evaluate(&mut evaluated, &expanded);
+                simplex[last] = match expanded_distance < reflected_distance {
+                    true => (expanded, expanded_distance),
+                    false => (reflected, reflected_distance),
+                };
+            } else if reflected_distance < second_worst {
+                simplex[last] = (reflected, reflected_distance);
+            } else {
+                let contracted = toward(&worst.0, &centroid, 0.5);
+                let contracted_distance = evaluate(&mut evaluated, &contracted);
+                if contracted_distance < worst.1 {
+                    simplex[last] = (contracted, contracted_distance);
+                } else {
+                    // shrink everything toward the best vertex
+                    let best_vertex = simplex[0].0.clone();
+                    for vertex in simplex.iter_mut().skip(1) {
+                        let shrunk = toward(&best_vertex, &vertex.0, 0.5);
+                        let distance = evaluate(&mut evaluated, &shrunk);
+                        *vertex = (shrunk, distance);
+                    }
+                }
+            }
+        }
+
+        simplex.sort_by(|a, b| a.1.total_cmp(&b.1));
+        let best_rates: Vec<f64> = simplex[0].0.iter().map(|log_rate| log_rate.exp()).collect();
+        let evaluations = evaluated.len();
+        let (distance, report) = evaluated.remove(&key(&simplex[0].0)).unwrap();
+        return FitResult {
+            rates: self.get_reactions().into_iter().zip(best_rates).collect(),
+            distance,
+            evaluations,
+            report,
+        };
+    }
+}
+
+impl Default for Fit {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
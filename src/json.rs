@@ -0,0 +1,302 @@
+use std::fmt::Display;
+
+/// A parsed JSON document. Numbers keep their text so counts beyond the precision of an f64 survive parsing
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Json {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    /// Members in the order they were written
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Parses a complete document, returning a message with the byte offset of the first problem found
+    pub(crate) fn parse(text: &str) -> Result<Json, String> {
+        let mut parser = Parser { bytes: text.as_bytes(), position: 0, depth: 0 };
+        let value = parser.value()?;
+        parser.whitespace();
+        if parser.position != parser.bytes.len() {
+            return Err(parser.error("unexpected trailing characters"));
+        }
+        return Ok(value);
+    }
+
+    /// returns the member with the given key of an object
+    pub(crate) fn get(&self, key: &str) -> Option<&Json> {
+        return match self {
+            Json::Object(members) => members.iter().find(|(name, _)| name == key).map(|(_, value)| value),
+            _ => None,
+        };
+    }
+
+    pub(crate) fn as_f64(&self) -> Option<f64> {
+        return match self {
+            Json::Number(number) => number.parse().ok(),
+            _ => None,
+        };
+    }
+
+    /// returns the number if it is a whole number which fits the type
+    pub(crate) fn as_integer<T: std::str::FromStr>(&self) -> Option<T> {
+        return match self {
+            Json::Number(number) => number.parse().ok(),
+            _ => None,
+        };
+    }
+
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        return match self {
+            Json::String(string) => Some(string),
+            _ => None,
+        };
+    }
+
+    pub(crate) fn as_array(&self) -> Option<&[Json]> {
+        return match self {
+            Json::Array(values) => Some(values),
+            _ => None,
+        };
+    }
+
+    pub(crate) fn as_object(&self) -> Option<&[(String, Json)]> {
+        return match self {
+            Json::Object(members) => Some(members),
+            _ => None,
+        };
+    }
+
+    /// returns a number, written as null if it is not finite since JSON has no NaN or infinity
+    pub(crate) fn from_f64(number: f64) -> Json {
+        return match number.is_finite() {
+            true => Json::Number(number.to_string()),
+            false => Json::Null,
+        };
+    }
+
+    pub(crate) fn from_integer(number: impl Display) -> Json {
+        return Json::Number(number.to_string());
+    }
+}
+
+impl Display for Json {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(value) => write!(f, "{}", value),
+            Json::Number(number) => write!(f, "{}", number),
+            Json::String(string) => write_string(f, string),
+            Json::Array(values) => {
+                write!(f, "[")?;
+                for (index, value) in values.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(members) => {
+                write!(f, "{{")?;
+                for (index, (name, value)) in members.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(f, name)?;
+                    write!(f, ":{}", value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut std::fmt::Formatter<'_>, string: &str) -> std::fmt::Result {
+    write!(f, "\"")?;
+    for character in string.chars() {
+        match character {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            character if (character as u32) < 0x20 => write!(f, "\\u{:04x}", character as u32)?,
+            character => write!(f, "{}", character)?,
+        }
+    }
+    return write!(f, "\"");
+}
+
+/// Deepest nesting of arrays and objects a document may have, so a hostile document can not overflow the stack
+const MAX_DEPTH: usize = 128;
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    /// Arrays and objects the parser is inside of
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> String {
+        return format!("{} at byte {}", message, self.position);
+    }
+
+    fn whitespace(&mut self) {
+        while self.bytes.get(self.position).is_some_and(|byte| byte.is_ascii_whitespace()) {
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, literal: &str) -> Result<(), String> {
+        if !self.bytes[self.position..].starts_with(literal.as_bytes()) {
+            return Err(self.error(&format!("expected {}", literal)));
+        }
+        self.position += literal.len();
+        return Ok(());
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.whitespace();
+        return match self.bytes.get(self.position) {
+            Some(b'n') => self.expect("null").map(|_| Json::Null),
+            Some(b't') => self.expect("true").map(|_| Json::Bool(true)),
+            Some(b'f') => self.expect("false").map(|_| Json::Bool(false)),
+            Some(b'"') => self.string().map(Json::String),
+            Some(b'[' | b'{') if self.depth >= MAX_DEPTH => Err(self.error("nesting too deep")),
+            Some(b'[') => self.nested(Self::array),
+            Some(b'{') => self.nested(Self::object),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(_) => Err(self.error("unexpected character")),
+            None => Err(self.error("unexpected end of input")),
+        };
+    }
+
+    fn nested(&mut self, parse: fn(&mut Self) -> Result<Json, String>) -> Result<Json, String> {
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        return value;
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let start = self.position;
+        while self.bytes.get(self.position).is_some_and(|byte| matches!(byte, b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')) {
+            self.position += 1;
+        }
+        let number = std::str::from_utf8(&self.bytes[start..self.position]).unwrap();
+        if number.parse::<f64>().is_err() {
+            return Err(format!("invalid number {} at byte {}", number, start));
+        }
+        return Ok(Json::Number(number.to_string()));
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect("\"")?;
+        let mut string = Vec::new();
+        loop {
+            match self.bytes.get(self.position) {
+                Some(b'"') => break,
+                Some(b'\\') => {
+                    self.position += 1;
+                    let escaped = match self.bytes.get(self.position) {
+                        Some(b'"') => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'/') => '/',
+                        Some(b'b') => '\u{8}',
+                        Some(b'f') => '\u{c}',
+                        Some(b'n') => '\n',
+                        Some(b'r') => '\r',
+                        Some(b't') => '\t',
+                        Some(b'u') => {
+                            let hex = self.bytes.get(self.position + 1..self.position + 5)
+                                .and_then(|hex| std::str::from_utf8(hex).ok())
+                                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                                .ok_or_else(|| self.error("invalid unicode escape"))?;
+                            self.position += 4;
+                            // surrogate pairs are not combined, unpaired halves become the replacement character
+                            char::from_u32(hex).unwrap_or(char::REPLACEMENT_CHARACTER)
+                        }
+                        _ => return Err(self.error("invalid escape")),
+                    };
+                    string.extend_from_slice(escaped.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+                Some(byte) => string.push(*byte),
+                None => return Err(self.error("unterminated string")),
+            }
+            self.position += 1;
+        }
+        self.position += 1;
+        return String::from_utf8(string).map_err(|_| self.error("invalid utf-8 in string"));
+    }
+
+    fn array(&mut self) -> Result<Json, String> {
+        self.expect("[")?;
+        let mut values = Vec::new();
+        self.whitespace();
+        if self.bytes.get(self.position) == Some(&b']') {
+            self.position += 1;
+            return Ok(Json::Array(values));
+        }
+        loop {
+            values.push(self.value()?);
+            self.whitespace();
+            match self.bytes.get(self.position) {
+                Some(b',') => self.position += 1,
+                Some(b']') => {
+                    self.position += 1;
+                    return Ok(Json::Array(values));
+                }
+                _ => return Err(self.error("expected , or ]")),
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Json, String> {
+        self.expect("{")?;
+        let mut members = Vec::new();
+        self.whitespace();
+        if self.bytes.get(self.position) == Some(&b'}') {
+            self.position += 1;
+            return Ok(Json::Object(members));
+        }
+        loop {
+            self.whitespace();
+            let name = self.string()?;
+            self.whitespace();
+            self.expect(":")?;
+            members.push((name, self.value()?));
+            self.whitespace();
+            match self.bytes.get(self.position) {
+                Some(b',') => self.position += 1,
+                Some(b'}') => {
+                    self.position += 1;
+                    return Ok(Json::Object(members));
+                }
+                _ => return Err(self.error("expected , or }")),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let text = r#"{"reactions":[{"reaction":"a + b => c","rate":1.5}],"solution":{"a":18446744073709551615},"name":"q\"\n","seed":null}"#;
+        let json = Json::parse(text).unwrap();
+        assert_eq!(json.to_string(), text);
+        assert_eq!(json.get("solution").and_then(|solution| solution.get("a")).and_then(Json::as_integer), Some(u64::MAX));
+        assert_eq!(Json::parse(" [1, 2 ").unwrap_err(), "expected , or ] at byte 7");
+    }
+
+    #[test]
+    fn test_nesting_limit() {
+        assert!(Json::parse(&format!("{}{}", "[".repeat(MAX_DEPTH), "]".repeat(MAX_DEPTH))).is_ok());
+        assert_eq!(Json::parse(&"[".repeat(1_000_000)).unwrap_err(), "nesting too deep at byte 128");
+    }
+}
@@ -1,46 +1,53 @@
-/// Author: Marceline Sorensen 
-/// Email: nadaso8th@gmail.com 
-/// Date: 08/03/2023
-/// 
-/// # Description
-/// This is the main simulation engine of MARlea it takes a set of reactions as well as a set of initial species valeus. 
-/// It uses these to simulate the average stable case of a chemical reaction network stochastically. 
-/// 
-/// # Arguments (input_path, init_path)
-/// - <input_path>
-///     Specifies an input file location 
-/// - <init_path> 
-///     Specifies a file location to read initial species values from
-///     Is of type Option which may be None
-///     - If none will simply initialize all values to 0
-/// - <out_path>
-///     Specifies an output file location 
-///     Is of type Option which may be None
-/// - <num_trials>
-///     Specifies the number of trials to be used in making a predicted average
-///     Is of type Option which may be None
-///     - if None will defualt to 100 trials to provide a simplistic estimation
-/// - <max_runtime>
-///     Specifies the maximum time the code my run for in seconds
-///     Is of type Option which may be None 
-///     - if None the simulation will run indefinitely
-/// 
-/// Accepted file types: 
-///     - CSV
-///     - XML UNIMPLEMENTED! 
-///     - JSON UNIMPLEMENTED!
-
-use std::collections::{HashMap, HashSet};
-
-use std::sync::mpsc::{
-    sync_channel,
-    SyncSender, Receiver,
+//! Author: Marceline Sorensen 
+//! Email: nadaso8th@gmail.com 
+//! Date: 08/03/2023
+//! 
+//! # Description
+//! This is the main simulation engine of MARlea it takes a set of reactions as well as a set of initial species valeus. 
+//! It uses these to simulate the average stable case of a chemical reaction network stochastically. 
+//! 
+//! # Arguments (input_path, init_path)
+//! - <input_path>
+//!   Specifies an input file location 
+//! - <init_path> 
+//!   Specifies a file location to read initial species values from
+//!   Is of type Option which may be None
+//!   - If none will simply initialize all values to 0
+//! - <out_path>
+//!   Specifies an output file location 
+//!   Is of type Option which may be None
+//! - <num_trials>
+//!   Specifies the number of trials to be used in making a predicted average
+//!   Is of type Option which may be None
+//!   - if None will defualt to 100 trials to provide a simplistic estimation
+//! - <max_runtime>
+//!   Specifies the maximum time the code my run for in seconds
+//!   Is of type Option which may be None 
+//!   - if None the simulation will run indefinitely
+//! 
+//! Accepted file types: 
+//!     - CSV
+//!     - XML UNIMPLEMENTED! 
+//!     - JSON UNIMPLEMENTED!
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use std::time::{Duration, Instant};
+use std::sync::{
+    Arc, Condvar, Mutex, OnceLock,
+    atomic::{AtomicBool, Ordering},
+    mpsc::{channel, sync_channel, SyncSender, Receiver, RecvTimeoutError},
 };
-use std::usize;
 use supported_file_type::SupportedFileType; 
 use threadpool::ThreadPool;
 use trial::{
-    results::TrialResult, 
+    observer::StepObserver,
+    passage::PassageTarget,
+    schedule::ScheduledEvent,
+    random::{self, RngBackend, RngStream},
+    results::{Termination, TrialResult, TrialSummary}, 
+    stability::{Cycle, DetectorFactory},
+    strata::{self, Stratum},
     reaction_network::{
         ReactionNetwork, 
         reaction::{
@@ -53,12 +60,52 @@ use trial::{
     }
 };
 
+use self::report::ResultSink;
 use self::supported_file_type::TimelineWriter; 
 
 
 pub mod trial;
+mod builder;
+mod checkpoint;
+mod diagnostics;
+mod error;
+mod experiment;
+mod fit;
+#[cfg(feature = "server")]
+mod json;
+mod log;
+#[cfg(feature = "plot")]
+mod plot;
+mod replay;
+mod report;
+mod sensitivity;
+#[cfg(feature = "server")]
+mod server;
+mod statistics;
 mod supported_file_type; 
-//mod tests;
+mod sweep;
+mod timeline;
+#[cfg(feature = "server")]
+mod websocket;
+
+pub use builder::Builder;
+pub use checkpoint::Checkpoint;
+pub use diagnostics::{BlockedReaction, Lint};
+pub use error::{BuildError, MarleaEngineError, MergeError};
+pub use fit::{Fit, FitResult};
+pub use log::{set_logger, Level};
+#[cfg(feature = "plot")]
+pub use plot::plot_timeline;
+pub use replay::Replay;
+pub use report::{Alarm, Backpressure, EngineConfig, FinalReport, MarleaResult, Progress, ResponseMode, RunStatus, SpeciesOrder, SpeciesPattern};
+pub use sensitivity::{Sensitivity, SobolIndices};
+#[cfg(feature = "server")]
+pub use server::Server;
+pub use sweep::{Parameter, ParameterValue, Sweep, SweepPoint};
+pub use timeline::{Marker, Point};
+pub use statistics::{compare, Binning, BootstrapEstimate, CorrelationMatrix, Difference, FirstPassage, Histogram, OrderStatistics, SpeciesStatistics};
+#[cfg(test)]
+mod tests;
 
 pub struct MarleaEngine {
     // set externally
@@ -67,33 +114,112 @@ pub struct MarleaEngine {
     num_trials: Option<usize>,
     max_runtime: Option<u64>,
     max_semi_stable_steps: Option<i32>,
+    rng_backend: RngBackend,
+    antithetic: bool,
+    strata: Vec<Stratum>,
+    memory_limit: Option<usize>,
+    seed: Option<u64>,
+    on_progress: Option<Box<dyn Fn(Progress) + Send + Sync>>,
+    on_alarm: Option<Box<dyn Fn(Alarm) + Send + Sync>>,
+    result_sinks: Vec<ResultSink>,
+    /// Finished trials and time which pass between two running averages sent to the result sinks
+    report_every: usize,
+    report_interval: Duration,
+    /// Kept for the channel `run_async` creates
+    #[cfg(feature = "tokio")]
+    backpressure: Backpressure,
+    #[cfg(feature = "tokio")]
+    result_capacity: usize,
+    observer: Option<Arc<dyn StepObserver>>,
+    histogram_binning: Option<Binning>,
+    percentiles: Option<Vec<f64>>,
+    target_precision: Option<(String, f64)>,
+    trajectory_interval: Option<i32>,
+    recorded_trajectories: Option<(i32, Option<Vec<usize>>)>,
+    passage_targets: Vec<PassageTarget>,
+    correlated_species: Option<Vec<String>>,
+    bootstrap_resamples: Option<usize>,
+    tracked_species: Option<Vec<String>>,
+    included_species: Vec<SpeciesPattern>,
+    excluded_species: Vec<SpeciesPattern>,
+    species_order: SpeciesOrder,
+    response: ResponseMode,
+    watched_trial: Option<usize>,
+    stop_targets: Vec<PassageTarget>,
+    events: Vec<ScheduledEvent>,
+    alarms: Vec<PassageTarget>,
+    max_steps: Option<i32>,
+    trial_time_limit: Option<Duration>,
+    stability_detector: Option<DetectorFactory>,
+    stall_diagnostics: bool,
+    sparse_solutions: bool,
+    cache_dir: Option<String>,
 
     // constructed by struct
-    computation_threads: ThreadPool,
+    // started by the first run so engines which are only stepped through never spawn a thread
+    computation_threads: OnceLock<ThreadPool>,
     computations_threads_sender: SyncSender<TrialResult>,
-    computation_threads_reciever: Receiver<TrialResult>,
-    prime_network: ReactionNetwork
+    computation_threads_reciever: Mutex<Receiver<TrialResult>>,
+    prime_network: ReactionNetwork,
+    interrupt: Arc<AtomicBool>,
+    pause_state: Mutex<PauseState>,
+    pause_signal: Condvar,
+    /// Where the run `run_for` works through stands between calls
+    stepped_run: Mutex<Option<Checkpoint>>,
+    /// Trial counts the strata were allocated over in turn, the total of the run followed by every extension. 
+    /// Empty unless the last run was extended with `extend_trials`
+    allocation_blocks: Mutex<Vec<usize>>,
+    live_status: Mutex<Option<LiveStatus>>,
 }
 
-impl MarleaEngine {
-    pub fn custom_block ( custom_network: ReactionNetwork ) -> Self {
+/// The latest progress the run loop published for `MarleaEngine::progress`, kept after a run until the next one starts
+struct LiveStatus {
+    progress: Progress,
+    started: Instant,
+    running: bool,
+}
 
-        let computation_threads = threadpool::Builder::new()
-            .thread_name("compute_thread".into())
-            .build();
-        let computation_threads_channels = sync_channel(0);
+/// Coordinates `pause` with a run in progress on another thread
+#[derive(Default)]
+struct PauseState {
+    running: bool,
+    requested: bool,
+    checkpoint: Option<Checkpoint>,
+}
 
-        Self { 
-            out_path: None,
-            out_timeline: None, 
-            num_trials: None, 
-            max_runtime: None, 
-            max_semi_stable_steps: None, 
-            computation_threads: computation_threads, 
-            computations_threads_sender: computation_threads_channels.0, 
-            computation_threads_reciever: computation_threads_channels.1, 
-            prime_network: custom_network
-        }
+/// Steps taken and current counts of the watched trial, see `Builder::watch`
+#[derive(Default)]
+struct WatchedTrial {
+    steps: i32,
+    counts: BTreeMap<Species, Species>,
+}
+
+/// Everything recorded about the trials of a single run, indexed by trial id
+struct TrialRecords {
+    completed_trials: BTreeMap<usize, TrialSummary>,
+    trial_seeds: Vec<u64>,
+    trial_strata: Vec<usize>,
+    /// Sampled solutions of each trial which recorded a trajectory keyed by trial id
+    trajectories: BTreeMap<usize, Vec<Solution>>,
+    /// First passage step of each target keyed by trial id
+    passage_steps: BTreeMap<usize, Vec<Option<i32>>>,
+    /// Limit cycle of each trial found to oscillate keyed by trial id
+    cycles: BTreeMap<usize, Cycle>,
+    /// Alarms in the order trials raised them
+    alarms: Vec<Alarm>,
+    /// Every reaction of the network the trials ran on
+    reactions: Vec<Reaction>,
+    network_fingerprint: u64,
+    wall_time: Duration,
+    /// Whether the runtime limit ran out before every trial finished
+    partial: bool,
+    /// Whether the run needs no further trials, false if it was paused or only ran a batch
+    finished: bool,
+}
+
+impl MarleaEngine {
+    pub fn custom_block ( custom_network: ReactionNetwork ) -> Self {
+        return Builder::new(custom_network).build();
     }
 
     pub fn new(
@@ -106,128 +232,911 @@ impl MarleaEngine {
         max_semi_stable_steps: Option<i32>,
     ) -> Self { 
 
-        let reactions = SupportedFileType::from(input_path).parse_reactions();
-        let solution = Self::solution_from(init_path, &reactions);
-        let prime_network = ReactionNetwork::new(reactions, solution);
-        let computation_threads = threadpool::Builder::new()
-            .thread_name("compute_thread".into())
-            .build();
-        let computation_threads_channels = sync_channel(0);
-
-        Self{
-            out_path,
-            out_timeline,
-            num_trials,
-            max_runtime,
-            max_semi_stable_steps,
-            computation_threads,
-            computations_threads_sender: computation_threads_channels.0,
-            computation_threads_reciever: computation_threads_channels.1,
-            prime_network,
-        } 
+        let mut builder = Builder::from_files(input_path, init_path);
+        if let Some(path) = out_path {builder = builder.out_path(path)}
+        if let Some(path) = out_timeline {builder = builder.out_timeline(path)}
+        if let Some(number) = num_trials {builder = builder.trials(number)}
+        if let Some(time) = max_runtime {builder = builder.runtime(time)}
+        if let Some(steps) = max_semi_stable_steps {builder = builder.max_semi_stable_steps(steps)}
+
+        return builder.build();
+    }
+
+    pub fn run(&self) -> FinalReport {
+        return self.try_run().unwrap_or_else(|error| panic!("{}", error));
+    }
+
+    /// Like `run` but returns an error instead of panicking when a trial fails or the engine can not run at all
+    pub fn try_run(&self) -> Result<FinalReport, MarleaEngineError> {
+        self.allocation_blocks.lock().unwrap().clear();
+        let cache_path = self.cache_path();
+        // a cache entry which can not be read is run again and overwritten
+        let cached = cache_path.as_ref()
+            .filter(|path| std::path::Path::new(path).exists())
+            .and_then(|path| Checkpoint::try_read(path)
+                .inspect_err(|error| log::log(Level::Warn, format_args!("ignoring the cached trials: {}", error)))
+                .ok())
+            .filter(|checkpoint| checkpoint.network_fingerprint == self.prime_network.fingerprint());
+        let cache_hit = cached.is_some();
+        if let (Some(path), true) = (&cache_path, cache_hit) {
+            log::log(Level::Info, format_args!("reusing the trials cached in {}", path));
+        }
+        let records = self.try_run_trials(&self.prime_network, cached, self.max_trials(), None)?;
+
+        if let (Some(path), false) = (&cache_path, cache_hit) {
+            if records.finished && !records.partial {
+                let checkpoint = Checkpoint {
+                    network_fingerprint: self.prime_network.fingerprint(),
+                    trial_seeds: records.trial_seeds.clone(),
+                    completed_trials: records.completed_trials.values().cloned().collect(),
+                };
+                // the run is done either way, a failed cache write only costs the next run its shortcut
+                let written = match &self.cache_dir {
+                    Some(cache_dir) => std::fs::create_dir_all(cache_dir)
+                        .map_err(|error| format!("failed to create cache directory {}: {}", cache_dir, error)),
+                    None => Ok(()),
+                }.and_then(|_| checkpoint.try_write(path));
+                if let Err(error) = written {
+                    log::log(Level::Warn, format_args!("the trials were not cached: {}", error));
+                }
+            }
+        }
+        return Ok(self.terminate(records));
+    }
+
+    /// Returns a hash of the network, its initial solution and the configuration of the engine which is stable across runs and builds, 
+    /// two engines with the same fingerprint and a master seed return the same report
+    pub fn fingerprint(&self) -> u64 {
+        return random::split_mix(self.prime_network.fingerprint() ^ self.get_config().fingerprint());
+    }
+
+    /// returns the file the trials of a run are cached in, or None if the run can not be rebuilt from its trials, see `Builder::cache_dir`
+    fn cache_path(&self) -> Option<String> {
+        let cache_dir = self.cache_dir.as_ref()?;
+        let cacheable = self.seed.is_some()
+            && self.max_runtime.is_none()
+            && self.trial_time_limit.is_none()
+            && self.target_precision.is_none()
+            && self.trajectory_interval.is_none()
+            && self.recorded_trajectories.is_none()
+            && self.passage_targets.is_empty()
+            && self.alarms.is_empty()
+            && self.stability_detector.is_none()
+            && self.out_timeline.is_none();
+        if !cacheable {
+            log::log(Level::Debug, format_args!("run is not cached since it has no master seed or records per step outputs"));
+            return None;
+        }
+        return Some(format!("{}/{:016x}.csv", cache_dir.trim_end_matches('/'), self.fingerprint()));
+    }
+
+    /// Runs the engine on tokio's blocking thread pool and returns a receiver yielding intermediary results as trials finish,
+    /// followed by the final report, or by `MarleaResult::Failed` if the run failed. Must be called from within a tokio runtime.
+    #[cfg(feature = "tokio")]
+    pub fn run_async(mut self) -> tokio::sync::mpsc::Receiver<MarleaResult> {
+        let (result_sender, result_reciever) = tokio::sync::mpsc::channel(self.result_capacity);
+        let failure_sender = result_sender.clone();
+        self.result_sinks.push(ResultSink::new(report::ResultChannel::Tokio(result_sender), self.backpressure));
+        tokio::task::spawn_blocking(move || {
+            if let Err(error) = self.try_run() {
+                // the receiver may have been dropped already, then there is no one left to tell
+                let _ = failure_sender.blocking_send(MarleaResult::Failed(error));
+            }
+        });
+        return result_reciever;
+    }
+
+    /// Continues a run from a checkpoint returned by `pause`. 
+    /// Completed trials are kept, trials which were in flight are restarted from their seeds and the remaining trials are run as usual.
+    pub fn resume(&self, checkpoint: Checkpoint) -> FinalReport {
+        return self.try_resume(checkpoint).unwrap_or_else(|error| panic!("{}", error));
+    }
+
+    /// Like `resume` but returns an error instead of panicking, including when the checkpoint is of a different network
+    pub fn try_resume(&self, checkpoint: Checkpoint) -> Result<FinalReport, MarleaEngineError> {
+        if checkpoint.network_fingerprint != self.prime_network.fingerprint() {
+            return Err(MarleaEngineError::CheckpointMismatch);
+        }
+        let records = self.try_run_trials(&self.prime_network, Some(checkpoint), self.max_trials(), None)?;
+        return Ok(self.terminate(records));
+    }
+
+    /// Runs `trials` more trials on top of a report this engine returned and returns the report over all of them, 
+    /// the same report a run of that many trials would have returned given a master seed and no strata. 
+    /// With strata the trials already run keep their stratum and the new trials are split between the strata on their own. 
+    /// Like `resume` only the trial summaries carry over, so trajectories, first passages, cycles and alarms only cover the new trials.
+    pub fn extend_trials(&self, report: &FinalReport, trials: usize) -> FinalReport {
+        return self.try_extend_trials(report, trials).unwrap_or_else(|error| panic!("{}", error));
+    }
+
+    /// Like `extend_trials` but returns an error instead of panicking
+    pub fn try_extend_trials(&self, report: &FinalReport, trials: usize) -> Result<FinalReport, MarleaEngineError> {
+        // trials missing from a partial report are run again from their seed
+        let completed: BTreeMap<usize, &TrialSummary> = report.trials.iter().map(|summary| (summary.id, summary)).collect();
+        let mut trial_seeds = Vec::new();
+        for id in 0..completed.keys().next_back().map_or(0, |id| id + 1) {
+            match completed.get(&id) {
+                Some(summary) => trial_seeds.push(summary.seed),
+                None => {self.trial_seed(id, &mut trial_seeds);}
+            }
+        }
+
+        let mut max_trials = trial_seeds.len() + trials;
+        if self.antithetic && max_trials % 2 == 1 {
+            max_trials += 1;
+        }
+        // the trials already run keep the strata they were sampled from, only the new ones are allocated
+        {
+            let mut blocks = self.allocation_blocks.lock().unwrap();
+            if blocks.is_empty() {
+                blocks.push(self.max_trials());
+            }
+            let allocated: usize = blocks.iter().sum();
+            if max_trials > allocated {
+                blocks.push(max_trials - allocated);
+            }
+        }
+        let checkpoint = Checkpoint { network_fingerprint: self.prime_network.fingerprint(), trial_seeds, completed_trials: report.trials.clone() };
+        let records = self.try_run_trials(&self.prime_network, Some(checkpoint), max_trials, None)?;
+        return Ok(self.terminate(records));
+    }
+
+    /// Runs at most the given number of further trials and returns, so the caller can do other work such as serving a UI 
+    /// or taking a checkpoint in between. Each call continues the run the previous one left off, the call finishing the last trial 
+    /// returns the final report and the next call starts a new run. Trials hold no state between calls, so only their summaries 
+    /// are kept, like with `resume`, and the wall time of the report only covers the final call.
+    pub fn run_for(&self, trials: usize) -> RunStatus {
+        return self.try_run_for(trials).unwrap_or_else(|error| panic!("{}", error));
+    }
+
+    /// Like `run_for` but returns an error instead of panicking, a failed call drops the run so the next call starts over
+    pub fn try_run_for(&self, trials: usize) -> Result<RunStatus, MarleaEngineError> {
+        let resume_from = self.stepped_run.lock().unwrap().take();
+        if resume_from.is_none() {
+            self.allocation_blocks.lock().unwrap().clear();
+        }
+        let records = self.try_run_trials(&self.prime_network, resume_from, self.max_trials(), Some(trials))?;
+        if records.finished {
+            return Ok(RunStatus::Done(Box::new(self.terminate(records))));
+        }
+        let status = RunStatus::Running { trials_completed: records.completed_trials.len(), trials_total: self.max_trials() };
+        *self.stepped_run.lock().unwrap() = Some(Checkpoint {
+            network_fingerprint: self.prime_network.fingerprint(),
+            trial_seeds: records.trial_seeds,
+            completed_trials: records.completed_trials.into_values().collect(),
+        });
+        return Ok(status);
+    }
+
+    /// Checks the network for likely mistakes without running a single trial, such as species which are never produced 
+    /// or reactions which can never fire. None of them stop a run from going ahead.
+    pub fn dry_run(&self) -> Vec<Lint> {
+        return diagnostics::lint(&self.prime_network);
+    }
+
+    /// returns how far the run in progress, or else the last run, has come. 
+    /// Meant to be polled from another thread while `run` blocks, None before the first run starts
+    pub fn progress(&self) -> Option<Progress> {
+        return self.live_status.lock().unwrap().as_ref().map(|status| Progress {
+            elapsed: match status.running {
+                true => status.started.elapsed(),
+                false => status.progress.elapsed,
+            },
+            ..status.progress
+        });
+    }
+
+    /// returns the number of trials completed in the current or last run, including those resumed from a checkpoint
+    pub fn trials_completed(&self) -> usize {
+        return self.progress().map_or(0, |progress| progress.trials_completed);
+    }
+
+    /// returns the number of trials started but not finished yet, 0 without a run in progress
+    pub fn trials_in_progress(&self) -> usize {
+        return self.progress().map_or(0, |progress| progress.trials_in_flight);
     }
 
-    pub fn run(&self) -> Vec<(String, f64)> {
-        // set containing all trial results
-        let mut simulation_results = HashSet::new();
+    /// returns the steps taken by all completed trials of the current or last run combined
+    pub fn total_steps(&self) -> u64 {
+        return self.progress().map_or(0, |progress| progress.total_steps);
+    }
+
+    /// returns the time the current run has taken so far, or the last run took
+    pub fn elapsed(&self) -> Duration {
+        return self.progress().map_or(Duration::ZERO, |progress| progress.elapsed);
+    }
+
+    fn publish_status(&self, progress: Progress, started: Instant, running: bool) {
+        *self.live_status.lock().unwrap() = Some(LiveStatus { progress, started, running });
+    }
+
+    /// Stops a run in progress on another thread and returns a checkpoint it can be resumed from. 
+    /// The interrupted `run` call returns a report over the trials completed so far. 
+    /// Returns None if no run is in progress.
+    pub fn pause(&self) -> Option<Checkpoint> {
+        let mut pause_state = self.pause_state.lock().unwrap();
+        if !pause_state.running {
+            return None;
+        }
+
+        pause_state.requested = true;
+        self.interrupt.store(true, Ordering::Relaxed);
+        while pause_state.checkpoint.is_none() {
+            pause_state = self.pause_signal.wait(pause_state).unwrap();
+        }
+        return pause_state.checkpoint.take();
+    }
+
+    /// Runs all trials of the given network and returns everything recorded about them, without averaging or writing any output. 
+    /// Panics where `try_run_trials` returns an error
+    fn run_trials(&self, network: &ReactionNetwork, resume_from: Option<Checkpoint>) -> TrialRecords {
+        return self.try_run_trials(network, resume_from, self.max_trials(), None).unwrap_or_else(|error| panic!("{}", error));
+    }
+
+    /// Runs the trials of a run of `max_trials` trials which are not completed yet, or only the given number of them
+    fn try_run_trials(&self, network: &ReactionNetwork, resume_from: Option<Checkpoint>, max_trials: usize, batch: Option<usize>) -> Result<TrialRecords, MarleaEngineError> {
+        // only keep as many trials alive at once as the memory limit allows
+        let max_in_flight = self.max_trials_in_flight()?;
+
+        {
+            let mut pause_state = self.pause_state.lock().unwrap();
+            pause_state.running = true;
+            self.interrupt.store(false, Ordering::Relaxed);
+        }
+
+        // map containing summaries of all finished trials keyed by trial id, 
+        // ordered so that everything derived from it is independent of the order trials finished in
+        let mut completed_trials = BTreeMap::new();
+        let mut trial_seeds = Vec::new();
+        if let Some(checkpoint) = resume_from {
+            trial_seeds = checkpoint.trial_seeds;
+            for summary in checkpoint.completed_trials {
+                completed_trials.insert(summary.id, summary);
+            }
+        }
 
         // setup loop variables
-        let mut trials_recieved = 0;
-        let mut trials_created = 0;
-        let max_trials = match self.num_trials{Some(number) => number, None => 100};        
+        let start_time = Instant::now();
+        let mut total_steps = completed_trials.values().map(|summary| summary.steps as u64).sum();
+        let mut total_reactions_fired = completed_trials.values().map(|summary| summary.reactions_fired).sum();
+        let trials_resumed = completed_trials.len();
+        let mut running_sums = HashMap::<String, u128>::new();
+        let mut averaged_trials = 0;
+        // trials finished and averaged trials when averages were last sent, see `Builder::report_every`
+        let (mut unreported_trials, mut reported_averages, mut last_report) = (0, 0, start_time);
+        for summary in completed_trials.values().filter(|summary| summary.termination != Termination::TimedOut) {
+            statistics::add_to_sums(&mut running_sums, &summary.solution);
+            averaged_trials += 1;
+        }
+        let mut trajectories = BTreeMap::new();
+        let mut passage_steps = BTreeMap::new();
+        let mut cycles = BTreeMap::new();
+        let mut alarms = Vec::new();
+        // names of reactions keyed by fingerprint, to label the firing counts trials send
+        let reaction_names: HashMap<u64, String> = network.get_reactions().iter()
+            .map(|reaction| (reaction.get_fingerprint(), reaction.to_string()))
+            .collect();
+        let by_name = |firings: HashMap<u64, u64>| -> Vec<(String, u64)> {
+            let mut firings: Vec<(String, u64)> = firings.into_iter()
+                .map(|(fingerprint, count)| (reaction_names[&fingerprint].clone(), count))
+                .collect();
+            firings.sort();
+            return firings;
+        };
+        let mut next_id = 0;
+        let mut trials_in_flight = 0;
+        let mut watched_trial = WatchedTrial::default();
+        let batch_end = batch.map_or(max_trials, |batch| max_trials.min(completed_trials.len() + batch));
+        let snapshot = |trials_completed: usize, trials_in_flight: usize, total_steps: u64, reactions_fired: u64| Progress {
+            trials_completed,
+            trials_in_flight,
+            trials_total: max_trials,
+            trials_resumed,
+            total_steps,
+            reactions_fired,
+            elapsed: start_time.elapsed(),
+        };
+        self.publish_status(snapshot(completed_trials.len(), 0, total_steps, total_reactions_fired), start_time, true);
 
         // setup timeline writer if one is needed
         let (timeline_writer_sender, timeline_writer_reciever) = sync_channel(0);
-        if let Some(path) = &self.out_timeline {
+        // the writer gets a thread of its own so it can not take the last worker of the pool away from the trials
+        let timeline_writer = self.out_timeline.as_ref().map(|path| {
             let timeline_writer = TimelineWriter::new(SupportedFileType::from(path.clone()), timeline_writer_reciever);
-            self.computation_threads.execute(move|| timeline_writer.begin_listen());
-        }
+            std::thread::spawn(move|| timeline_writer.begin_listen())
+        });
   
-        // start runtime timer
-        let (timer_sender, timer_reciever) = sync_channel(0);
+        // the run is cut short once the elapsed time reaches the runtime limit. A watchdog stops the trials rather than this loop 
+        // so the limit holds while the loop is held up, such as by a blocking result channel or a slow callback
+        let deadline_passed = Arc::new(AtomicBool::new(false));
+        let (run_over, run_over_reciever) = channel::<()>();
         if let Some(time) = self.max_runtime {
-            self.computation_threads.execute(move|| Self::engine_runtime_timer(time, timer_sender));
+            let (interrupt, deadline_passed) = (self.interrupt.clone(), deadline_passed.clone());
+            std::thread::spawn(move|| {
+                // returns early without interrupting anything once the run is over and the sender dropped
+                if run_over_reciever.recv_timeout(Duration::from_secs(time)) == Err(RecvTimeoutError::Timeout) {
+                    deadline_passed.store(true, Ordering::Relaxed);
+                    interrupt.store(true, Ordering::Relaxed);
+                }
+            });
         }
+        let mut failed = None;
 
-        // create trials 
-        match &self.out_timeline {
-            Some(_) => {
-                while trials_created < max_trials {
-                    let mut current_trial = trial::Trial::from(self.prime_network.clone(), self.max_semi_stable_steps, trials_created);
-                    let trial_sender = self.computations_threads_sender.clone();
-                    self.computation_threads.execute(move|| current_trial.simulate_with_timeline(trial_sender));
-                    trials_created += 1;
+        // assign each trial to a stratum, keeping antithetic pairs together
+        let trial_strata = self.assign_strata(&self.allocation(max_trials));
+
+        // with a precision target the run ends early once enough trials are done, possibly already on resume
+        let mut precision_tracker = self.target_precision.as_ref()
+            .map(|(species, relative_ci)| statistics::PrecisionTracker::new(species.clone(), *relative_ci, self.antithetic));
+        let mut trials_needed = precision_tracker.as_mut().and_then(|tracker| tracker.advance(&completed_trials));
+
+        let computation_threads = self.computation_threads.get_or_init(|| threadpool::Builder::new().thread_name("compute_thread".into()).build());
+        let computation_threads_reciever = self.computation_threads_reciever.lock().unwrap();
+        while completed_trials.len() < batch_end && trials_needed.is_none() {
+
+            // create trials, skipping any already completed before a resume
+            while next_id < max_trials && trials_in_flight < max_in_flight && completed_trials.len() + trials_in_flight < batch_end {
+                if completed_trials.contains_key(&next_id) {
+                    next_id += 1;
+                    continue;
                 }
-            }
-            None => {
-                while trials_created < max_trials {
-                    let mut current_trial = trial::Trial::from(self.prime_network.clone(), self.max_semi_stable_steps, trials_created);
-                    let trial_sender = self.computations_threads_sender.clone();
-                    self.computation_threads.execute(move || current_trial.simulate(trial_sender));                    
-                    trials_created += 1;
+
+                let seed = self.trial_seed(next_id, &mut trial_seeds);
+                let stratum = trial_strata.get(next_id).map(|index| &self.strata[*index]);
+                let mut current_trial = self.create_trial(network, next_id, seed, stratum);
+                let trial_sender = self.computations_threads_sender.clone();
+                let interrupt = self.interrupt.clone();
+                let watched = self.watched_trial() == Some(next_id);
+                if watched {
+                    watched_trial.counts = current_trial.get_solution().iter().map(|(name, count)| (name.clone(), count.clone())).collect();
+                    self.stream_watched(watched_trial.counts.clone().into_iter().collect(), 0);
+                }
+                match self.out_timeline.is_some() || watched {
+                    true => computation_threads.execute(move|| current_trial.simulate_with_timeline(trial_sender, interrupt)),
+                    false => computation_threads.execute(move|| current_trial.simulate(trial_sender, interrupt)),
                 }
+                next_id += 1;
+                trials_in_flight += 1;
+                self.publish_status(snapshot(completed_trials.len(), trials_in_flight, total_steps, total_reactions_fired), start_time, true);
             }
-        }
 
-        // poll for trial results
-        while trials_recieved < max_trials {
-            if let Ok(result) = self.computation_threads_reciever.try_recv() {
+            // poll for trial results
+            if let Ok(result) = computation_threads_reciever.try_recv() {
                 match result {
-                    TrialResult::StableSolution(solution, steps) => {
-                        trials_recieved += 1;
-                        println!("Trial stable after {} steps", steps);
-                        println!("Recieved {} trials", trials_recieved);
-                        simulation_results.insert(solution);
+                    TrialResult::StableSolution(solution, steps, reactions_fired, firings, termination, id) => {
+                        trials_in_flight -= 1;
+                        total_steps += steps as u64;
+                        total_reactions_fired += reactions_fired;
+                        log::log(Level::Debug, format_args!("trial {} finished after {} steps and {} reactions, {:?}", id, steps, reactions_fired, termination));
+                        let summary = TrialSummary { id, seed: trial_seeds[id], steps, reactions_fired, 
+                            reaction_firings: by_name(firings.counts), tail_firings: by_name(firings.tail), tail_steps: firings.tail_steps, termination, solution };
+                        unreported_trials += 1;
+                        let report_due = unreported_trials >= self.report_every && last_report.elapsed() >= self.report_interval;
+                        if report_due {
+                            unreported_trials = 0;
+                            last_report = Instant::now();
+                        }
+                        if self.streams_trials() {
+                            self.send_result(MarleaResult::Trial(Box::new(summary.clone())));
+                            if termination != Termination::TimedOut {
+                                statistics::add_to_sums(&mut running_sums, &summary.solution);
+                                averaged_trials += 1;
+                            }
+                            // averages are only worked out when they are sent and only if a trial was added since
+                            if report_due && averaged_trials > reported_averages {
+                                reported_averages = averaged_trials;
+                                let mut averages: Vec<(String, f64)> = statistics::sums_to_averages(&running_sums, averaged_trials).into_iter()
+                                    .filter(|(name, _)| self.averages_species(name))
+                                    .collect();
+                                self.species_order.sort(&mut averages);
+                                self.send_result(MarleaResult::Intermediary(averages));
+                            }
+                        }
+                        completed_trials.insert(id, summary);
+                        if let Some(tracker) = &mut precision_tracker {
+                            trials_needed = tracker.advance(&completed_trials);
+                            if trials_needed.is_some() {
+                                // stop the trials still running, their results are not needed
+                                self.interrupt.store(true, Ordering::Relaxed);
+                            }
+                        }
+                        let progress = snapshot(completed_trials.len(), trials_in_flight, total_steps, total_reactions_fired);
+                        self.publish_status(progress, start_time, true);
+                        if let Some(on_progress) = &self.on_progress {
+                            on_progress(progress);
+                        }
+                        if report_due && self.streams_trials() {
+                            self.send_result(MarleaResult::Progress(progress));
+                        }
+                    }
+                    TrialResult::Trajectory(samples, id) => {
+                        trajectories.insert(id, samples);
+                    }
+                    TrialResult::FirstPassage(steps, id) => {
+                        passage_steps.insert(id, steps);
+                    }
+                    TrialResult::Cycle(cycle, id) => {
+                        cycles.insert(id, cycle);
                     }
-                    TrialResult::TimelineEntry(solution, id) => {
-                        timeline_writer_sender.send((solution, id)).unwrap();
+                    TrialResult::Alarm(index, step, id) => {
+                        alarms.push(self.raise_alarm(index, step, id));
+                    }
+                    TrialResult::Failed(failure, id) => {
+                        // the run can not be reported without the trial, stop the others and wait for them below
+                        trials_in_flight -= 1;
+                        failed.get_or_insert(MarleaEngineError::TrialFailed { trial: id, failure });
+                        self.interrupt.store(true, Ordering::Relaxed);
+                    }
+                    TrialResult::Interrupted(_) => {
+                        trials_in_flight -= 1;
+                    }
+                    timeline_entry => {
+                        self.forward_timeline_entry(timeline_entry, &mut watched_trial, &timeline_writer_sender);
                     }
                 }
             }
-            
-            if let Ok(_) = timer_reciever.try_recv() {
-                println!("forced termination because max time was reached\n\nWARNING: returned results may not be accurate and should be used for debugging purposes only");
+
+            if self.interrupt.load(Ordering::Relaxed) {
                 break;
             }
         }
 
+        // when interrupted wait for every trial still in flight to stop, trials finishing in the meantime are kept
+        if self.interrupt.load(Ordering::Relaxed) {
+            while trials_in_flight > 0 {
+                match computation_threads_reciever.recv() {
+                    Ok(TrialResult::StableSolution(solution, steps, reactions_fired, firings, termination, id)) => {
+                        trials_in_flight -= 1;
+                        completed_trials.insert(id, TrialSummary { id, seed: trial_seeds[id], steps, reactions_fired, 
+                            reaction_firings: by_name(firings.counts), tail_firings: by_name(firings.tail), tail_steps: firings.tail_steps, termination, solution });
+                    }
+                    Ok(TrialResult::Trajectory(samples, id)) => {trajectories.insert(id, samples);}
+                    Ok(TrialResult::FirstPassage(steps, id)) => {passage_steps.insert(id, steps);}
+                    Ok(TrialResult::Cycle(cycle, id)) => {cycles.insert(id, cycle);}
+                    Ok(TrialResult::Alarm(index, step, id)) => alarms.push(self.raise_alarm(index, step, id)),
+                    Ok(TrialResult::Failed(failure, id)) => {
+                        trials_in_flight -= 1;
+                        failed.get_or_insert(MarleaEngineError::TrialFailed { trial: id, failure });
+                    }
+                    Ok(TrialResult::Interrupted(_)) => trials_in_flight -= 1,
+                    Ok(timeline_entry) => self.forward_timeline_entry(timeline_entry, &mut watched_trial, &timeline_writer_sender),
+                    Err(_) => break,
+                }
+            }
+        }
+        drop(computation_threads_reciever);
+        drop(run_over);
+
+        // a deadline passing just as the last trial finished leaves nothing out
+        let partial = deadline_passed.load(Ordering::Relaxed) && completed_trials.len() < max_trials;
+        if partial {
+            log::log(Level::Warn, format_args!("forced termination because max time was reached, returned results are partial and only cover the trials completed in time"));
+        }
+
         drop(timeline_writer_sender);
+        // wait for the timeline file to be complete before returning
+        if let Some(timeline_writer) = timeline_writer {
+            timeline_writer.join().unwrap();
+        }
+
+        let finished = partial || trials_needed.is_some() || completed_trials.len() >= max_trials;
+        // keep exactly the trials the precision target was reached at so the result only depends on the seeds
+        if let Some(trials_needed) = trials_needed {
+            completed_trials.retain(|id, _| *id < trials_needed);
+            trajectories.retain(|id, _| *id < trials_needed);
+            passage_steps.retain(|id, _| *id < trials_needed);
+            cycles.retain(|id, _| *id < trials_needed);
+            alarms.retain(|alarm| alarm.trial < trials_needed);
+            trial_seeds.truncate(trials_needed);
+        }
 
-        return self.terminate(simulation_results);
+        self.publish_status(snapshot(completed_trials.len(), trials_in_flight, total_steps, total_reactions_fired), start_time, false);
+        let records = TrialRecords { completed_trials, trial_seeds, trial_strata, trajectories, passage_steps, cycles, alarms, reactions: network.get_reactions().iter().cloned().collect(), 
+            network_fingerprint: network.fingerprint(), wall_time: start_time.elapsed(), partial, finished };
 
+        // hand a checkpoint to a waiting pause call
+        let mut pause_state = self.pause_state.lock().unwrap();
+        pause_state.running = false;
+        if pause_state.requested {
+            pause_state.requested = false;
+            pause_state.checkpoint = Some(Checkpoint {
+                network_fingerprint: network.fingerprint(),
+                trial_seeds: records.trial_seeds.clone(),
+                completed_trials: records.completed_trials.values().cloned().collect(),
+            });
+            self.pause_signal.notify_all();
+        }
+
+        return match failed {
+            Some(error) => Err(error),
+            None => Ok(records),
+        };
     }
-    
-    fn average_trials(simulation_results: HashSet<Solution>) -> Vec<(String, f64)> {
-        let mut summed_values = HashMap::<String, f64>::new();
-        let num_trials = simulation_results.len() as f64;
-    
-        // Sum values of each species across all trials
-        for result in &simulation_results {
-            for (name, count) in result.species_counts.clone() {
-                if let Species::Name(species_name) = name {
-                    if let Species::Count(species_count) = count  {
-                        summed_values.entry(species_name)
-                        .and_modify(|summed_count| *summed_count +=  species_count as f64)
-                        .or_insert(species_count as f64);    
+
+    /// Passes an alarm a trial raised to the alarm callback and the result sinks, returning it to be kept for the report
+    fn raise_alarm(&self, index: usize, step: i32, id: usize) -> Alarm {
+        let alarm = Alarm { trial: id, step, threshold: self.alarms[index].clone() };
+        if let Some(on_alarm) = &self.on_alarm {
+            on_alarm(alarm.clone());
+        }
+        self.send_result(MarleaResult::Alarm(alarm.clone()));
+        return alarm;
+    }
+
+    /// Sends a result to every subscribed result sink
+    fn send_result(&self, result: MarleaResult) {
+        let Some((last, others)) = self.result_sinks.split_last() else {return};
+        for result_sink in others {
+            result_sink.send(result.clone());
+        }
+        last.send(result);
+    }
+
+    /// returns true if trial summaries, running averages and progress are sent to the result sinks, see `Builder::response`
+    fn streams_trials(&self) -> bool {
+        return !self.result_sinks.is_empty() && self.response != ResponseMode::None;
+    }
+
+    /// returns the id of the trial whose counts are streamed to the result sinks, see `Builder::watch`
+    fn watched_trial(&self) -> Option<usize> {
+        if !self.streams_trials() {
+            return None;
+        }
+        return match self.response {
+            ResponseMode::EveryStep { .. } => Some(self.watched_trial.unwrap_or(0)),
+            _ => self.watched_trial,
+        };
+    }
+
+    /// Streams a timeline message of the watched trial to the result sinks and passes it on to the timeline writer if there is one
+    fn forward_timeline_entry(&self, entry: TrialResult, watched_trial: &mut WatchedTrial, timeline_writer_sender: &SyncSender<TrialResult>) {
+        let watched_changes = match &entry {
+            TrialResult::TimelineEntry(solution, id) if Some(*id) == self.watched_trial() => 
+                Some(solution.iter().map(|(name, count)| (name.clone(), count.clone())).collect()),
+            TrialResult::TimelineDelta(changes, id) if Some(*id) == self.watched_trial() => Some(changes.clone()),
+            _ => None,
+        };
+        if let Some(mut changes) = watched_changes {
+            // every timeline message of a trial is one step
+            watched_trial.steps += 1;
+            match self.response {
+                ResponseMode::EveryStep { every_n } => {
+                    watched_trial.counts.extend(changes);
+                    if watched_trial.steps % every_n == 0 {
+                        self.stream_watched(watched_trial.counts.clone().into_iter().collect(), watched_trial.steps);
                     }
-                } else {
-                    panic!("Got non-species name when calculating averages");
+                }
+                _ => {
+                    changes.sort();
+                    self.stream_watched(changes, watched_trial.steps);
                 }
             }
         }
-    
-        // Calculate averages and sort alphabetically
-        let mut averaged_values: Vec<(String, f64)> = summed_values
-                        .into_iter()
-                        .map(|(key, value)| (key, value / num_trials))
-                        .collect();
-        averaged_values.sort_by_key(|(species, _)| species.to_owned());
+        if self.out_timeline.is_some() {
+            timeline_writer_sender.send(entry).unwrap();
+        }
+    }
+
+    fn stream_watched(&self, counts: Vec<(Species, Species)>, step: i32) {
+        if self.result_sinks.is_empty() {
+            return;
+        }
+        for (name, count) in counts {
+            let tracked = self.tracked_species.as_ref().is_none_or(|species| species.contains(&name.to_string()));
+            if let (true, Species::Count(count)) = (tracked, count) {
+                self.send_result(MarleaResult::Point(name.to_string(), step, count));
+            }
+        }
+    }
+
+    /// Returns the seed of the trial with the given id, generating and recording it if the trial has not been seeded yet. 
+    /// In antithetic mode every odd trial mirrors the seed of the trial before it.
+    fn trial_seed(&self, id: usize, trial_seeds: &mut Vec<u64>) -> u64 {
+        if let Some(seed) = trial_seeds.get(id) {
+            return *seed;
+        }
 
-        return averaged_values;
+        let seed = if self.antithetic && id % 2 == 1 {
+            trial_seeds[id - 1]
+        } else {
+            let seed_index = if self.antithetic {id / 2} else {id};
+            match self.seed {
+                Some(master_seed) => random::trial_seed(master_seed, seed_index),
+                None => rand::random(),
+            }
+        };
+        trial_seeds.push(seed);
+
+        return seed;
+    }
+
+    /// Runs the simulation like `run` and writes a self contained experiment bundle to `directory`. 
+    /// The bundle holds the canonical network and initial solution, the engine configuration, the seed of every trial, 
+    /// the averaged results, any recorded timelines and a manifest tying them together. 
+    /// Returns an error if a trial fails or a file of the bundle can not be written.
+    pub fn run_experiment(&self, directory: &str) -> Result<FinalReport, MarleaEngineError> {
+        let records = self.try_run_trials(&self.prime_network, None, self.max_trials(), None)?;
+        let trial_seeds = records.trial_seeds.clone();
+
+        let report = self.terminate(records);
+        experiment::export(self, directory, &trial_seeds, &report).map_err(MarleaEngineError::ExportFailed)?;
+
+        return Ok(report);
+    }
+
+    /// Runs the full simulation at every point of the sweep's parameter grid one after another on the same thread pool. 
+    /// Every point uses the same trial seeds, so differences between points come from the parameters rather than sampling noise. 
+    /// Reports are returned in grid order and are not written to the output path or sent to the frontend.
+    pub fn sweep(&self, sweep: &Sweep) -> Vec<SweepPoint> {
+        return sweep.grid().into_iter()
+            .map(|parameters| {
+                let network = Sweep::network_at(&self.prime_network, &parameters);
+                let report = self.build_report(self.run_trials(&network, None));
+                SweepPoint { parameters, report }
+            })
+            .collect();
+    }
+
+    /// Searches the rates chosen in `fit` for those whose simulated averages come closest to the fit's target counts
+    pub fn fit(&self, fit: &Fit) -> FitResult {
+        let reactions = fit.get_reactions();
+        return fit.minimize(|rates| {
+            let point: Vec<(Parameter, ParameterValue)> = reactions.iter()
+                .map(|reaction| Parameter::ReactionRate(reaction.clone()))
+                .zip(rates.iter().copied().map(ParameterValue::Rate))
+                .collect();
+            return self.build_report(self.run_trials(&Sweep::network_at(&self.prime_network, &point), None));
+        });
+    }
+
+    /// Runs a Sobol sensitivity analysis and returns the indices of every parameter for each output species, 
+    /// ranked by total index so the parameters dominating an output's variance come first. 
+    /// Parameter samples are drawn from the master seed when one is set. Indices of outputs which never vary are NaN.
+    /// Panics before running anything if an output is not a species of the network
+    pub fn sensitivity(&self, analysis: &Sensitivity, outputs: &[&str]) -> Vec<(String, Vec<SobolIndices>)> {
+        let solution = self.prime_network.get_solution();
+        if let Some(output) = outputs.iter().find(|output| !solution.species_counts.contains_key(&Species::Name(output.to_string()))) {
+            panic!("can not analyse output {} which is not in the network", output);
+        }
+        let seed = match self.seed {
+            Some(master_seed) => random::split_mix(master_seed ^ 0x5eed_5e50_b01e),
+            None => rand::random(),
+        };
+        let (a, b) = analysis.sample(self.rng_backend.seeded(seed).as_mut());
+        let parameters = analysis.get_parameters();
+
+        // run the full simulation at a row of parameter values and return the average of every output
+        let evaluate = |row: &[ParameterValue]| -> Vec<f64> {
+            let point: Vec<(Parameter, ParameterValue)> = parameters.iter().cloned().zip(row.iter().copied()).collect();
+            let report = self.build_report(self.run_trials(&Sweep::network_at(&self.prime_network, &point), None));
+            // statistics hold every species of the network, which the outputs were checked to be
+            return outputs.iter()
+                .map(|output| report.statistics.iter().find(|(name, _)| name == output).unwrap().1.mean)
+                .collect();
+        };
+        let f_a: Vec<Vec<f64>> = a.iter().map(|row| evaluate(row)).collect();
+        let f_b: Vec<Vec<f64>> = b.iter().map(|row| evaluate(row)).collect();
+        let f_ab: Vec<Vec<Vec<f64>>> = (0..parameters.len())
+            .map(|i| a.iter().zip(&b)
+                .map(|(a_row, b_row)| {
+                    let mut row = a_row.clone();
+                    row[i] = b_row[i];
+                    evaluate(&row)
+                })
+                .collect())
+            .collect();
+
+        let column = |rows: &[Vec<f64>], output: usize| -> Vec<f64> {rows.iter().map(|row| row[output]).collect()};
+        return outputs.iter().enumerate()
+            .map(|(output, name)| {
+                let f_ab_output: Vec<Vec<f64>> = f_ab.iter().map(|rows| column(rows, output)).collect();
+                (name.to_string(), sensitivity::indices(parameters.clone(), &column(&f_a, output), &column(&f_b, output), &f_ab_output))
+            })
+            .collect();
+    }
+
+    /// Uses the estimated size of a trial to decide how many trials may exist at once under the memory limit
+    fn max_trials_in_flight(&self) -> Result<usize, MarleaEngineError> {
+        match self.memory_limit {
+            Some(limit) => {
+                let trial_size = self.estimated_trial_size();
+                if trial_size > limit {
+                    return Err(MarleaEngineError::MemoryLimit { limit, trial_size });
+                }
+                return Ok(limit / trial_size);
+            }
+            None => return Ok(usize::MAX),
+        }
+    }
+
+    /// returns a rough estimate in bytes of the memory a single trial occupies, 
+    /// its network along with the trajectory it records and what the timeline writer keeps for it
+    fn estimated_trial_size(&self) -> usize {
+        let mut trial_size = self.prime_network.estimated_size();
+        let interval = self.trajectory_interval.or(self.recorded_trajectories.as_ref().map(|(interval, _)| *interval));
+        if let (Some(interval), Some(max_steps)) = (interval, self.max_steps) {
+            // the longest trajectory is that of a trial running up to the step limit, `Builder::try_build` makes sure there is one
+            let samples = max_steps.max(0) as usize / interval.max(1) as usize + 1;
+            trial_size += samples * self.tracked(self.prime_network.get_solution()).estimated_size();
+        }
+        if self.out_timeline.is_some() {
+            // the writer keeps the running solution of every trial along with a buffered csv writer of 8 KiB
+            trial_size += self.prime_network.get_solution().estimated_size() + 8 * 1024;
+        }
+        return trial_size;
+    }
+
+    fn create_trial(&self, network: &ReactionNetwork, id: usize, seed: u64, stratum: Option<&Stratum>) -> trial::Trial {
+        let antithetic = self.antithetic && id % 2 == 1;
+        let mut network = network.clone();
+        if let Some(stratum) = stratum {
+            stratum.sample(&mut network, self.rng_backend.stream(seed, RngStream::InitialSolution).as_mut());
+        }
+        let mut trial = trial::Trial::from(network, self.max_semi_stable_steps, id, seed, self.rng_backend, antithetic);
+        if let Some(observer) = &self.observer {
+            trial.observe(observer.clone());
+        }
+        if let Some(detector) = &self.stability_detector {
+            trial.detect_stability(detector());
+        }
+        if self.sparse_solutions {
+            trial.sparse_solutions();
+        }
+        if !self.events.is_empty() {
+            trial.schedule(self.events.clone());
+        }
+        if let Some(interval) = self.trajectory_interval {
+            trial.record_trajectory(interval);
+        } else if let Some((interval, selected)) = &self.recorded_trajectories {
+            if selected.as_ref().is_none_or(|selected| selected.contains(&id)) {
+                trial.record_trajectory(*interval);
+            }
+        }
+        if let Some(species) = &self.tracked_species {
+            trial.track_species(species.clone());
+        }
+        if !self.passage_targets.is_empty() {
+            trial.watch_passage(self.passage_targets.clone());
+        }
+        if !self.alarms.is_empty() {
+            trial.watch_alarms(self.alarms.clone());
+        }
+        for target in &self.stop_targets {
+            trial.stop_at(target.clone());
+        }
+        if let Some(max_steps) = self.max_steps {
+            trial.limit_steps(max_steps);
+        }
+        if let Some(time_limit) = self.trial_time_limit {
+            trial.limit_time(time_limit);
+        }
+        return trial;
+    }
+
+    /// Returns how many trials a run starts at most
+    fn max_trials(&self) -> usize {
+        let max_trials = self.num_trials.unwrap_or(100);
+        if self.antithetic && max_trials % 2 == 1 {
+            // antithetic trials only make sense in complete pairs
+            return max_trials + 1;
+        }
+        return max_trials;
+    }
+
+    /// Re-creates a trial exactly as it was started during a run from its id and seed
+    fn recreate_trial(&self, id: usize, seed: u64) -> trial::Trial {
+        let trial_strata = self.assign_strata(&self.allocation(self.max_trials()));
+        let stratum = trial_strata.get(id).map(|index| &self.strata[*index]);
+        return self.create_trial(&self.prime_network, id, seed, stratum);
+    }
+
+    /// Creates the trial with the given id set up and seeded exactly as `run` would start it, 
+    /// to be stepped through with `Trial::advance` on the calling thread. 
+    /// Stepping needs neither worker threads nor a clock, so this is how the engine is driven where there are none, 
+    /// such as in a browser on `wasm32-unknown-unknown` where `run` panics.
+    pub fn stepper(&self, id: usize) -> trial::Trial {
+        let mut trial_seeds = Vec::new();
+        for earlier in 0..id {
+            self.trial_seed(earlier, &mut trial_seeds);
+        }
+        let seed = self.trial_seed(id, &mut trial_seeds);
+        return self.recreate_trial(id, seed);
+    }
+
+    /// Re-simulates a completed trial from its id and seed, both found in its `TrialSummary`, 
+    /// and returns the count of every species over the trial compressed to the points where its slope changes. 
+    /// Drawing straight lines between the points reproduces the full trajectory exactly.
+    /// Only the species set with `Builder::tracked_species` are included if any were.
+    pub fn get_timeline(&self, id: usize, seed: u64) -> Vec<(String, Vec<Point>)> {
+        return self.compress_timeline(id, seed, self.tracked_species.as_deref(), false).0;
     }
-    
 
-    fn solution_from(file_path: Option<String>, reactions: &HashSet<Reaction>) -> Solution {
+    /// Like `get_timeline` but also returns a marker for every reaction fired in step order, 
+    /// showing which branch the trial took where the counts alone are ambiguous
+    pub fn get_timeline_with_markers(&self, id: usize, seed: u64) -> (Vec<(String, Vec<Point>)>, Vec<Marker>) {
+        return self.compress_timeline(id, seed, self.tracked_species.as_deref(), true);
+    }
+
+    /// Like `get_timeline` but only tracks and compresses the given species
+    pub fn get_timeline_of(&self, id: usize, seed: u64, species: &[&str]) -> Vec<(String, Vec<Point>)> {
+        let species: Vec<String> = species.iter().map(|name| name.to_string()).collect();
+        return self.compress_timeline(id, seed, Some(&species), false).0;
+    }
+
+    fn compress_timeline(&self, id: usize, seed: u64, tracked_species: Option<&[String]>, with_markers: bool) -> (Vec<(String, Vec<Point>)>, Vec<Marker>) {
+        let mut trial = self.recreate_trial(id, seed);
+        let mut compressor = timeline::Compressor::new(trial.get_solution(), tracked_species);
+        let mut markers = Vec::new();
+        loop {
+            let stable = trial.advance();
+            if let (true, Some(reaction)) = (with_markers, trial.get_last_reaction()) {
+                markers.push(Marker { step: trial.get_step_count(), reaction: reaction.to_string() });
+            }
+            compressor.push(trial.get_step_count(), trial.get_solution());
+            if stable {
+                break;
+            }
+        }
+        return (compressor.finish(), markers);
+    }
+
+    /// Lazily replays the trial with the given id step by step, re-creating it from the seed the master seed assigns it. 
+    /// Only the current state is held in memory, so long trials can be inspected without recording their trajectory.
+    /// 
+    /// # Panics
+    /// If no master seed was set, since the seeds of unseeded trials are not kept. 
+    /// Use `get_timeline` with the seed from the trial's `TrialSummary` instead.
+    pub fn replay(&self, id: usize) -> Replay {
+        if self.seed.is_none() {
+            panic!("replaying trial {} requires a master seed", id);
+        }
+        let mut trial_seeds = Vec::new();
+        let mut seed = 0;
+        for trial_id in 0..=id {
+            seed = self.trial_seed(trial_id, &mut trial_seeds);
+        }
+        return Replay::new(self.recreate_trial(id, seed));
+    }
+
+    /// Returns the trial counts the strata are allocated over in turn for a run of `max_trials` trials, 
+    /// the blocks of an extended run if they cover it
+    fn allocation(&self, max_trials: usize) -> Vec<usize> {
+        let blocks = self.allocation_blocks.lock().unwrap();
+        if !blocks.is_empty() && blocks.iter().sum::<usize>() >= max_trials {
+            return blocks.clone();
+        }
+        return vec![max_trials];
+    }
+
+    /// Returns the index of the stratum each trial id samples from, empty if no strata were set. 
+    /// Each block of trials is split between the strata on its own, so extending a run leaves earlier trials in their stratum
+    fn assign_strata(&self, blocks: &[usize]) -> Vec<usize> {
+        if self.strata.is_empty() {
+            return Vec::new();
+        }
+
+        let trials_per_unit = if self.antithetic {2} else {1};
+        let mut trial_strata = Vec::new();
+        for block in blocks {
+            let allocation = strata::allocate(&self.strata, block / trials_per_unit);
+            for (index, units) in allocation.into_iter().enumerate() {
+                trial_strata.extend(std::iter::repeat_n(index, units * trials_per_unit));
+            }
+        }
+        return trial_strata;
+    }
+
+    /// Drops trials whose antithetic partner never finished, since a lone half of a pair biases the estimator
+    fn complete_pairs(trials: Vec<&TrialSummary>) -> Vec<&TrialSummary> {
+        let ids: HashSet<usize> = trials.iter().map(|summary| summary.id).collect();
+        return trials.into_iter()
+            .filter(|summary| ids.contains(&(summary.id ^ 1)))
+            .collect();
+    }
+    
+    // returns every species of the reactions at 0 with the counts of the initial solution file if given, 
+    // or a message describing why the file could not be read
+    pub(crate) fn solution_from(file_path: Option<String>, reactions: &HashSet<Reaction>) -> Result<Solution, String> {
         let mut species_counts: HashMap<Species, Species> = HashMap::new();
 
         // Get possible species from reactions
@@ -243,34 +1152,274 @@ impl MarleaEngine {
         }
 
         if let Some(path) = file_path {
-            SupportedFileType::from(path).parse_initial_solution(&mut species_counts);
+            SupportedFileType::try_from_path(path)?.parse_initial_solution(&mut species_counts)?;
         }
 
-        return Solution{species_counts}; 
+        return Ok(Solution{species_counts}); 
     }
 
-    fn terminate(&self, simulation_results: HashSet<Solution>) -> Vec<(String, f64)> {
-        
-        let average_stable_solution = Self::average_trials(simulation_results);
+    /// Writes the averages of a finished run to the output file or stdout and hands the report to the frontend
+    fn terminate(&self, records: TrialRecords) -> FinalReport {
+        let report = self.build_report(records);
 
         //write results if output option ennabled
         if let Some(path) = &self.out_path {
-            let output_file = SupportedFileType::from(path.clone());
-            output_file.write_solution(average_stable_solution.clone());
+            // the report is returned either way, so a file which can not be written does not throw the run away
+            let written = SupportedFileType::try_from_path(path.clone())
+                .and_then(|output_file| output_file.write_solution(report.averages.clone()));
+            if let Err(error) = written {
+                log::log(Level::Warn, format_args!("the averages were not written to {}: {}", path, error));
+            }
         } else {
-            for entry in average_stable_solution.clone() {
-                println!("{},{}", entry.0 , entry.1);
+            for entry in &report.averages {
+                log::log(Level::Info, format_args!("{},{}", entry.0 , entry.1));
             }
         }
 
-        return average_stable_solution;
+        if !self.result_sinks.is_empty() {
+            self.send_result(MarleaResult::Final(Box::new(report.clone())));
+        }
+
+        return report;
     }
 
-    fn engine_runtime_timer(runtime: u64, tx: SyncSender<bool>) {
-        let max_runtime = std::time::Duration::from_secs(runtime);
-        std::thread::sleep(max_runtime);
-        tx.send(true).unwrap();
-        return;
-    } 
+    /// Computes every statistic of the report from the recorded trials
+    fn build_report(&self, records: TrialRecords) -> FinalReport {
+        let trial_strata = records.trial_strata;
+        // timed out trials were abandoned part way, so their solutions say nothing about the network
+        let finished_trials: Vec<&TrialSummary> = records.completed_trials.values()
+            .filter(|summary| summary.termination != Termination::TimedOut)
+            .collect();
+        let simulation_results = match self.antithetic {
+            true => Self::complete_pairs(finished_trials),
+            false => finished_trials,
+        };
+        let num_trials = simulation_results.len();
+        let solutions: Vec<&Solution> = simulation_results.iter().map(|summary| &summary.solution).collect();
+
+        let histograms = match self.histogram_binning {
+            Some(binning) => statistics::histograms(&solutions, binning),
+            None => Vec::new(),
+        };
+        let bootstrap = match self.bootstrap_resamples {
+            Some(resamples) => {
+                // resample whole antithetic pairs within the stratum their trials were drawn from
+                let unit_size = if self.antithetic {2} else {1};
+                let mut groups: Vec<(f64, Vec<Vec<&Solution>>)> = match self.strata.is_empty() {
+                    true => vec![(1.0, Vec::new())],
+                    false => self.strata.iter().map(|stratum| (stratum.get_weight(), Vec::new())).collect(),
+                };
+                for unit in simulation_results.chunks(unit_size) {
+                    let group = if self.strata.is_empty() {0} else {trial_strata[unit[0].id]};
+                    groups[group].1.push(unit.iter().map(|summary| &summary.solution).collect());
+                }
+                groups.retain(|(_, units)| !units.is_empty());
+
+                let seed = match self.seed {
+                    Some(master_seed) => random::split_mix(master_seed ^ 0xb007_57a9),
+                    None => rand::random(),
+                };
+                statistics::bootstrap(&groups, resamples, self.rng_backend.seeded(seed).as_mut())
+            }
+            None => Vec::new(),
+        };
+        let correlations = self.correlated_species.as_ref()
+            .map(|species| statistics::correlations(&solutions, species));
+        let order_statistics = match &self.percentiles {
+            Some(percentiles) => statistics::order_statistics(&solutions, percentiles),
+            None => Vec::new(),
+        };
+        let statistics = self.summarize(simulation_results.iter().map(|summary| (summary.id, &summary.solution)).collect(), &trial_strata);
+
+        // trials which stabilized before a sample point hold their stable solution at it
+        let trajectory = match self.trajectory_interval {
+            Some(_) => {
+                let stable_solutions: BTreeMap<usize, Solution> = simulation_results.iter()
+                    .map(|summary| (summary.id, self.tracked(&summary.solution)))
+                    .collect();
+                let longest = records.trajectories.values().map(|samples| samples.len()).max().unwrap_or(0);
+                (0..longest).map(|point| {
+                    let solutions_at_point = simulation_results.iter()
+                        .filter_map(|summary| {
+                            let samples = records.trajectories.get(&summary.id)?;
+                            Some((summary.id, samples.get(point).unwrap_or(&stable_solutions[&summary.id])))
+                        })
+                        .collect();
+                    self.summarize(solutions_at_point, &trial_strata)
+                }).collect()
+            }
+            None => Vec::new(),
+        };
+        // per trial samples of the trials the user asked to keep, ending on the stable solution
+        let trajectories = match &self.recorded_trajectories {
+            Some((interval, selected)) => records.trajectories.iter()
+                .filter(|(id, _)| selected.as_ref().is_none_or(|selected| selected.contains(id)))
+                .filter_map(|(id, samples)| {
+                    let summary = records.completed_trials.get(id)?;
+                    let mut samples: Vec<(i32, Solution)> = samples.iter().cloned().enumerate()
+                        .map(|(index, solution)| (index as i32 * interval, solution))
+                        .collect();
+                    if summary.steps % interval != 0 {
+                        samples.push((summary.steps, self.tracked(&summary.solution)));
+                    }
+                    Some((*id, samples))
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+
+        let first_passage = self.passage_targets.iter().enumerate()
+            .map(|(index, target)| {
+                let steps = simulation_results.iter()
+                    .filter_map(|summary| records.passage_steps.get(&summary.id))
+                    .map(|steps| steps[index])
+                    .collect();
+                FirstPassage::new(target.clone(), steps)
+            })
+            .collect();
+
+        // firing counts summed over every trial, including those left out of the statistics
+        let mut reaction_firings: BTreeMap<String, u64> = records.reactions.iter().map(|reaction| (reaction.to_string(), 0)).collect();
+        let mut tail_firings = HashMap::<String, u64>::new();
+        for summary in records.completed_trials.values() {
+            for (reaction, count) in &summary.reaction_firings {
+                *reaction_firings.entry(reaction.clone()).or_insert(0) += count;
+            }
+            for (reaction, count) in &summary.tail_firings {
+                *tail_firings.entry(reaction.clone()).or_insert(0) += count;
+            }
+        }
+
+        // fluxes leave out the stable tails, where reactions only fire to confirm a trial is stable
+        let flux_steps: i64 = records.completed_trials.values().map(|summary| (summary.steps - summary.tail_steps) as i64).sum();
+        let reaction_fluxes: Vec<(String, f64)> = reaction_firings.iter()
+            .map(|(reaction, count)| {
+                let count = count - tail_firings.get(reaction).unwrap_or(&0);
+                (reaction.clone(), match flux_steps {
+                    0 => 0.0,
+                    _ => count as f64 / flux_steps as f64,
+                })
+            })
+            .collect();
+        let mut species_rates = BTreeMap::<String, f64>::new();
+        for reaction in &records.reactions {
+            let flux = reaction_fluxes.iter().find(|(name, _)| *name == reaction.to_string()).map_or(0.0, |(_, flux)| *flux);
+            for (species, change) in reaction.get_net_changes().iter().filter(|(species, _)| !self.prime_network.get_clamped_species().contains(species)) {
+                *species_rates.entry(species.to_string()).or_insert(0.0) += flux * *change as f64;
+            }
+        }
+        let reaction_firings: Vec<(String, u64)> = reaction_firings.into_iter().collect();
+
+        let mut alarms = records.alarms;
+        alarms.sort_by_key(|alarm| (alarm.trial, alarm.step));
+
+        let stalls = match self.stall_diagnostics {
+            true => simulation_results.iter()
+                .filter(|summary| summary.termination == Termination::Stable)
+                .map(|summary| (summary.id, diagnostics::blocked_reactions(self.prime_network.get_reactions(), &summary.solution)))
+                .collect(),
+            false => Vec::new(),
+        };
+
+        let mut average_stable_solution: Vec<(String, f64)> = statistics.iter()
+            .filter(|(name, _)| self.averages_species(name))
+            .map(|(name, species_statistics)| (name.clone(), species_statistics.mean))
+            .collect();
+        self.species_order.sort(&mut average_stable_solution);
+
+        return FinalReport {
+            averages: average_stable_solution,
+            statistics,
+            histograms,
+            trajectory,
+            trajectories,
+            first_passage,
+            cycles: records.cycles.into_iter().collect(),
+            alarms,
+            stalls,
+            dead_reactions: reaction_firings.iter()
+                .filter(|(_, count)| *count == 0)
+                .map(|(reaction, _)| reaction.clone())
+                .collect(),
+            reaction_firings,
+            reaction_fluxes,
+            species_rates: species_rates.into_iter().collect(),
+            correlations,
+            bootstrap,
+            order_statistics,
+            num_trials,
+            total_reactions_fired: records.completed_trials.values().map(|summary| summary.reactions_fired).sum(),
+            wall_time: records.wall_time,
+            partial: records.partial,
+            seed: self.seed,
+            network_fingerprint: records.network_fingerprint,
+            config: self.get_config(),
+            trials: records.completed_trials.into_values().collect(),
+        };
+    }
+
+    /// Returns the settings this engine runs with, with defaults filled in
+    pub fn get_config(&self) -> EngineConfig {
+        return EngineConfig {
+            trials: self.num_trials.unwrap_or(100),
+            max_runtime: self.max_runtime,
+            max_semi_stable_steps: self.max_semi_stable_steps,
+            max_steps: self.max_steps,
+            trial_time_limit: self.trial_time_limit,
+            memory_limit: self.memory_limit,
+            seed: self.seed,
+            rng_backend: self.rng_backend,
+            antithetic: self.antithetic,
+            strata: self.strata.clone(),
+            histogram_binning: self.histogram_binning,
+            percentiles: self.percentiles.clone(),
+            target_precision: self.target_precision.clone(),
+            trajectory_interval: self.trajectory_interval,
+            recorded_trajectories: self.recorded_trajectories.clone(),
+            passage_targets: self.passage_targets.clone(),
+            alarms: self.alarms.clone(),
+            correlated_species: self.correlated_species.clone(),
+            bootstrap_resamples: self.bootstrap_resamples,
+            tracked_species: self.tracked_species.clone(),
+            included_species: self.included_species.clone(),
+            excluded_species: self.excluded_species.clone(),
+            until: self.stop_targets.clone(),
+            events: self.events.clone(),
+            stall_diagnostics: self.stall_diagnostics,
+            sparse_solutions: self.sparse_solutions,
+            species_order: self.species_order.clone(),
+        };
+    }
+
+    /// returns true if the species is included in averages, see `Builder::include_species`
+    fn averages_species(&self, name: &str) -> bool {
+        return (self.included_species.is_empty() || self.included_species.iter().any(|pattern| pattern.matches(name)))
+            && !self.excluded_species.iter().any(|pattern| pattern.matches(name));
+    }
+
+    /// returns a copy of the tracked species of a solution, or of all species if none were chosen
+    fn tracked(&self, solution: &Solution) -> Solution {
+        return match &self.tracked_species {
+            Some(species) => solution.select(species),
+            None => solution.clone(),
+        };
+    }
+
+    /// Computes species statistics over the given trials keyed by trial id, 
+    /// summarizing each stratum on its own and weighting the results together if strata are set
+    fn summarize(&self, solutions: Vec<(usize, &Solution)>, trial_strata: &[usize]) -> Vec<(String, SpeciesStatistics)> {
+        if self.strata.is_empty() {
+            return statistics::summarize(&solutions.into_iter().map(|(_, solution)| solution).collect::<Vec<&Solution>>(), self.antithetic);
+        }
+
+        let mut grouped: Vec<Vec<&Solution>> = vec![Vec::new(); self.strata.len()];
+        for (id, solution) in solutions {
+            grouped[trial_strata[id]].push(solution);
+        }
+        let stratum_statistics = grouped.into_iter()
+            .map(|group| if group.is_empty() {None} else {Some(statistics::summarize(&group, self.antithetic))})
+            .collect();
+        return statistics::combine_strata(&self.strata, stratum_statistics);
+    }
 
 }
\ No newline at end of file
@@ -0,0 +1,45 @@
+use std::fmt::Arguments;
+use std::sync::{RwLock, atomic::{AtomicU8, Ordering}};
+
+/// How much detail a log message goes into, from problems with a run down to single steps of a trial
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    /// Problems with the network or the run which results should be read with in mind
+    Warn,
+    /// Once per run, such as the averages a run ended with
+    Info,
+    /// Once per trial
+    Debug,
+    /// Every reaction fired in every trial, which slows simulation down considerably
+    Trace,
+}
+
+type Logger = Box<dyn Fn(Level, &str) + Send + Sync>;
+
+static LOGGER: RwLock<Option<Logger>> = RwLock::new(None);
+/// Most detailed level messages are passed on at, checked before a message is formatted so unwanted levels cost next to nothing
+static MAX_LEVEL: AtomicU8 = AtomicU8::new(Level::Info as u8);
+
+/// Passes every message the engine logs at `max_level` or a less detailed level to the logger, for all engines in the process.
+/// Without a logger warnings and run information are printed to stdout,
+/// `set_logger(Level::Warn, |_, _| {})` silences the engine entirely.
+pub fn set_logger(max_level: Level, logger: impl Fn(Level, &str) + Send + Sync + 'static) {
+    *LOGGER.write().unwrap() = Some(Box::new(logger));
+    MAX_LEVEL.store(max_level as u8, Ordering::Relaxed);
+}
+
+/// returns true if a message at the level would be passed on
+pub(crate) fn enabled(level: Level) -> bool {
+    return level as u8 <= MAX_LEVEL.load(Ordering::Relaxed);
+}
+
+pub(crate) fn log(level: Level, message: Arguments) {
+    if !enabled(level) {
+        return;
+    }
+    match LOGGER.read().unwrap().as_ref() {
+        Some(logger) => logger(level, &message.to_string()),
+        None if level == Level::Warn => println!("WARNING: {}", message),
+        None => println!("{}", message),
+    }
+}
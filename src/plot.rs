@@ -0,0 +1,188 @@
+use std::fmt::Write;
+use super::statistics::{Histogram, SpeciesStatistics};
+use super::timeline::Point;
+
+const WIDTH: f64 = 800.0;
+const HEIGHT: f64 = 500.0;
+const MARGIN: f64 = 60.0;
+/// Space right of the plot area the legend is drawn in
+const LEGEND: f64 = 140.0;
+const COLOURS: [&str; 10] = ["#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b", "#e377c2", "#7f7f7f", "#bcbd22", "#17becf"];
+
+/// Renders the average trajectory of each species with its 95% confidence band,
+/// sample `i` of the trajectory being drawn `i * interval` steps in
+pub(crate) fn trajectory(trajectory: &[Vec<(String, SpeciesStatistics)>], interval: i32, path: &str) {
+    let species: Vec<&String> = trajectory.first().map(|sample| sample.iter().map(|(name, _)| name).collect()).unwrap_or_default();
+    let upper = trajectory.iter().flatten().map(|(_, statistics)| statistics.confidence_interval.1).filter(|bound| bound.is_finite());
+    let mut chart = Chart::new((0.0, (trajectory.len().max(2) - 1) as f64 * interval as f64), (0.0, upper.fold(1.0, f64::max)));
+
+    for (index, name) in species.iter().enumerate() {
+        let colour = COLOURS[index % COLOURS.len()];
+        let along = |value: fn(&SpeciesStatistics) -> f64| -> Vec<(f64, f64)> {
+            return trajectory.iter().enumerate()
+                .filter_map(|(sample, statistics)| {
+                    let (_, statistics) = statistics.iter().find(|(species, _)| species == *name)?;
+                    return Some(((sample as i32 * interval) as f64, value(statistics)));
+                })
+                .filter(|(_, value)| value.is_finite())
+                .collect();
+        };
+        chart.band(&along(|statistics| statistics.confidence_interval.0), &along(|statistics| statistics.confidence_interval.1), colour);
+        chart.line(&along(|statistics| statistics.mean), colour);
+    }
+    chart.legend(&species);
+    chart.write(path, "Average trajectory", "step", "count");
+}
+
+/// Renders the histogram of a species' stable count across trials
+pub(crate) fn histogram(species: &str, histogram: &Histogram, path: &str) {
+    let start = histogram.start as f64;
+    let width = histogram.bin_width as f64;
+    let tallest = histogram.counts.iter().copied().max().unwrap_or(0).max(1);
+    let mut chart = Chart::new((start, start + width * histogram.counts.len().max(1) as f64), (0.0, tallest as f64));
+
+    for (bin, trials) in histogram.counts.iter().enumerate() {
+        chart.bar(start + width * bin as f64, width, *trials as f64, COLOURS[0]);
+    }
+    chart.write(path, &format!("Stable count of {}", species), "count", "trials");
+}
+
+/// Renders the count of each species over the steps of a single trial, as returned by `MarleaEngine::get_timeline`
+pub fn plot_timeline(timeline: &[(String, Vec<Point>)], path: &str) {
+    let points = || timeline.iter().flat_map(|(_, points)| points);
+    let last_step = points().map(|point| point.step).max().unwrap_or(0).max(1);
+    let highest = points().map(|point| point.count).max().unwrap_or(0).max(1);
+    let mut chart = Chart::new((0.0, last_step as f64), (0.0, highest as f64));
+
+    for (index, (_, points)) in timeline.iter().enumerate() {
+        // the timeline only keeps the points the slope changes at, so straight lines between them give back every step
+        let line: Vec<(f64, f64)> = points.iter().map(|point| (point.step as f64, point.count as f64)).collect();
+        chart.line(&line, COLOURS[index % COLOURS.len()]);
+    }
+    chart.legend(&timeline.iter().map(|(name, _)| name).collect::<Vec<&String>>());
+    chart.write(path, "Timeline", "step", "count");
+}
+
+/// Plot area mapping data coordinates onto the image, collecting the SVG elements drawn into it
+struct Chart {
+    x_range: (f64, f64),
+    y_range: (f64, f64),
+    body: String,
+}
+
+impl Chart {
+    fn new(x_range: (f64, f64), y_range: (f64, f64)) -> Self {
+        // an empty range would divide by zero when mapping coordinates
+        let widen = |(low, high): (f64, f64)| if high > low {(low, high)} else {(low, low + 1.0)};
+        return Self { x_range: widen(x_range), y_range: widen(y_range), body: String::new() };
+    }
+
+    fn x(&self, x: f64) -> f64 {
+        return MARGIN + (x - self.x_range.0) / (self.x_range.1 - self.x_range.0) * (WIDTH - 2.0 * MARGIN - LEGEND);
+    }
+
+    fn y(&self, y: f64) -> f64 {
+        return HEIGHT - MARGIN - (y - self.y_range.0) / (self.y_range.1 - self.y_range.0) * (HEIGHT - 2.0 * MARGIN);
+    }
+
+    fn coordinates<'a>(&self, points: impl Iterator<Item = &'a (f64, f64)>) -> String {
+        return points.map(|(x, y)| format!("{:.1},{:.1}", self.x(*x), self.y(*y))).collect::<Vec<String>>().join(" ");
+    }
+
+    fn line(&mut self, points: &[(f64, f64)], colour: &str) {
+        let coordinates = self.coordinates(points.iter());
+        writeln!(self.body, r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="1.5"/>"#, coordinates, colour).unwrap();
+    }
+
+    /// shades the area between two lines sampled at the same x values
+    fn band(&mut self, lower: &[(f64, f64)], upper: &[(f64, f64)], colour: &str) {
+        let coordinates = self.coordinates(lower.iter().chain(upper.iter().rev()));
+        writeln!(self.body, r#"<polygon points="{}" fill="{}" fill-opacity="0.2" stroke="none"/>"#, coordinates, colour).unwrap();
+    }
+
+    fn bar(&mut self, x: f64, width: f64, height: f64, colour: &str) {
+        let (left, right, top) = (self.x(x), self.x(x + width), self.y(height));
+        writeln!(
+            self.body, r#"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="{}" stroke="white"/>"#,
+            left, top, right - left, self.y(self.y_range.0) - top, colour
+        ).unwrap();
+    }
+
+    fn legend(&mut self, names: &[&String]) {
+        for (index, name) in names.iter().enumerate() {
+            let (x, y) = (WIDTH - LEGEND - MARGIN + 20.0, MARGIN + 18.0 * index as f64);
+            let colour = COLOURS[index % COLOURS.len()];
+            writeln!(self.body, r#"<rect x="{:.1}" y="{:.1}" width="12" height="12" fill="{}"/>"#, x, y, colour).unwrap();
+            writeln!(self.body, r#"<text x="{:.1}" y="{:.1}">{}</text>"#, x + 18.0, y + 11.0, escape(name)).unwrap();
+        }
+    }
+
+    /// draws the axes with 5 evenly spaced ticks each around the plot and writes the image
+    fn write(self, path: &str, title: &str, x_label: &str, y_label: &str) {
+        if !path.ends_with(".svg") {
+            panic!("plots can only be written as svg, tried to write {}", path);
+        }
+        let (left, right, top, bottom) = (self.x(self.x_range.0), self.x(self.x_range.1), self.y(self.y_range.1), self.y(self.y_range.0));
+        let mut image = String::new();
+        writeln!(image, r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" font-family="sans-serif" font-size="12">"#, WIDTH, HEIGHT).unwrap();
+        writeln!(image, r#"<rect width="100%" height="100%" fill="white"/>"#).unwrap();
+        writeln!(image, r#"<text x="{:.1}" y="30" font-size="16" text-anchor="middle">{}</text>"#, (left + right) / 2.0, escape(title)).unwrap();
+        image.push_str(&self.body);
+        writeln!(image, r#"<polyline points="{:.1},{:.1} {:.1},{:.1} {:.1},{:.1}" fill="none" stroke="black"/>"#, left, top, left, bottom, right, bottom).unwrap();
+
+        for tick in 0..=4 {
+            let fraction = tick as f64 / 4.0;
+            let x_value = self.x_range.0 + fraction * (self.x_range.1 - self.x_range.0);
+            let y_value = self.y_range.0 + fraction * (self.y_range.1 - self.y_range.0);
+            let (x, y) = (self.x(x_value), self.y(y_value));
+            writeln!(image, r#"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="black"/>"#, x, bottom, x, bottom + 5.0).unwrap();
+            writeln!(image, r#"<text x="{:.1}" y="{:.1}" text-anchor="middle">{}</text>"#, x, bottom + 18.0, tick_label(x_value)).unwrap();
+            writeln!(image, r#"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="black"/>"#, left - 5.0, y, left, y).unwrap();
+            writeln!(image, r#"<text x="{:.1}" y="{:.1}" text-anchor="end">{}</text>"#, left - 8.0, y + 4.0, tick_label(y_value)).unwrap();
+        }
+        writeln!(image, r#"<text x="{:.1}" y="{:.1}" text-anchor="middle">{}</text>"#, (left + right) / 2.0, HEIGHT - 15.0, escape(x_label)).unwrap();
+        writeln!(
+            image, r#"<text x="15" y="{:.1}" text-anchor="middle" transform="rotate(-90 15 {:.1})">{}</text>"#,
+            (top + bottom) / 2.0, (top + bottom) / 2.0, escape(y_label)
+        ).unwrap();
+        image.push_str("</svg>\n");
+
+        std::fs::write(path, image).unwrap();
+    }
+}
+
+fn tick_label(value: f64) -> String {
+    return match value.fract() == 0.0 {
+        true => format!("{}", value),
+        false => format!("{:.1}", value),
+    };
+}
+
+/// species names may hold characters with a meaning in XML
+fn escape(text: &str) -> String {
+    return text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plot_timeline() {
+        let path = std::env::temp_dir().join(format!("marlea_test_{}_plot_timeline.svg", std::process::id()));
+        let timeline = vec![
+            ("a".to_string(), vec![Point { step: 0, count: 10 }, Point { step: 3, count: 7 }]),
+            ("b<c".to_string(), vec![Point { step: 0, count: 0 }, Point { step: 3, count: 3 }]),
+        ];
+        plot_timeline(&timeline, path.to_str().unwrap());
+
+        let image = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(image.starts_with("<svg") && image.ends_with("</svg>\n"));
+        assert_eq!(image.matches("<polyline").count(), 3);
+        // a steady decline is drawn as one straight segment rather than flat then dropping
+        assert!(image.contains(r#"<polyline points="60.0,60.0 600.0,174.0" fill="none""#));
+        assert!(image.contains(r#"<polyline points="60.0,440.0 600.0,326.0" fill="none""#));
+        assert!(image.contains(">b&lt;c</text>"));
+    }
+}
@@ -0,0 +1,31 @@
+use super::trial::{Trial, reaction_network::reaction::term::solution::Solution};
+
+/// Steps through a re-created trial one reaction at a time, yielding the step count and solution after each step.
+/// The initial solution comes first as step 0 and the stable solution last.
+pub struct Replay {
+    trial: Trial,
+    started: bool,
+    finished: bool,
+}
+
+impl Replay {
+    pub(crate) fn new(trial: Trial) -> Self {
+        return Self { trial, started: false, finished: false };
+    }
+}
+
+impl Iterator for Replay {
+    type Item = (i32, Solution);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.started {
+            self.started = true;
+            return Some((0, self.trial.get_solution().clone()));
+        }
+        if self.finished {
+            return None;
+        }
+        self.finished = self.trial.advance();
+        return Some((self.trial.get_step_count(), self.trial.get_solution().clone()));
+    }
+}
@@ -0,0 +1,737 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
+use std::sync::{Arc, Mutex, mpsc::SyncSender};
+use std::time::Duration;
+use super::diagnostics::BlockedReaction;
+use super::error::{MarleaEngineError, MergeError};
+#[cfg(feature = "plot")]
+use super::plot;
+use super::supported_file_type::SupportedFileType;
+use super::statistics::{self, Binning, BootstrapEstimate, CorrelationMatrix, FirstPassage, Histogram, OrderStatistics, SpeciesStatistics};
+use super::trial::{reaction_network::{compartments, reaction::term::solution::{Count, Solution}}, passage::{Comparison, PassageTarget}, random::{self, RngBackend}, schedule::{Perturbation, ScheduledEvent}, stability::Cycle, results::{Termination, TrialSummary}, strata::Stratum};
+
+/// The outcome of a full engine run
+#[derive(Debug, Clone, PartialEq)]
+pub struct FinalReport {
+    /// Average stable count of each species, sorted by name unless set otherwise with `Builder::order_averages`
+    pub averages: Vec<(String, f64)>,
+    /// Mean, spread and confidence interval of each species, sorted by name
+    pub statistics: Vec<(String, SpeciesStatistics)>,
+    /// Histogram of the stable count of each species sorted by name, empty unless enabled with `Builder::histograms`
+    pub histograms: Vec<(String, Histogram)>,
+    /// Statistics of each species at every sample point of the trajectory, point `i` lies `i` times the trajectory interval steps in. 
+    /// Empty unless enabled with `Builder::trajectory`, trials completed before a resume are not part of it.
+    pub trajectory: Vec<Vec<(String, SpeciesStatistics)>>,
+    /// The step and solution of each sample of the trials kept with `Builder::record_trajectories`, sorted by trial id. 
+    /// The last sample of a trial is always its stable solution.
+    pub trajectories: Vec<(usize, Vec<(i32, Solution)>)>,
+    /// First passage distribution of each target in the order they were added with `Builder::first_passage`. 
+    /// Like the trajectory, trials completed before a resume are not part of it.
+    pub first_passage: Vec<FirstPassage>,
+    /// Period and amplitude of every trial which settled into a limit cycle, sorted by trial id. 
+    /// Only filled by a stability detector looking for cycles such as `Oscillation`
+    pub cycles: Vec<(usize, Cycle)>,
+    /// The reactions blocked in the stable solution of each trial and the reactants they were missing, sorted by trial id. 
+    /// Empty unless enabled with `Builder::stall_diagnostics`
+    pub stalls: Vec<(usize, Vec<BlockedReaction>)>,
+    /// Every alarm set with `Builder::alarm` raised during the run, sorted by trial id and step. 
+    /// Trials completed before a resume are not part of it
+    pub alarms: Vec<Alarm>,
+    /// Every reaction of the network which never fired in any trial, written `a + 2 b => c`
+    pub dead_reactions: Vec<String>,
+    /// How often each reaction of the network fired summed over every trial, sorted by reaction
+    pub reaction_firings: Vec<(String, u64)>,
+    /// Average firings of each reaction per step outside the stable tails of trials, sorted by reaction
+    pub reaction_fluxes: Vec<(String, f64)>,
+    /// Average net production of each species per step outside the stable tails of trials, 
+    /// negative for species consumed on balance, sorted by species
+    pub species_rates: Vec<(String, f64)>,
+    /// Correlation of the species selected with `Builder::correlations`
+    pub correlations: Option<CorrelationMatrix>,
+    /// Bootstrap estimate of each species' mean sorted by name, empty unless enabled with `Builder::bootstrap`
+    pub bootstrap: Vec<(String, BootstrapEstimate)>,
+    /// Min, median, max and percentiles of each species sorted by name, empty unless enabled with `Builder::percentiles`
+    pub order_statistics: Vec<(String, OrderStatistics)>,
+    /// Number of trials the averages were taken over, leaving out timed out trials
+    pub num_trials: usize,
+    /// Reactions fired across every completed trial
+    pub total_reactions_fired: u64,
+    /// Time spent running trials, only counting this run when resumed from a checkpoint
+    pub wall_time: Duration,
+    /// True if the runtime limit ran out before every trial finished, 
+    /// the statistics then only cover the trials completed in time
+    pub partial: bool,
+    /// The master seed trial seeds were derived from, None if trials were seeded from entropy
+    pub seed: Option<u64>,
+    /// Fingerprint of the reaction network and initial solution the trials ran on, see `ReactionNetwork::fingerprint`
+    pub network_fingerprint: u64,
+    pub config: EngineConfig,
+    /// Summaries of every trial which finished, sorted by trial id. 
+    /// Trials cut off at the step limit or abandoned after their time limit are included and flagged by their termination
+    pub trials: Vec<TrialSummary>,
+}
+
+impl FinalReport {
+    /// Combines the report with that of an independent run of the same network and settings, such as one run on another machine, 
+    /// into the report of a single run over the trials of both. The trials of `other` follow those of this report with their ids shifted past the last id here. 
+    /// Means, variances and standard errors are pooled weighted by the number of trials and histograms, correlations and order statistics 
+    /// are worked out again from the stable solutions of every trial. Bootstrap estimates can not be combined without their resamples and are left out. 
+    /// Panics if the reports come from different networks or settings, see `try_merge`
+    pub fn merge(&self, other: &FinalReport) -> FinalReport {
+        return match self.try_merge(other) {
+            Ok(merged) => merged,
+            Err(error) => panic!("{}", error),
+        };
+    }
+
+    /// Merges the reports like `merge`, returning an error instead if they were run on different networks, 
+    /// watched different first passage targets or were built with settings which change the outcome of a trial. 
+    /// The trial count, seed, runtime and memory limits and the order of averages may differ, the merged averages follow the order of this report.
+    /// Reports run from the same master seed, or sharing any trial seed, are refused since their trials would be counted twice.
+    pub fn try_merge(&self, other: &FinalReport) -> Result<FinalReport, MergeError> {
+        if self.network_fingerprint != other.network_fingerprint {
+            return Err(MergeError::NetworkMismatch);
+        }
+        if self.config.passage_targets != other.config.passage_targets {
+            return Err(MergeError::PassageTargetMismatch);
+        }
+        // settings which only decide how many trials run, or how fast, do not change what a single trial does
+        let outcome_settings = |report: &FinalReport| EngineConfig {
+            trials: 0,
+            seed: None,
+            max_runtime: None,
+            trial_time_limit: None,
+            memory_limit: None,
+            species_order: SpeciesOrder::Name,
+            ..report.config.clone()
+        };
+        if outcome_settings(self) != outcome_settings(other) {
+            return Err(MergeError::ConfigMismatch);
+        }
+        // the same seeds rerun the same trials, which would be counted twice and overstate the confidence in the result
+        let seeds: HashSet<u64> = self.trials.iter().map(|summary| summary.seed).collect();
+        if (self.seed.is_some() && self.seed == other.seed) || other.trials.iter().any(|summary| seeds.contains(&summary.seed)) {
+            return Err(MergeError::OverlappingTrials);
+        }
+
+        let offset = self.trials.last().map_or(0, |summary| summary.id + 1);
+        let mut trials = self.trials.clone();
+        trials.extend(other.trials.iter().cloned().map(|mut summary| {
+            summary.id += offset;
+            summary
+        }));
+        let solutions: Vec<&Solution> = trials.iter()
+            .filter(|summary| summary.termination != Termination::TimedOut)
+            .map(|summary| &summary.solution)
+            .collect();
+
+        let pooled = statistics::pool(&self.statistics, self.num_trials, &other.statistics, other.num_trials);
+        let averaged: BTreeSet<&String> = self.averages.iter().chain(&other.averages).map(|(name, _)| name).collect();
+        let mut averages: Vec<(String, f64)> = pooled.iter()
+            .filter(|(name, _)| averaged.contains(name))
+            .map(|(name, species_statistics)| (name.clone(), species_statistics.mean))
+            .collect();
+        self.config.species_order.sort(&mut averages);
+
+        // every trial is stable past the end of a trajectory, so the final statistics of its tracked species hold there
+        let point = |report: &FinalReport, index: usize| -> Vec<(String, SpeciesStatistics)> {
+            return match (report.trajectory.get(index), report.trajectory.last()) {
+                (Some(point), _) => point.clone(),
+                (None, Some(last)) => report.statistics.iter().filter(|(name, _)| last.iter().any(|(tracked, _)| tracked == name)).cloned().collect(),
+                (None, None) => Vec::new(),
+            };
+        };
+        let trajectory_trials = |report: &FinalReport| if report.trajectory.is_empty() {0} else {report.num_trials};
+        let trajectory = (0..self.trajectory.len().max(other.trajectory.len()))
+            .map(|index| statistics::pool(&point(self, index), trajectory_trials(self), &point(other, index), trajectory_trials(other)))
+            .collect();
+
+        let first_passage = self.first_passage.iter().zip(&other.first_passage)
+            .map(|(first, second)| FirstPassage::new(first.target.clone(), first.steps.iter().chain(&second.steps).copied().collect()))
+            .collect();
+
+        let mut trajectories = self.trajectories.clone();
+        trajectories.extend(other.trajectories.iter().map(|(id, samples)| (id + offset, samples.clone())));
+        let mut cycles = self.cycles.clone();
+        cycles.extend(other.cycles.iter().map(|(id, cycle)| (id + offset, cycle.clone())));
+        let mut stalls = self.stalls.clone();
+        stalls.extend(other.stalls.iter().map(|(id, blocked)| (id + offset, blocked.clone())));
+        let mut alarms = self.alarms.clone();
+        alarms.extend(other.alarms.iter().cloned().map(|mut alarm| {
+            alarm.trial += offset;
+            alarm
+        }));
+
+        let mut reaction_firings: BTreeMap<String, u64> = self.reaction_firings.iter().cloned().collect();
+        for (reaction, count) in &other.reaction_firings {
+            *reaction_firings.entry(reaction.clone()).or_insert(0) += count;
+        }
+        // fluxes and rates are per step outside the stable tails, so each run weighs in with its steps there
+        let flux_steps = |report: &FinalReport| report.trials.iter().map(|summary| (summary.steps - summary.tail_steps) as f64).sum::<f64>();
+        let (steps_a, steps_b) = (flux_steps(self), flux_steps(other));
+        let per_step = |first: &[(String, f64)], second: &[(String, f64)]| -> Vec<(String, f64)> {
+            let mut combined = BTreeMap::<String, f64>::new();
+            for ((name, value), steps) in first.iter().map(|entry| (entry, steps_a)).chain(second.iter().map(|entry| (entry, steps_b))) {
+                *combined.entry(name.clone()).or_insert(0.0) += value * steps;
+            }
+            return combined.into_iter()
+                .map(|(name, weighted)| (name, if steps_a + steps_b > 0.0 {weighted / (steps_a + steps_b)} else {0.0}))
+                .collect();
+        };
+
+        let mut config = self.config.clone();
+        config.trials += other.config.trials;
+        return Ok(FinalReport {
+            averages,
+            statistics: pooled,
+            histograms: self.config.histogram_binning.map_or(Vec::new(), |binning| statistics::histograms(&solutions, binning)),
+            trajectory,
+            trajectories,
+            first_passage,
+            cycles,
+            stalls,
+            alarms,
+            dead_reactions: self.dead_reactions.iter().filter(|reaction| other.dead_reactions.contains(reaction)).cloned().collect(),
+            reaction_firings: reaction_firings.into_iter().collect(),
+            reaction_fluxes: per_step(&self.reaction_fluxes, &other.reaction_fluxes),
+            species_rates: per_step(&self.species_rates, &other.species_rates),
+            correlations: self.config.correlated_species.as_ref().map(|species| statistics::correlations(&solutions, species)),
+            bootstrap: Vec::new(),
+            order_statistics: self.config.percentiles.as_ref().map_or(Vec::new(), |percentiles| statistics::order_statistics(&solutions, percentiles)),
+            num_trials: self.num_trials + other.num_trials,
+            total_reactions_fired: self.total_reactions_fired + other.total_reactions_fired,
+            // the runs are taken to have run side by side
+            wall_time: self.wall_time.max(other.wall_time),
+            partial: self.partial || other.partial,
+            seed: if self.seed == other.seed {self.seed} else {None},
+            network_fingerprint: self.network_fingerprint,
+            config,
+            trials,
+        });
+    }
+
+    /// returns the average of every species in the named compartment of a network built with `Compartments`, 
+    /// under its name within the compartment and in the order of `averages`
+    pub fn compartment(&self, compartment: &str) -> Vec<(String, f64)> {
+        let suffix = format!("{}{}", compartments::SEPARATOR, compartment);
+        return self.averages.iter()
+            .filter_map(|(name, average)| name.strip_suffix(&suffix).map(|species| (species.to_string(), *average)))
+            .collect();
+    }
+
+    /// returns the fraction of trials in which the species died out, 
+    /// None unless it was watched with `Builder::extinction`
+    pub fn extinction_probability(&self, species: &str) -> Option<f64> {
+        return self.first_passage.iter()
+            .find(|passage| {
+                passage.target.get_species().to_string() == species 
+                    && passage.target.get_comparison() == Comparison::Equal 
+                    && passage.target.get_count() == 0
+            })
+            .map(|passage| passage.fraction_reached);
+    }
+
+    /// Writes the recorded trajectories to a long format CSV file with a `trial,step,species,count` header, 
+    /// ready to load into pandas or R. Returns a message describing why the file could not be written
+    pub fn write_trajectories(&self, path: &str) -> Result<(), String> {
+        return SupportedFileType::try_from_path(path.to_string())?.write_trajectories(&self.trajectories);
+    }
+
+    /// Draws the average trajectory of every species with its 95% confidence band to an SVG file,
+    /// panics unless the trajectory was enabled with `Builder::trajectory`
+    #[cfg(feature = "plot")]
+    pub fn plot_trajectory(&self, path: &str) {
+        let Some(interval) = self.config.trajectory_interval else {
+            panic!("tried to plot the trajectory of a run without one, enable it with Builder::trajectory");
+        };
+        plot::trajectory(&self.trajectory, interval, path);
+    }
+
+    /// Draws the histogram of a species' stable count to an SVG file,
+    /// panics unless histograms were enabled with `Builder::histograms`
+    #[cfg(feature = "plot")]
+    pub fn plot_histogram(&self, species: &str, path: &str) {
+        let Some((_, histogram)) = self.histograms.iter().find(|(name, _)| name == species) else {
+            panic!("no histogram of {} to plot, enable them with Builder::histograms", species);
+        };
+        plot::histogram(species, histogram, path);
+    }
+}
+
+/// The settings an engine was built with, recorded in reports so results can be traced back to how they were produced
+#[derive(Debug, Clone, PartialEq)]
+pub struct EngineConfig {
+    pub trials: usize,
+    pub max_runtime: Option<u64>,
+    pub max_semi_stable_steps: Option<i32>,
+    pub max_steps: Option<i32>,
+    pub trial_time_limit: Option<Duration>,
+    pub memory_limit: Option<usize>,
+    pub seed: Option<u64>,
+    pub rng_backend: RngBackend,
+    pub antithetic: bool,
+    pub strata: Vec<Stratum>,
+    pub histogram_binning: Option<Binning>,
+    pub percentiles: Option<Vec<f64>>,
+    /// Species and relative confidence interval width the run stops at
+    pub target_precision: Option<(String, f64)>,
+    pub trajectory_interval: Option<i32>,
+    /// Sampling interval of recorded trajectories and the ids of the trials they are kept for, all trials if None
+    pub recorded_trajectories: Option<(i32, Option<Vec<usize>>)>,
+    pub passage_targets: Vec<PassageTarget>,
+    /// Thresholds raising an alarm the first step a trial crosses them
+    pub alarms: Vec<PassageTarget>,
+    pub correlated_species: Option<Vec<String>>,
+    pub bootstrap_resamples: Option<usize>,
+    /// Species trajectories and timelines are limited to, all species if None
+    pub tracked_species: Option<Vec<String>>,
+    /// Species averages are limited to, all species if empty
+    pub included_species: Vec<SpeciesPattern>,
+    /// Species left out of averages
+    pub excluded_species: Vec<SpeciesPattern>,
+    /// Targets a trial stops at once any of them holds
+    pub until: Vec<PassageTarget>,
+    /// Perturbations applied at fixed steps of every trial
+    pub events: Vec<ScheduledEvent>,
+    pub stall_diagnostics: bool,
+    pub sparse_solutions: bool,
+    pub species_order: SpeciesOrder,
+}
+
+impl EngineConfig {
+    /// returns a hash of every setting which is stable across runs and builds, 
+    /// floats are hashed by their bits and species by name so the result does not depend on how the settings are formatted or stored. 
+    /// A `SpeciesOrder::By` comparison can not be hashed and only counts as set
+    pub(crate) fn fingerprint(&self) -> u64 {
+        let mut fingerprint = Fingerprint(0);
+        fingerprint.word(self.trials as u64);
+        fingerprint.option(self.max_runtime, Fingerprint::word);
+        fingerprint.option(self.max_semi_stable_steps, |fingerprint, steps| fingerprint.word(steps as u64));
+        fingerprint.option(self.max_steps, |fingerprint, steps| fingerprint.word(steps as u64));
+        fingerprint.option(self.trial_time_limit, |fingerprint, limit| {
+            fingerprint.word(limit.as_secs());
+            fingerprint.word(limit.subsec_nanos() as u64);
+        });
+        fingerprint.option(self.memory_limit, |fingerprint, limit| fingerprint.word(limit as u64));
+        fingerprint.option(self.seed, Fingerprint::word);
+        fingerprint.word(match self.rng_backend {
+            RngBackend::Std => 0,
+            RngBackend::Xoshiro256PlusPlus => 1,
+            RngBackend::Pcg64 => 2,
+        });
+        fingerprint.word(self.antithetic as u64);
+        fingerprint.list(&self.strata, |fingerprint, stratum| {
+            fingerprint.float(stratum.get_weight());
+            let mut ranges = stratum.get_ranges();
+            ranges.sort();
+            fingerprint.list(&ranges, |fingerprint, (species, (min, max))| {
+                fingerprint.text(species);
+                fingerprint.count(*min);
+                fingerprint.count(*max);
+            });
+        });
+        fingerprint.option(self.histogram_binning, |fingerprint, binning| match binning {
+            Binning::Width(width) => {
+                fingerprint.word(0);
+                fingerprint.count(width);
+            }
+            Binning::Bins(bins) => {
+                fingerprint.word(1);
+                fingerprint.word(bins as u64);
+            }
+        });
+        fingerprint.option(self.percentiles.as_ref(), |fingerprint, percentiles| fingerprint.list(percentiles, |fingerprint, percentile| fingerprint.float(*percentile)));
+        fingerprint.option(self.target_precision.as_ref(), |fingerprint, (species, relative_ci)| {
+            fingerprint.text(species);
+            fingerprint.float(*relative_ci);
+        });
+        fingerprint.option(self.trajectory_interval, |fingerprint, interval| fingerprint.word(interval as u64));
+        fingerprint.option(self.recorded_trajectories.as_ref(), |fingerprint, (interval, trials)| {
+            fingerprint.word(*interval as u64);
+            fingerprint.option(trials.as_ref(), |fingerprint, trials| fingerprint.list(trials, |fingerprint, trial| fingerprint.word(*trial as u64)));
+        });
+        for targets in [&self.passage_targets, &self.alarms, &self.until] {
+            fingerprint.list(targets, |fingerprint, target| {
+                fingerprint.text(&target.get_species().to_string());
+                fingerprint.word(match target.get_comparison() {
+                    Comparison::Less => 0,
+                    Comparison::LessOrEqual => 1,
+                    Comparison::Equal => 2,
+                    Comparison::GreaterOrEqual => 3,
+                    Comparison::Greater => 4,
+                });
+                fingerprint.count(target.get_count());
+            });
+        }
+        for species in [&self.correlated_species, &self.tracked_species] {
+            fingerprint.option(species.as_ref(), |fingerprint, species| fingerprint.list(species, |fingerprint, name| fingerprint.text(name)));
+        }
+        fingerprint.option(self.bootstrap_resamples, |fingerprint, resamples| fingerprint.word(resamples as u64));
+        for patterns in [&self.included_species, &self.excluded_species] {
+            fingerprint.list(patterns, |fingerprint, pattern| {
+                let (kind, text) = match pattern {
+                    SpeciesPattern::Exact(text) => (0, text),
+                    SpeciesPattern::Prefix(text) => (1, text),
+                    SpeciesPattern::Wildcard(text) => (2, text),
+                };
+                fingerprint.word(kind);
+                fingerprint.text(text);
+            });
+        }
+        fingerprint.list(&self.events, |fingerprint, event| {
+            fingerprint.word(event.get_step() as u64);
+            fingerprint.option(event.get_period(), |fingerprint, period| fingerprint.word(period as u64));
+            fingerprint.text(&event.get_species().to_string());
+            let (kind, count) = match event.get_perturbation() {
+                Perturbation::Set(count) => (0, count),
+                Perturbation::Add(count) => (1, count),
+                Perturbation::Remove(count) => (2, count),
+            };
+            fingerprint.word(kind);
+            fingerprint.count(count);
+        });
+        fingerprint.word(self.stall_diagnostics as u64);
+        fingerprint.word(self.sparse_solutions as u64);
+        fingerprint.word(match self.species_order {
+            SpeciesOrder::Name => 0,
+            SpeciesOrder::DescendingAverage => 1,
+            SpeciesOrder::By(_) => 2,
+        });
+        return fingerprint.0;
+    }
+}
+
+/// Mixes values into a fingerprint one word at a time with SplitMix64, like `ReactionNetwork::fingerprint`
+struct Fingerprint(u64);
+
+impl Fingerprint {
+    fn word(&mut self, word: u64) {
+        self.0 = random::split_mix(self.0 ^ word);
+    }
+
+    fn float(&mut self, value: f64) {
+        self.word(value.to_bits());
+    }
+
+    /// mixes in the bytes of the text followed by a 0, so neighbouring strings can not run into each other
+    fn text(&mut self, text: &str) {
+        for byte in text.bytes().chain([0]) {
+            self.word(byte as u64);
+        }
+    }
+
+    /// a wide count mixes in one 8 byte word at a time
+    fn count(&mut self, count: Count) {
+        for word in count.to_le_bytes().chunks(8) {
+            self.word(u64::from_le_bytes(word.try_into().unwrap()));
+        }
+    }
+
+    fn option<T>(&mut self, value: Option<T>, mix: impl FnOnce(&mut Self, T)) {
+        match value {
+            Some(value) => {
+                self.word(1);
+                mix(self, value);
+            }
+            None => self.word(0),
+        }
+    }
+
+    fn list<T>(&mut self, values: &[T], mix: impl Fn(&mut Self, &T)) {
+        self.word(values.len() as u64);
+        for value in values {
+            mix(self, value);
+        }
+    }
+}
+
+/// A snapshot of how far a run has progressed, passed to the progress callback and sent as `MarleaResult::Progress` each time a trial finishes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    pub trials_completed: usize,
+    pub trials_in_flight: usize,
+    /// Trials the run is set to complete, a precision target may end it sooner
+    pub trials_total: usize,
+    /// Trials which were already complete in the checkpoint the run was resumed from
+    pub trials_resumed: usize,
+    /// Steps taken by all completed trials combined
+    pub total_steps: u64,
+    /// Reactions fired by all completed trials combined
+    pub reactions_fired: u64,
+    /// Time spent by this run, not counting the run a checkpoint was taken from
+    pub elapsed: Duration,
+}
+
+impl Progress {
+    /// returns how many reactions completed trials fired per second of this run, 0 before any time has passed
+    pub fn reactions_per_second(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        return match seconds > 0.0 {
+            true => self.reactions_fired as f64 / seconds,
+            false => 0.0,
+        };
+    }
+
+    /// returns the time the remaining trials should take at the pace trials were completed so far in this run, 
+    /// None until this run completed its first trial
+    pub fn estimated_remaining(&self) -> Option<Duration> {
+        let completed_by_this_run = self.trials_completed.saturating_sub(self.trials_resumed);
+        if completed_by_this_run == 0 {
+            return None;
+        }
+        let remaining = self.trials_total.saturating_sub(self.trials_completed);
+        return Some(self.elapsed.mul_f64(remaining as f64 / completed_by_this_run as f64));
+    }
+}
+
+/// A trial crossing a threshold set with `Builder::alarm`, raised at the first step the threshold holds in that trial
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alarm {
+    pub trial: usize,
+    /// Step the threshold first held at, 0 if it already held in the initial solution
+    pub step: i32,
+    pub threshold: PassageTarget,
+}
+
+/// Selects species by name, see `Builder::include_species` and `Builder::exclude_species`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpeciesPattern {
+    /// Only the species with exactly this name
+    Exact(String),
+    /// Every species whose name starts with the prefix
+    Prefix(String),
+    /// Every species whose name matches the pattern, where `*` stands for any run of characters and `?` for any single one
+    Wildcard(String),
+}
+
+impl SpeciesPattern {
+    pub fn matches(&self, name: &str) -> bool {
+        return match self {
+            Self::Exact(exact) => name == exact,
+            Self::Prefix(prefix) => name.starts_with(prefix.as_str()),
+            Self::Wildcard(pattern) => wildcard_matches(&pattern.chars().collect::<Vec<char>>(), &name.chars().collect::<Vec<char>>()),
+        };
+    }
+}
+
+impl std::fmt::Display for SpeciesPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Exact(exact) => write!(f, "{}", exact),
+            Self::Prefix(prefix) => write!(f, "{}*", prefix),
+            Self::Wildcard(pattern) => write!(f, "{}", pattern),
+        }
+    }
+}
+
+// matches greedily, going back to the last `*` to let it take one more character whenever the rest does not match
+fn wildcard_matches(pattern: &[char], name: &[char]) -> bool {
+    let (mut p, mut n) = (0, 0);
+    let mut last_star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                last_star = Some((p, n));
+                p += 1;
+            }
+            Some(character) if *character == '?' || *character == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match last_star {
+                Some((star, matched)) => {
+                    last_star = Some((star, matched + 1));
+                    p = star + 1;
+                    n = matched + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    return pattern[p..].iter().all(|character| *character == '*');
+}
+
+/// Where a run stands after a call to `MarleaEngine::run_for`
+#[derive(Debug, Clone, PartialEq)]
+pub enum RunStatus {
+    /// Trials are left, the next call continues the run
+    Running { trials_completed: usize, trials_total: usize },
+    /// Every trial is done, or the run stopped early at its runtime limit or target precision
+    Done(Box<FinalReport>),
+}
+
+/// Compares two species by name and average
+pub type CompareAverages = Arc<dyn Fn(&(String, f64), &(String, f64)) -> Ordering + Send + Sync>;
+
+/// How averages are sorted, see `Builder::order_averages`
+#[derive(Clone, Default)]
+pub enum SpeciesOrder {
+    /// Alphabetically by species name
+    #[default]
+    Name,
+    /// Highest average first, species with equal averages by name
+    DescendingAverage,
+    /// By a comparison of two species and their averages, species it finds equal by name
+    By(CompareAverages),
+}
+
+impl SpeciesOrder {
+    /// sorts averages which are already sorted by name
+    pub(crate) fn sort(&self, averages: &mut [(String, f64)]) {
+        match self {
+            Self::Name => {}
+            // the sorts are stable so ties stay in name order
+            Self::DescendingAverage => averages.sort_by(|first, second| second.1.total_cmp(&first.1)),
+            Self::By(compare) => averages.sort_by(|first, second| compare(first, second)),
+        }
+    }
+}
+
+impl PartialEq for SpeciesOrder {
+    fn eq(&self, other: &Self) -> bool {
+        return match (self, other) {
+            (Self::Name, Self::Name) | (Self::DescendingAverage, Self::DescendingAverage) => true,
+            // comparisons can not be told apart beyond being the same closure
+            (Self::By(first), Self::By(second)) => Arc::ptr_eq(first, second),
+            _ => false,
+        };
+    }
+}
+
+impl std::fmt::Debug for SpeciesOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Name => write!(f, "Name"),
+            Self::DescendingAverage => write!(f, "DescendingAverage"),
+            Self::By(_) => write!(f, "By(..)"),
+        }
+    }
+}
+
+/// Messages sent to the frontend while the engine runs
+#[derive(Debug, Clone, PartialEq)]
+pub enum MarleaResult {
+    /// Summary of a trial as soon as it finishes with its id, seed, steps and stable solution, 
+    /// sent before the running mean it adds to. Timed out trials are sent too, flagged by their termination
+    Trial(Box<TrialSummary>),
+    /// Running mean of each species over the trials completed so far, sorted like the averages of the final report
+    Intermediary(Vec<(String, f64)>),
+    /// The name, step and count of a species of the watched trial, sent for every species at step 0 
+    /// and afterwards each time a count changes while the run is in progress, or for every species at each step 
+    /// the `ResponseMode::EveryStep` interval comes around
+    Point(String, i32, Count),
+    /// How far the run has progressed, sent each time a trial finishes right after the running mean
+    Progress(Progress),
+    /// An alarm raised by a trial, sent as soon as the engine hears of it
+    Alarm(Alarm),
+    /// The report `run` returns, sent once all trials are done
+    Final(Box<FinalReport>),
+    /// The error a run started with `run_async` failed with, sent in place of the final report
+    Failed(MarleaEngineError),
+}
+
+/// What the engine does with a trial summary, running average or progress snapshot when the results channel is full because the receiver fell behind. 
+/// These are sent for every trial and called snapshots here. Every other result is always delivered, after any snapshots held back before it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backpressure {
+    /// Waits until the receiver makes room, slowing the simulation down to the pace of the receiver
+    Block,
+    /// Holds back at most the given number of snapshots until there is room, dropping the oldest beyond that
+    DropOldest(usize),
+    /// Holds back only the latest snapshot of each kind, replacing any older one still waiting
+    Latest,
+}
+
+/// Which results the engine sends while a run is in progress, see `Builder::response`. 
+/// Alarms and the final report are sent whichever is chosen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResponseMode {
+    /// Nothing besides alarms and the final report
+    None,
+    /// The summary of each trial as it finishes along with running averages and progress, 
+    /// and the counts of a trial picked with `Builder::watch` each time they change
+    #[default]
+    PerTrial,
+    /// Everything sent per trial, and the count of every species of the watched trial every `every_n` steps counting from step 0. 
+    /// The watched trial is trial 0 unless another one was picked with `Builder::watch`
+    EveryStep { every_n: i32 },
+}
+
+/// A channel the engine sends `MarleaResult`s down
+pub(crate) enum ResultChannel {
+    Std(SyncSender<MarleaResult>),
+    /// Any other channel, wrapped in a closure sending to it
+    Callback(Mutex<Box<dyn FnMut(MarleaResult) + Send>>),
+    #[cfg(feature = "tokio")]
+    Tokio(tokio::sync::mpsc::Sender<MarleaResult>),
+}
+
+impl ResultChannel {
+    fn send(&self, result: MarleaResult) {
+        // a frontend which stopped listening should not bring the simulation down with it
+        match self {
+            Self::Std(sender) => {let _ = sender.send(result);}
+            Self::Callback(send) => (send.lock().unwrap())(result),
+            #[cfg(feature = "tokio")]
+            Self::Tokio(sender) => {let _ = sender.blocking_send(result);}
+        }
+    }
+
+    /// sends the result unless the channel is full, in which case it is handed back. 
+    /// A callback is never full
+    fn try_send(&self, result: MarleaResult) -> Result<(), MarleaResult> {
+        match self {
+            Self::Std(sender) => if let Err(std::sync::mpsc::TrySendError::Full(result)) = sender.try_send(result) {
+                return Err(result);
+            },
+            Self::Callback(send) => (send.lock().unwrap())(result),
+            #[cfg(feature = "tokio")]
+            Self::Tokio(sender) => if let Err(tokio::sync::mpsc::error::TrySendError::Full(result)) = sender.try_send(result) {
+                return Err(result);
+            },
+        }
+        return Ok(());
+    }
+}
+
+/// Where the engine sends `MarleaResult`s, applying the backpressure policy to snapshots
+pub(crate) struct ResultSink {
+    channel: ResultChannel,
+    backpressure: Backpressure,
+    /// Snapshots the channel had no room for, oldest first
+    held_back: Mutex<VecDeque<MarleaResult>>,
+}
+
+impl ResultSink {
+    pub(crate) fn new(channel: ResultChannel, backpressure: Backpressure) -> Self {
+        return Self { channel, backpressure, held_back: Mutex::new(VecDeque::new()) };
+    }
+
+    pub(crate) fn send(&self, result: MarleaResult) {
+        let mut held_back = self.held_back.lock().unwrap();
+        let snapshot = matches!(result, MarleaResult::Trial(_) | MarleaResult::Intermediary(_) | MarleaResult::Progress(_));
+        if self.backpressure == Backpressure::Block || !snapshot {
+            for held_back_result in held_back.drain(..) {
+                self.channel.send(held_back_result);
+            }
+            self.channel.send(result);
+            return;
+        }
+
+        if self.backpressure == Backpressure::Latest {
+            held_back.retain(|held_back_result| std::mem::discriminant(held_back_result) != std::mem::discriminant(&result));
+        }
+        held_back.push_back(result);
+        while let Some(result) = held_back.pop_front() {
+            if let Err(result) = self.channel.try_send(result) {
+                held_back.push_front(result);
+                break;
+            }
+        }
+        if let Backpressure::DropOldest(limit) = self.backpressure {
+            while held_back.len() > limit {
+                held_back.pop_front();
+            }
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use rand::{Rng, RngCore};
+use super::sweep::{Parameter, ParameterValue};
+use super::trial::reaction_network::reaction::term::solution::Count;
+
+/// A global sensitivity analysis over ranges of network parameters.
+///
+/// Parameter values are drawn uniformly from their ranges using Saltelli's scheme, two independent sample matrices
+/// `A` and `B` plus one matrix per parameter where that parameter's column of `A` is taken from `B`.
+/// The engine runs a full simulation at each of the `samples * (parameters + 2)` resulting points,
+/// so the cost grows quickly with both the number of samples and the number of parameters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sensitivity {
+    ranges: Vec<(Parameter, ParameterValue, ParameterValue)>,
+    samples: usize,
+}
+
+/// How much of the variance of an output one parameter is responsible for
+#[derive(Debug, Clone, PartialEq)]
+pub struct SobolIndices {
+    pub parameter: Parameter,
+    /// Share of the output variance explained by the parameter alone
+    pub first_order: f64,
+    /// Share of the output variance the parameter is involved in, including interactions with other parameters
+    pub total: f64,
+}
+
+impl Sensitivity {
+    pub fn new(samples: usize) -> Self {
+        if samples < 2 {
+            panic!("a sensitivity analysis needs at least 2 samples, found {}", samples);
+        }
+        return Self { ranges: Vec::new(), samples };
+    }
+
+    /// Varies the initial count of a species over the whole numbers within `min..=max`
+    pub fn initial_count(self, species: &str, min: Count, max: Count) -> Self {
+        if min > max {
+            panic!("invalid range {}..={} for the count of {}", min, max, species);
+        }
+        return self.range(Parameter::InitialCount(species.to_string()), ParameterValue::Count(min), ParameterValue::Count(max));
+    }
+
+    /// Varies the rate of a reaction within `min..=max`
+    pub fn reaction_rate(self, reaction: &str, min: f64, max: f64) -> Self {
+        if !(min >= 0.0 && max.is_finite() && min <= max) {
+            panic!("invalid range {}..={} for the rate of {}", min, max, reaction);
+        }
+        return self.range(Parameter::ReactionRate(reaction.to_string()), ParameterValue::Rate(min), ParameterValue::Rate(max));
+    }
+
+    fn range(mut self, parameter: Parameter, min: ParameterValue, max: ParameterValue) -> Self {
+        self.ranges.push((parameter, min, max));
+        return self;
+    }
+
+    pub(crate) fn get_parameters(&self) -> Vec<Parameter> {
+        return self.ranges.iter().map(|(parameter, _, _)| parameter.clone()).collect();
+    }
+
+    /// Draws the `A` and `B` sample matrices, each row holding one value per parameter
+    pub(crate) fn sample(&self, rng: &mut dyn RngCore) -> (Vec<Vec<ParameterValue>>, Vec<Vec<ParameterValue>>) {
+        let mut draw = || -> Vec<Vec<ParameterValue>> {
+            return (0..self.samples)
+                .map(|_| self.ranges.iter()
+                    .map(|(_, min, max)| match (min, max) {
+                        // counts are drawn as whole numbers so every count in the range is equally likely
+                        (ParameterValue::Count(min), ParameterValue::Count(max)) => ParameterValue::Count(rng.gen_range(*min..=*max)),
+                        (min, max) => ParameterValue::Rate(rng.gen_range(min.as_f64()..=max.as_f64())),
+                    })
+                    .collect())
+                .collect();
+        };
+        let a = draw();
+        let b = draw();
+        return (a, b);
+    }
+}
+
+/// Estimates the first order and total indices of each parameter from model outputs at the rows of `A`, `B`
+/// and each `AB_i`, using the Saltelli 2010 estimator for first order and Jansen's estimator for total indices.
+/// Parameters are returned ranked by total index, largest first.
+pub(crate) fn indices(parameters: Vec<Parameter>, f_a: &[f64], f_b: &[f64], f_ab: &[Vec<f64>]) -> Vec<SobolIndices> {
+    let samples = f_a.len() as f64;
+    let all_outputs: Vec<f64> = f_a.iter().chain(f_b).copied().collect();
+    let mean = all_outputs.iter().sum::<f64>() / all_outputs.len() as f64;
+    let variance = all_outputs.iter().map(|output| (output - mean).powi(2)).sum::<f64>() / all_outputs.len() as f64;
+
+    let mut indices: Vec<SobolIndices> = parameters.into_iter().zip(f_ab)
+        .map(|(parameter, f_ab_i)| {
+            let first_order = f_b.iter().zip(f_ab_i).zip(f_a)
+                .map(|((b, ab), a)| b * (ab - a))
+                .sum::<f64>() / samples / variance;
+            let total = f_a.iter().zip(f_ab_i)
+                .map(|(a, ab)| (a - ab).powi(2))
+                .sum::<f64>() / (2.0 * samples) / variance;
+            SobolIndices { parameter, first_order, total }
+        })
+        .collect();
+    indices.sort_by(|a, b| b.total.total_cmp(&a.total));
+
+    return indices;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_indices_rank_inert_parameter_last() {
+        // a model which only depends on the first parameter, so AB_1 reproduces B and AB_2 reproduces A
+        let f_a = vec![1.0, 4.0, 2.0, 8.0];
+        let f_b = vec![3.0, 6.0, 5.0, 1.0];
+        let parameters = vec![Parameter::InitialCount("x".to_string()), Parameter::InitialCount("y".to_string())];
+        let ranked = indices(parameters, &f_a, &f_b, &[f_b.clone(), f_a.clone()]);
+
+        assert_eq!(ranked[0].parameter, Parameter::InitialCount("x".to_string()));
+        assert!(ranked[0].total > 0.5);
+        assert_eq!((ranked[1].first_order, ranked[1].total), (0.0, 0.0));
+    }
+}
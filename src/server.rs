@@ -0,0 +1,529 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Condvar, Mutex, mpsc::sync_channel};
+use super::json::Json;
+use super::supported_file_type::parse_reaction;
+use super::websocket;
+use super::trial::reaction_network::{ReactionNetwork, reaction::term::solution::{Count, Species}};
+use super::{Builder, FinalReport, MarleaEngine, MarleaResult, Progress};
+use super::error::BuildError;
+
+/// Runs the engine as a shared HTTP service, simulating each submitted network as a job on threads of its own.
+///
+/// - `POST /jobs` submits a network as JSON and answers with the id of its job, `{"id": 0}`
+/// - `GET /jobs/<id>` answers how far the job has progressed
+/// - `GET /jobs/<id>/events` streams the running averages as server sent events, ending with a `result` event
+/// - `GET /jobs/<id>/ws` streams the same events over a WebSocket, each a text frame holding `{"event": "intermediate", "data": ...}`
+/// - `GET /jobs/<id>/result` answers the result of a finished job, 409 while it is still running 
+///   and 422 with the error of a job whose run failed, which the event streams end with as an `error` event
+///
+/// A network is submitted as
+/// `{"reactions": [{"reaction": "a + b => c", "rate": 1}], "solution": {"a": 50, "b": 30}, "trials": 100, "seed": 42}`.
+/// Species left out of the solution start at 0, and `trials`, `seed` and a `runtime` limit in seconds are optional.
+/// Jobs are kept until the server is dropped. Request bodies larger than 16 MiB are refused with 413 Payload Too Large 
+/// and request lines and headers together larger than 64 KiB with 431 Request Header Fields Too Large. 
+/// Submissions while as many jobs are running as the server allows, see `max_jobs`, are refused with 503 Service Unavailable. 
+/// Jobs asking for more trials or a longer runtime than the server allows, see `max_trials` and `max_runtime`, are refused with 400, 
+/// and jobs without a runtime are stopped at the longest one allowed.
+pub struct Server {
+    listener: TcpListener,
+    jobs: Arc<Mutex<Vec<Arc<Job>>>>,
+    limits: Limits,
+}
+
+/// The most a single job may ask of the server
+#[derive(Debug, Clone, Copy)]
+struct Limits {
+    trials: usize,
+    runtime: u64,
+    /// Jobs which may run at the same time, across all clients
+    jobs: usize,
+}
+
+struct Job {
+    trials: usize,
+    state: Mutex<JobState>,
+    changed: Condvar,
+}
+
+#[derive(Default)]
+struct JobState {
+    progress: Option<Progress>,
+    /// Running averages in the order the engine sent them, written as JSON
+    intermediates: Vec<String>,
+    /// The report of a finished job, or the error of a failed one written as `{"error": ...}`
+    result: Option<String>,
+    failed: bool,
+}
+
+/// Largest request body in bytes the server reads, a network description is far smaller
+const MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
+
+/// Largest request line and headers in bytes the server reads, counted together
+const MAX_HEADER_SIZE: usize = 64 * 1024;
+
+struct Request {
+    method: String,
+    path: String,
+    /// Sec-WebSocket-Key of a client asking to upgrade the connection to a WebSocket
+    websocket_key: Option<String>,
+    body: String,
+}
+
+impl Server {
+    /// Listens on the given address, port 0 picks a free port which `local_addr` then returns
+    pub fn bind(address: impl ToSocketAddrs) -> std::io::Result<Self> {
+        return Ok(Self { listener: TcpListener::bind(address)?, jobs: Arc::new(Mutex::new(Vec::new())), limits: Limits { trials: 100_000, runtime: 3600, jobs: 16 } });
+    }
+
+    /// Sets the most trials a job may run, 100000 by default
+    pub fn max_trials(mut self, trials: usize) -> Self {
+        self.limits.trials = trials;
+        return self;
+    }
+
+    /// Sets the longest runtime in seconds a job may ask for, which jobs without one are stopped at. An hour by default
+    pub fn max_runtime(mut self, seconds: u64) -> Self {
+        self.limits.runtime = seconds;
+        return self;
+    }
+
+    /// Sets how many jobs may run at the same time, 16 by default
+    pub fn max_jobs(mut self, jobs: usize) -> Self {
+        self.limits.jobs = jobs;
+        return self;
+    }
+
+    pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
+        return self.listener.local_addr();
+    }
+
+    /// Answers requests until the listener fails, each connection on a thread of its own
+    pub fn serve(&self) {
+        for stream in self.listener.incoming() {
+            let Ok(stream) = stream else {continue};
+            let (jobs, limits) = (self.jobs.clone(), self.limits);
+            std::thread::spawn(move || handle(stream, &jobs, limits));
+        }
+    }
+}
+
+fn handle(mut stream: TcpStream, jobs: &Mutex<Vec<Arc<Job>>>, limits: Limits) {
+    let request = match read_request(&stream) {
+        Ok(request) => request,
+        Err((status, message)) => return respond(&mut stream, status, &error(&message)),
+    };
+    let segments: Vec<&str> = request.path.trim_matches('/').split('/').collect();
+    let job = |id: &str| -> Option<Arc<Job>> {
+        return id.parse::<usize>().ok().and_then(|id| jobs.lock().unwrap().get(id).cloned());
+    };
+
+    match (request.method.as_str(), segments.as_slice()) {
+        ("POST", ["jobs"]) => match submit(&request.body, limits) {
+            Ok((builder, trials)) => {
+                // the jobs stay locked until the new one is counted, so concurrent submissions can't overshoot the limit
+                let mut jobs = jobs.lock().unwrap();
+                let running = jobs.iter().filter(|job| job.state.lock().unwrap().result.is_none()).count();
+                if running >= limits.jobs {
+                    return respond(&mut stream, "503 Service Unavailable", &error(&format!("the server is already running {} jobs, try again later", running)));
+                }
+                match start(builder, trials) {
+                    Ok(job) => {
+                        let id = jobs.len();
+                        jobs.push(job);
+                        respond(&mut stream, "201 Created", &Json::Object(vec![("id".to_string(), Json::from_integer(id))]).to_string());
+                    }
+                    Err(build_error) => respond(&mut stream, "400 Bad Request", &error(&build_error.to_string())),
+                }
+            }
+            Err(message) => respond(&mut stream, "400 Bad Request", &error(&message)),
+        },
+        ("GET", ["jobs", id]) => match job(id) {
+            Some(job) => respond(&mut stream, "200 OK", &progress(&job)),
+            None => respond(&mut stream, "404 Not Found", &error("no such job")),
+        },
+        ("GET", ["jobs", id, "result"]) => match job(id) {
+            Some(job) => {
+                let state = job.state.lock().unwrap();
+                match (&state.result, state.failed) {
+                    (Some(result), false) => respond(&mut stream, "200 OK", result),
+                    (Some(failure), true) => respond(&mut stream, "422 Unprocessable Entity", failure),
+                    (None, _) => respond(&mut stream, "409 Conflict", &error("the job is still running")),
+                }
+            }
+            None => respond(&mut stream, "404 Not Found", &error("no such job")),
+        },
+        ("GET", ["jobs", id, "events"]) => match job(id) {
+            Some(job) => stream_events(&mut stream, &job),
+            None => respond(&mut stream, "404 Not Found", &error("no such job")),
+        },
+        ("GET", ["jobs", id, "ws"]) => match (job(id), &request.websocket_key) {
+            (Some(job), Some(key)) => stream_websocket(&mut stream, &job, key),
+            (Some(_), None) => respond(&mut stream, "400 Bad Request", &error("missing Sec-WebSocket-Key")),
+            (None, _) => respond(&mut stream, "404 Not Found", &error("no such job")),
+        },
+        _ => respond(&mut stream, "404 Not Found", &error("unknown endpoint")),
+    }
+}
+
+/// reads the request line, the headers and a body of the length given by `Content-Length`, 
+/// returning the status and message to answer with if the request can not be read
+fn read_request(stream: &TcpStream) -> Result<Request, (&'static str, String)> {
+    let bad_request = |message: String| ("400 Bad Request", message);
+    let mut reader = BufReader::new(stream);
+    // the request line and headers share one budget, so neither a single endless line nor endless headers are buffered
+    let mut head = reader.by_ref().take(MAX_HEADER_SIZE as u64);
+    let mut read_line = |line: &mut String| -> Result<(), (&'static str, String)> {
+        line.clear();
+        head.read_line(line).map_err(|error| bad_request(error.to_string()))?;
+        if !line.ends_with('\n') {
+            return Err(match head.limit() {
+                0 => ("431 Request Header Fields Too Large", format!("the request line and headers may be at most {} bytes", MAX_HEADER_SIZE)),
+                _ => bad_request("the connection closed before the end of the headers".to_string()),
+            });
+        }
+        return Ok(());
+    };
+    let mut line = String::new();
+    read_line(&mut line)?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Err(bad_request("malformed request line".to_string()));
+    };
+    let (method, path) = (method.to_string(), path.to_string());
+
+    let mut content_length = 0;
+    let mut websocket_key = None;
+    loop {
+        read_line(&mut line)?;
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().map_err(|_| bad_request("malformed Content-Length".to_string()))?;
+            }
+            if name.eq_ignore_ascii_case("sec-websocket-key") {
+                websocket_key = Some(value.trim().to_string());
+            }
+        }
+    }
+
+    // the length is only allocated once it is known to be reasonable
+    if content_length > MAX_BODY_SIZE {
+        return Err(("413 Payload Too Large", format!("the body may be at most {} bytes", MAX_BODY_SIZE)));
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).map_err(|error| bad_request(error.to_string()))?;
+    let body = String::from_utf8(body).map_err(|_| bad_request("the body is not valid utf-8".to_string()))?;
+    return Ok(Request { method, path, websocket_key, body });
+}
+
+fn respond(stream: &mut TcpStream, status: &str, body: &str) {
+    // the client may already be gone, there is nobody left to tell
+    let _ = write!(stream, "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", status, body.len(), body);
+}
+
+fn error(message: &str) -> String {
+    return Json::Object(vec![("error".to_string(), Json::String(message.to_string()))]).to_string();
+}
+
+/// reads a submitted network into a builder, returning it with the number of trials it runs
+fn submit(body: &str, limits: Limits) -> Result<(Builder, usize), String> {
+    let submission = Json::parse(body)?;
+    let reactions = submission.get("reactions").and_then(Json::as_array).ok_or("missing reactions array")?;
+    let reactions = reactions.iter()
+        .map(|reaction| {
+            let written = reaction.get("reaction").and_then(Json::as_str).ok_or("every reaction needs a reaction string")?;
+            let rate = reaction.get("rate").and_then(Json::as_f64).ok_or("every reaction needs a numeric rate")?;
+            return parse_reaction(written, rate);
+        })
+        .collect::<Result<_, String>>()?;
+
+    let mut solution = MarleaEngine::solution_from(None, &reactions).unwrap();
+    for (name, count) in submission.get("solution").and_then(Json::as_object).unwrap_or_default() {
+        let count: Count = count.as_integer().ok_or_else(|| format!("the count of {} must be a whole number", name))?;
+        solution.species_counts.insert(Species::Name(name.clone()), Species::Count(count));
+    }
+
+    let optional = |key: &str| -> Result<Option<u64>, String> {
+        return match submission.get(key) {
+            None | Some(Json::Null) => Ok(None),
+            Some(value) => value.as_integer().map(Some).ok_or_else(|| format!("{} must be a whole number", key)),
+        };
+    };
+    let trials = optional("trials")?.unwrap_or(100);
+    if trials > limits.trials as u64 {
+        return Err(format!("a job may run at most {} trials, found {}", limits.trials, trials));
+    }
+    let runtime = optional("runtime")?.unwrap_or(limits.runtime);
+    if runtime > limits.runtime {
+        return Err(format!("a job may run for at most {} seconds, found {}", limits.runtime, runtime));
+    }
+    let mut builder = Builder::new(ReactionNetwork::new(reactions, solution)).trials(trials as usize).runtime(runtime);
+    if let Some(seed) = optional("seed")? {
+        builder = builder.seed(seed);
+    }
+    return Ok((builder, trials as usize));
+}
+
+/// builds the engine and runs it on a thread of its own, recording what it sends in a new job
+fn start(builder: Builder, trials: usize) -> Result<Arc<Job>, BuildError> {
+    let job = Arc::new(Job { trials, state: Mutex::new(JobState::default()), changed: Condvar::new() });
+    let (result_sender, result_reciever) = sync_channel(16);
+    let progress_job = job.clone();
+    let engine = builder
+        .on_progress(move |progress| progress_job.state.lock().unwrap().progress = Some(progress))
+        .results(result_sender)
+        .try_build()?;
+    let running_job = job.clone();
+    std::thread::spawn(move || {
+        // the report itself arrives over the results channel
+        if let Err(failure) = engine.try_run() {
+            let mut state = running_job.state.lock().unwrap();
+            state.result = Some(error(&failure.to_string()));
+            state.failed = true;
+            running_job.changed.notify_all();
+        }
+    });
+
+    let recording_job = job.clone();
+    std::thread::spawn(move || {
+        for result in result_reciever {
+            let mut state = recording_job.state.lock().unwrap();
+            match result {
+                MarleaResult::Intermediary(averages) => {
+                    let averages = Json::Object(vec![("averages".to_string(), species_values(&averages))]);
+                    state.intermediates.push(averages.to_string());
+                }
+                MarleaResult::Final(report) => state.result = Some(report_json(&report).to_string()),
+                _ => continue,
+            }
+            recording_job.changed.notify_all();
+        }
+    });
+    return Ok(job);
+}
+
+fn progress(job: &Job) -> String {
+    let state = job.state.lock().unwrap();
+    let progress = state.progress.unwrap_or(Progress { trials_completed: 0, trials_in_flight: 0, trials_total: job.trials, trials_resumed: 0, total_steps: 0, reactions_fired: 0, elapsed: Default::default() });
+    let running = match (&state.result, state.failed) {
+        (Some(_), false) => "done",
+        (Some(_), true) => "failed",
+        (None, _) => "running",
+    };
+    return Json::Object(vec![
+        ("state".to_string(), Json::String(running.to_string())),
+        ("trials".to_string(), Json::from_integer(job.trials)),
+        ("trials_completed".to_string(), Json::from_integer(progress.trials_completed)),
+        ("total_steps".to_string(), Json::from_integer(progress.total_steps)),
+        ("elapsed_ms".to_string(), Json::from_integer(progress.elapsed.as_millis())),
+        ("reactions_per_second".to_string(), Json::from_f64(progress.reactions_per_second())),
+        ("estimated_remaining_ms".to_string(), progress.estimated_remaining().map_or(Json::Null, |remaining| Json::from_integer(remaining.as_millis()))),
+    ]).to_string();
+}
+
+/// passes every running average so far and then each new one as it arrives to `send` along with the name of the event, 
+/// ending after the result or error of the job or as soon as `send` fails
+fn follow(job: &Job, mut send: impl FnMut(&str, &str) -> std::io::Result<()>) {
+    let mut sent = 0;
+    loop {
+        // the lock is released while sending so a slow client can't hold up the engine
+        let (intermediates, result, failed) = {
+            let mut state = job.state.lock().unwrap();
+            while sent == state.intermediates.len() && state.result.is_none() {
+                state = job.changed.wait(state).unwrap();
+            }
+            (state.intermediates[sent..].to_vec(), state.result.clone(), state.failed)
+        };
+        sent += intermediates.len();
+        for intermediate in intermediates {
+            if send("intermediate", &intermediate).is_err() {
+                return;
+            }
+        }
+        if let Some(result) = result {
+            let _ = send(if failed {"error"} else {"result"}, &result);
+            return;
+        }
+    }
+}
+
+fn stream_events(stream: &mut TcpStream, job: &Job) {
+    if write!(stream, "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n").is_err() {
+        return;
+    }
+    follow(job, |event, data| write!(stream, "event: {}\ndata: {}\n\n", event, data));
+}
+
+/// sends each event as a text frame holding `{"event": <name>, "data": <value>}` and closes the connection after the result
+fn stream_websocket(stream: &mut TcpStream, job: &Job, key: &str) {
+    if websocket::accept(stream, key).is_err() {
+        return;
+    }
+    follow(job, |event, data| websocket::write_text(stream, &format!(r#"{{"event":"{}","data":{}}}"#, event, data)));
+    let _ = websocket::write_close(stream);
+}
+
+fn species_values(values: &[(String, f64)]) -> Json {
+    return Json::Object(values.iter().map(|(name, value)| (name.clone(), Json::from_f64(*value))).collect());
+}
+
+fn report_json(report: &FinalReport) -> Json {
+    let statistics = report.statistics.iter()
+        .map(|(name, statistics)| (name.clone(), Json::Object(vec![
+            ("mean".to_string(), Json::from_f64(statistics.mean)),
+            ("variance".to_string(), Json::from_f64(statistics.variance)),
+            ("std_error".to_string(), Json::from_f64(statistics.std_error)),
+            ("confidence_interval".to_string(), Json::Array(vec![
+                Json::from_f64(statistics.confidence_interval.0),
+                Json::from_f64(statistics.confidence_interval.1),
+            ])),
+        ])))
+        .collect();
+    return Json::Object(vec![
+        ("averages".to_string(), species_values(&report.averages)),
+        ("statistics".to_string(), Json::Object(statistics)),
+        ("num_trials".to_string(), Json::from_integer(report.num_trials)),
+        ("total_reactions_fired".to_string(), Json::from_integer(report.total_reactions_fired)),
+        ("wall_time_ms".to_string(), Json::from_integer(report.wall_time.as_millis())),
+        ("partial".to_string(), Json::Bool(report.partial)),
+        ("seed".to_string(), report.seed.map_or(Json::Null, Json::from_integer)),
+    ]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // sends a request and returns the status code and everything the server wrote after the headers
+    fn request(address: SocketAddr, method: &str, path: &str, body: &str) -> (u16, String) {
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(stream, "{} {} HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}", method, path, body.len(), body).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        return (head[9..12].parse().unwrap(), body.to_string());
+    }
+
+    #[test]
+    fn test_job_lifecycle() {
+        let server = Server::bind("127.0.0.1:0").unwrap();
+        let address = server.local_addr().unwrap();
+        std::thread::spawn(move || server.serve());
+
+        let network = r#"{"reactions": [{"reaction": "a => b", "rate": 1}], "solution": {"a": 10}, "trials": 4, "seed": 1}"#;
+        assert_eq!(request(address, "POST", "/jobs", network), (201, r#"{"id":0}"#.to_string()));
+
+        // the event stream only closes once the job is done
+        let (status, events) = request(address, "GET", "/jobs/0/events", "");
+        assert_eq!(status, 200);
+        assert_eq!(events.matches("event: intermediate").count(), 4);
+        assert!(events.contains(r#"event: result
+data: {"averages":{"a":0,"b":10}"#));
+
+        let mut websocket = TcpStream::connect(address).unwrap();
+        write!(websocket, "GET /jobs/0/ws HTTP/1.1\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n").unwrap();
+        let mut frames = Vec::new();
+        websocket.read_to_end(&mut frames).unwrap();
+        assert!(frames.ends_with(&[0x88, 0x00]));
+        let frames = String::from_utf8_lossy(&frames);
+        assert!(frames.starts_with("HTTP/1.1 101 Switching Protocols\r\n"));
+        assert_eq!(frames.matches(r#"{"event":"intermediate","data":{"averages":"#).count(), 4);
+
+        let (status, result) = request(address, "GET", "/jobs/0/result", "");
+        assert_eq!(Json::parse(&result).unwrap().get("num_trials"), Some(&Json::from_integer(4)));
+        assert_eq!(status, 200);
+        assert!(request(address, "GET", "/jobs/0", "").1.starts_with(r#"{"state":"done","trials":4,"trials_completed":4"#));
+
+        assert_eq!(request(address, "POST", "/jobs", r#"{"reactions": [{"reaction": "a b", "rate": 1}]}"#).0, 400);
+        assert_eq!(request(address, "GET", "/jobs/7", "").0, 404);
+    }
+
+    #[test]
+    fn test_failed_job_ends_its_streams() {
+        let server = Server::bind("127.0.0.1:0").unwrap();
+        let address = server.local_addr().unwrap();
+        std::thread::spawn(move || server.serve());
+
+        // producing another a takes its count past the largest count
+        let network = format!(r#"{{"reactions": [{{"reaction": " => a", "rate": 1}}], "solution": {{"a": {}}}, "trials": 2}}"#, Count::MAX);
+        assert_eq!(request(address, "POST", "/jobs", &network).0, 201);
+        let (status, events) = request(address, "GET", "/jobs/0/events", "");
+        assert_eq!(status, 200);
+        assert!(events.contains("event: error\ndata: {\"error\":\"trial "));
+        assert_eq!(request(address, "GET", "/jobs/0/result", "").0, 422);
+        assert!(request(address, "GET", "/jobs/0", "").1.starts_with(r#"{"state":"failed""#));
+    }
+
+    #[test]
+    fn test_job_limits() {
+        let server = Server::bind("127.0.0.1:0").unwrap().max_trials(10).max_runtime(60);
+        let address = server.local_addr().unwrap();
+        std::thread::spawn(move || server.serve());
+
+        let job = |settings: &str| request(address, "POST", "/jobs", &format!(r#"{{"reactions": [{{"reaction": "a => b", "rate": 1}}], {}}}"#, settings));
+        assert_eq!(job(r#""trials": 1000000000000000000"#), (400, error("a job may run at most 10 trials, found 1000000000000000000")));
+        assert_eq!(job(r#""trials": 10, "runtime": 61"#).0, 400);
+        assert_eq!(job(r#""trials": 10, "runtime": 60"#).0, 201);
+    }
+
+    #[test]
+    fn test_oversized_body_is_refused() {
+        let server = Server::bind("127.0.0.1:0").unwrap();
+        let address = server.local_addr().unwrap();
+        std::thread::spawn(move || server.serve());
+
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(stream, "POST /jobs HTTP/1.1\r\nContent-Length: 99999999999999\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 413 Payload Too Large\r\n"));
+    }
+
+    #[test]
+    fn test_oversized_headers_are_refused() {
+        let server = Server::bind("127.0.0.1:0").unwrap();
+        let address = server.local_addr().unwrap();
+        std::thread::spawn(move || server.serve());
+
+        // a single header line which never ends, sent only up to the limit so the server reads all of it before answering
+        let mut stream = TcpStream::connect(address).unwrap();
+        let request_line = "GET /jobs/0 HTTP/1.1\r\nX-Endless: ";
+        write!(stream, "{}{}", request_line, "a".repeat(MAX_HEADER_SIZE - request_line.len())).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 431 Request Header Fields Too Large\r\n"));
+        assert_eq!(request(address, "GET", "/jobs/0", "").0, 404);
+    }
+
+    #[test]
+    fn test_running_jobs_are_limited() {
+        let server = Server::bind("127.0.0.1:0").unwrap().max_jobs(1);
+        let address = server.local_addr().unwrap();
+        // a job which never finishes on its own holds the only slot
+        let running = Arc::new(Job { trials: 1, state: Mutex::new(JobState::default()), changed: Condvar::new() });
+        server.jobs.lock().unwrap().push(running.clone());
+        std::thread::spawn(move || server.serve());
+
+        let network = r#"{"reactions": [{"reaction": "a => b", "rate": 1}], "solution": {"a": 10}, "trials": 2, "seed": 1}"#;
+        assert_eq!(request(address, "POST", "/jobs", network).0, 503);
+        running.state.lock().unwrap().result = Some(String::new());
+        assert_eq!(request(address, "POST", "/jobs", network), (201, r#"{"id":1}"#.to_string()));
+    }
+
+    #[test]
+    fn test_deeply_nested_body_is_refused() {
+        let server = Server::bind("127.0.0.1:0").unwrap();
+        let address = server.local_addr().unwrap();
+        std::thread::spawn(move || server.serve());
+
+        let (status, body) = request(address, "POST", "/jobs", &"[".repeat(MAX_BODY_SIZE));
+        assert_eq!(status, 400);
+        assert!(body.contains("nesting too deep"));
+        // the server is still up
+        assert_eq!(request(address, "GET", "/jobs/0", "").0, 404);
+    }
+}
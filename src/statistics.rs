@@ -0,0 +1,652 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use rand::{Rng, RngCore};
+use super::FinalReport;
+use super::trial::{passage::PassageTarget, results::{Termination, TrialSummary}, strata::Stratum, reaction_network::reaction::term::solution::{Count, Species, Solution}};
+
+/// Two sided 95% quantile of the standard normal distribution
+const Z_95: f64 = 1.959963984540054;
+
+/// Spread of one species' stable count across trials
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpeciesStatistics {
+    pub mean: f64,
+    /// Sample variance of the stable count, NaN when fewer than two trials finished
+    pub variance: f64,
+    pub std_dev: f64,
+    /// Standard error of the mean, accounting for antithetic pairing and strata where used
+    pub std_error: f64,
+    /// Normal approximation of the 95% confidence interval of the mean as (lower, upper)
+    pub confidence_interval: (f64, f64),
+}
+
+impl SpeciesStatistics {
+    fn new(mean: f64, variance: f64, std_error: f64) -> Self {
+        let half_width = Z_95 * std_error;
+        return Self {
+            mean,
+            variance,
+            std_dev: variance.sqrt(),
+            std_error,
+            confidence_interval: (mean - half_width, mean + half_width),
+        };
+    }
+}
+
+/// How the range of stable counts is divided into histogram bins
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Binning {
+    /// Bins of a fixed width, aligned to multiples of the width
+    Width(Count),
+    /// At most this many bins of equal width spanning the observed counts
+    Bins(usize),
+}
+
+/// Number of trials ending with a stable count in each bin.
+/// Bin `i` covers the counts `start + i * bin_width` up to but excluding `start + (i + 1) * bin_width`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Histogram {
+    pub start: Count,
+    pub bin_width: Count,
+    pub counts: Vec<usize>,
+}
+
+impl Histogram {
+    fn new(samples: &[Count], binning: Binning) -> Self {
+        let min = samples.iter().copied().min().unwrap_or(0);
+        let max = samples.iter().copied().max().unwrap_or(0);
+
+        let (start, bin_width) = match binning {
+            Binning::Width(width) => {
+                if width == 0 {
+                    panic!("histogram bin width must be at least 1");
+                }
+                (min - min % width, width)
+            }
+            Binning::Bins(bins) => {
+                if bins == 0 {
+                    panic!("histograms need at least 1 bin");
+                }
+                (min, (max - min) / bins as Count + 1)
+            }
+        };
+
+        let mut counts = vec![0; ((max - start) / bin_width + 1) as usize];
+        for sample in samples {
+            counts[((sample - start) / bin_width) as usize] += 1;
+        }
+        return Self { start, bin_width, counts };
+    }
+
+    /// returns the inclusive range of counts falling into the given bin
+    pub fn get_bin_range(&self, index: usize) -> (Count, Count) {
+        let lower = self.start + index as Count * self.bin_width;
+        return (lower, lower + self.bin_width - 1);
+    }
+}
+
+/// Order statistics of one species' stable count across trials
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderStatistics {
+    pub min: Count,
+    pub median: f64,
+    pub max: Count,
+    /// Each requested percentile paired with its value, interpolated linearly between the closest ranks
+    pub percentiles: Vec<(f64, f64)>,
+}
+
+impl OrderStatistics {
+    fn new(mut samples: Vec<Count>, percentiles: &[f64]) -> Self {
+        samples.sort_unstable();
+        return Self {
+            min: samples.first().copied().unwrap_or(0),
+            median: percentile(&samples, 50.0),
+            max: samples.last().copied().unwrap_or(0),
+            percentiles: percentiles.iter().map(|p| (*p, percentile(&samples, *p))).collect(),
+        };
+    }
+}
+
+/// Distribution of the step at which trials first met a passage target
+#[derive(Debug, Clone, PartialEq)]
+pub struct FirstPassage {
+    pub target: PassageTarget,
+    /// First passage step of every reported trial in trial id order, None if the trial never met the target
+    pub steps: Vec<Option<i32>>,
+    /// Fraction of trials which met the target
+    pub fraction_reached: f64,
+    /// Mean over the trials which met the target, NaN if none did
+    pub mean_steps: f64,
+    /// Median over the trials which met the target, NaN if none did
+    pub median_steps: f64,
+}
+
+impl FirstPassage {
+    pub(crate) fn new(target: PassageTarget, steps: Vec<Option<i32>>) -> Self {
+        let mut reached: Vec<Count> = steps.iter().flatten().map(|step| *step as Count).collect();
+        reached.sort_unstable();
+        return Self {
+            target,
+            fraction_reached: reached.len() as f64 / steps.len() as f64,
+            mean_steps: reached.iter().sum::<Count>() as f64 / reached.len() as f64,
+            median_steps: percentile(&reached, 50.0),
+            steps,
+        };
+    }
+}
+
+/// Pearson correlation of the stable counts of a set of species across trials
+#[derive(Debug, Clone, PartialEq)]
+pub struct CorrelationMatrix {
+    pub species: Vec<String>,
+    /// `values[i][j]` is the correlation between `species[i]` and `species[j]`, NaN where a species never varied
+    pub values: Vec<Vec<f64>>,
+}
+
+impl CorrelationMatrix {
+    /// returns the correlation between two of the selected species, None if either was not selected
+    pub fn get(&self, first: &str, second: &str) -> Option<f64> {
+        let i = self.species.iter().position(|species| species == first)?;
+        let j = self.species.iter().position(|species| species == second)?;
+        return Some(self.values[i][j]);
+    }
+}
+
+/// Spread of the mean of one species over bootstrap resamples of the completed trials
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BootstrapEstimate {
+    /// Standard deviation of the resampled means
+    pub std_error: f64,
+    /// The 2.5th and 97.5th percentile of the resampled means
+    pub confidence_interval: (f64, f64),
+}
+
+/// Resamples the trials with replacement and returns the bootstrap estimate of every species' mean, sorted by species name.
+/// 
+/// `groups` holds the weight of each stratum and its sampling units, a unit being a single trial or an antithetic pair. 
+/// Units are drawn within their stratum and the stratum means are weighted together the same way the reported mean is.
+pub(crate) fn bootstrap(groups: &[(f64, Vec<Vec<&Solution>>)], resamples: usize, rng: &mut dyn RngCore) -> Vec<(String, BootstrapEstimate)> {
+    let all_solutions: Vec<&Solution> = groups.iter().flat_map(|(_, units)| units.iter().flatten().copied()).collect();
+    let species: Vec<String> = average(&all_solutions).into_iter().map(|(name, _)| name).collect();
+    let total_weight: f64 = groups.iter().map(|(weight, _)| weight).sum();
+
+    // the value of a unit is the mean count of its trials, computed once for every species
+    let unit_values: Vec<Vec<Vec<f64>>> = groups.iter()
+        .map(|(_, units)| units.iter()
+            .map(|unit| species.iter()
+                .map(|name| unit.iter().map(|solution| count_of(solution, name) as f64).sum::<f64>() / unit.len() as f64)
+                .collect())
+            .collect())
+        .collect();
+
+    let mut resampled_means = vec![Vec::with_capacity(resamples); species.len()];
+    for _ in 0..resamples {
+        let mut means = vec![0.0; species.len()];
+        for ((weight, _), units) in groups.iter().zip(&unit_values) {
+            for _ in 0..units.len() {
+                let unit = &units[rng.gen_range(0..units.len())];
+                for (mean, value) in means.iter_mut().zip(unit) {
+                    *mean += weight / total_weight * value / units.len() as f64;
+                }
+            }
+        }
+        for (resampled, mean) in resampled_means.iter_mut().zip(means) {
+            resampled.push(mean);
+        }
+    }
+
+    return species.into_iter().zip(resampled_means)
+        .map(|(name, mut means)| {
+            means.sort_by(|a, b| a.total_cmp(b));
+            let mean = means.iter().sum::<f64>() / means.len() as f64;
+            let estimate = BootstrapEstimate {
+                std_error: sample_variance(&means, mean).sqrt(),
+                confidence_interval: (interpolate(&means, 2.5), interpolate(&means, 97.5)),
+            };
+            (name, estimate)
+        })
+        .collect();
+}
+
+/// How the stable count of a species differs between two runs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Difference {
+    /// Mean of the first run minus mean of the second
+    pub mean_difference: f64,
+    /// Welch's t statistic
+    pub t_statistic: f64,
+    /// Welch–Satterthwaite degrees of freedom
+    pub degrees_of_freedom: f64,
+    /// Two sided p value of Welch's t-test, small values mean the runs likely differ
+    pub p_value: f64,
+    /// Cohen's d, the difference of means in units of the pooled standard deviation
+    pub effect_size: f64,
+}
+
+/// Compares the stable counts of every species the two reports share with Welch's t-test, sorted by species name.
+/// The standard errors of the reports are used, so antithetic pairing and strata are accounted for.
+pub fn compare(first: &FinalReport, second: &FinalReport) -> Vec<(String, Difference)> {
+    let mut differences = Vec::new();
+    for (name, a) in &first.statistics {
+        let Some((_, b)) = second.statistics.iter().find(|(other, _)| other == name) else {
+            continue;
+        };
+
+        let (variance_a, variance_b) = (a.std_error.powi(2), b.std_error.powi(2));
+        let mean_difference = a.mean - b.mean;
+        let t_statistic = mean_difference / (variance_a + variance_b).sqrt();
+        let degrees_of_freedom = (variance_a + variance_b).powi(2) 
+            / (variance_a.powi(2) / (first.num_trials as f64 - 1.0) + variance_b.powi(2) / (second.num_trials as f64 - 1.0));
+
+        differences.push((name.clone(), Difference {
+            mean_difference,
+            t_statistic,
+            degrees_of_freedom,
+            p_value: student_t_two_sided(t_statistic, degrees_of_freedom),
+            effect_size: mean_difference / ((a.variance + b.variance) / 2.0).sqrt(),
+        }));
+    }
+    return differences;
+}
+
+/// Probability of a Student t variable with `degrees_of_freedom` being at least `|t|` away from 0
+fn student_t_two_sided(t: f64, degrees_of_freedom: f64) -> f64 {
+    return regularized_incomplete_beta(degrees_of_freedom / (degrees_of_freedom + t * t), degrees_of_freedom / 2.0, 0.5);
+}
+
+/// The regularized incomplete beta function I_x(a, b), evaluated with Lentz's continued fraction
+fn regularized_incomplete_beta(x: f64, a: f64, b: f64) -> f64 {
+    if x <= 0.0 || x >= 1.0 || x.is_nan() {
+        return x.clamp(0.0, 1.0);
+    }
+    // the continued fraction converges quickly only below the mean of the distribution
+    if x > (a + 1.0) / (a + b + 2.0) {
+        return 1.0 - regularized_incomplete_beta(1.0 - x, b, a);
+    }
+
+    let front = (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp() / a;
+    let tiny = 1e-300;
+    let (mut c, mut d) = (1.0, 1.0 - (a + b) * x / (a + 1.0));
+    d = 1.0 / if d.abs() < tiny {tiny} else {d};
+    let mut fraction = d;
+    for m in 1..300 {
+        let m = m as f64;
+        for numerator in [
+            m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m)),
+            -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0)),
+        ] {
+            d = 1.0 + numerator * d;
+            d = 1.0 / if d.abs() < tiny {tiny} else {d};
+            c = 1.0 + numerator / c;
+            c = if c.abs() < tiny {tiny} else {c};
+            fraction *= c * d;
+        }
+        if (c * d - 1.0).abs() < 1e-15 {
+            break;
+        }
+    }
+    return front * fraction;
+}
+
+/// Lanczos approximation of the log gamma function
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 6] = [76.18009172947146, -86.50532032941677, 24.01409824083091, -1.231739572450155, 0.1208650973866179e-2, -0.5395239384953e-5];
+    let tmp = x + 5.5 - (x + 0.5) * (x + 5.5).ln();
+    let series: f64 = 1.000000000190015 + COEFFICIENTS.iter().enumerate().map(|(i, c)| c / (x + 1.0 + i as f64)).sum::<f64>();
+    return -tmp + (2.5066282746310005 * series / x).ln();
+}
+
+/// Fewest independent samples a precision target is checked against, so a lucky start can not end a run early
+const MIN_PRECISION_SAMPLES: usize = 10;
+
+/// Watches the confidence interval of one species while trials complete.
+/// 
+/// Only the longest run of completed trials starting at id 0 is considered, 
+/// so the number of trials a target is reached at depends on the seeds alone and not on the order trials finished in. 
+/// Antithetic pairs enter as a single sample once both halves are done.
+pub(crate) struct PrecisionTracker {
+    species: String,
+    relative_ci: f64,
+    paired: bool,
+    next_id: usize,
+    count: usize,
+    sum: f64,
+    sum_squares: f64,
+}
+
+impl PrecisionTracker {
+    pub(crate) fn new(species: String, relative_ci: f64, paired: bool) -> Self {
+        return Self { species, relative_ci, paired, next_id: 0, count: 0, sum: 0.0, sum_squares: 0.0 };
+    }
+
+    /// Takes in any newly contiguous trials and returns the number of trials needed to reach the target, if it has been reached
+    pub(crate) fn advance(&mut self, completed_trials: &BTreeMap<usize, TrialSummary>) -> Option<usize> {
+        let step = if self.paired {2} else {1};
+        while (self.next_id..self.next_id + step).all(|id| completed_trials.contains_key(&id)) {
+            let unit: Vec<&TrialSummary> = (self.next_id..self.next_id + step).map(|id| &completed_trials[&id]).collect();
+            self.next_id += step;
+            if unit.iter().any(|summary| summary.termination == Termination::TimedOut) {
+                continue;
+            }
+            let sample = unit.iter()
+                .map(|summary| count_of(&summary.solution, &self.species) as f64)
+                .sum::<f64>() / step as f64;
+            self.count += 1;
+            self.sum += sample;
+            self.sum_squares += sample * sample;
+
+            if self.count >= MIN_PRECISION_SAMPLES && self.relative_half_width() < self.relative_ci {
+                return Some(self.next_id);
+            }
+        }
+        return None;
+    }
+
+    fn relative_half_width(&self) -> f64 {
+        let count = self.count as f64;
+        let mean = self.sum / count;
+        let variance = (self.sum_squares - count * mean * mean).max(0.0) / (count - 1.0);
+        return Z_95 * (variance / count).sqrt() / mean.abs();
+    }
+}
+
+/// Averages species counts across trials.
+/// Counts are summed as integers so the result is exact and does not depend on the order trials finished in,
+/// panics if the sum of a species leaves the range of a u128, which wide counts make possible.
+pub(crate) fn average(simulation_results: &[&Solution]) -> Vec<(String, f64)> {
+    let mut summed_values = HashMap::<String, u128>::new();
+
+    // Sum values of each species across all trials
+    for result in simulation_results {
+        add_to_sums(&mut summed_values, result);
+    }
+
+    return sums_to_averages(&summed_values, simulation_results.len());
+}
+
+pub(crate) fn add_to_sums(summed_values: &mut HashMap<String, u128>, solution: &Solution) {
+    for (name, count) in &solution.species_counts {
+        if let Species::Name(species_name) = name {
+            if let Species::Count(species_count) = count  {
+                let sum = summed_values.entry(species_name.clone()).or_insert(0);
+                // the cast only widens when counts are u64
+                #[allow(clippy::unnecessary_cast)]
+                let species_count = *species_count as u128;
+                *sum = sum.checked_add(species_count)
+                    .unwrap_or_else(|| panic!("sum of the counts of {} across trials overflowed", species_name));
+            }
+        } else {
+            panic!("Got non-species name when calculating averages");
+        }
+    }
+}
+
+pub(crate) fn sums_to_averages(summed_values: &HashMap<String, u128>, num_trials: usize) -> Vec<(String, f64)> {
+    // Calculate averages and sort alphabetically
+    let mut averaged_values: Vec<(String, f64)> = summed_values
+                    .iter()
+                    .map(|(key, value)| (key.clone(), *value as f64 / num_trials as f64))
+                    .collect();
+    averaged_values.sort_by_key(|(species, _)| species.to_owned());
+
+    return averaged_values;
+}
+
+/// Computes the statistics of every species over the given trials, sorted by species name.
+/// When `paired` is set consecutive solutions are antithetic pairs, their spread is still measured per trial
+/// but the standard error is taken over pair means since the two halves of a pair are not independent.
+pub(crate) fn summarize(simulation_results: &[&Solution], paired: bool) -> Vec<(String, SpeciesStatistics)> {
+    let averages = average(simulation_results);
+
+    let mut statistics = Vec::new();
+    for (name, mean) in averages {
+        let samples: Vec<f64> = simulation_results.iter().map(|solution| count_of(solution, &name) as f64).collect();
+        let variance = sample_variance(&samples, mean);
+        let std_error = match paired {
+            true => {
+                let pair_means: Vec<f64> = samples.chunks(2).map(|pair| pair.iter().sum::<f64>() / pair.len() as f64).collect();
+                (sample_variance(&pair_means, mean) / pair_means.len() as f64).sqrt()
+            }
+            false => (variance / samples.len() as f64).sqrt(),
+        };
+        statistics.push((name, SpeciesStatistics::new(mean, variance, std_error)));
+    }
+
+    return statistics;
+}
+
+/// Combines per stratum statistics into statistics of the whole initial condition space.
+/// The mean and the standard error follow the stratified estimator, the variance is that of the weighted mixture of strata.
+/// Strata which produced no results are left out and the remaining weights are renormalized.
+pub(crate) fn combine_strata(strata: &[Stratum], stratum_statistics: Vec<Option<Vec<(String, SpeciesStatistics)>>>) -> Vec<(String, SpeciesStatistics)> {
+    let total_weight: f64 = strata.iter().zip(&stratum_statistics)
+        .filter(|(_, statistics)| statistics.is_some())
+        .map(|(stratum, _)| stratum.get_weight())
+        .sum();
+
+    let mut per_species = HashMap::<String, Vec<(f64, SpeciesStatistics)>>::new();
+    for (stratum, statistics) in strata.iter().zip(stratum_statistics) {
+        for (name, species_statistics) in statistics.into_iter().flatten() {
+            per_species.entry(name).or_default().push((stratum.get_weight() / total_weight, species_statistics));
+        }
+    }
+
+    let mut combined: Vec<(String, SpeciesStatistics)> = per_species.into_iter()
+        .map(|(name, strata)| {
+            let mean: f64 = strata.iter().map(|(weight, statistics)| weight * statistics.mean).sum();
+            let variance = strata.iter()
+                .map(|(weight, statistics)| weight * (statistics.variance + (statistics.mean - mean).powi(2)))
+                .sum();
+            let std_error = strata.iter()
+                .map(|(weight, statistics)| (weight * statistics.std_error).powi(2))
+                .sum::<f64>()
+                .sqrt();
+            (name, SpeciesStatistics::new(mean, variance, std_error))
+        })
+        .collect();
+    combined.sort_by(|a, b| a.0.cmp(&b.0));
+
+    return combined;
+}
+
+/// Pools the statistics of two independent sets of trials of the same network weighted by their number of trials, sorted by species name. 
+/// A species missing from one set is taken to be at 0 throughout it, as it would be in sparse solutions.
+pub(crate) fn pool(first: &[(String, SpeciesStatistics)], first_trials: usize, second: &[(String, SpeciesStatistics)], second_trials: usize) -> Vec<(String, SpeciesStatistics)> {
+    if first_trials == 0 || second_trials == 0 {
+        return match first_trials {
+            0 => second.to_vec(),
+            _ => first.to_vec(),
+        };
+    }
+
+    let absent = SpeciesStatistics::new(0.0, 0.0, 0.0);
+    let (n_a, n_b) = (first_trials as f64, second_trials as f64);
+    let total = n_a + n_b;
+    let names: BTreeSet<&String> = first.iter().chain(second).map(|(name, _)| name).collect();
+    return names.into_iter()
+        .map(|name| {
+            let find = |statistics: &[(String, SpeciesStatistics)]| statistics.iter().find(|(other, _)| other == name).map_or(absent, |(_, statistics)| *statistics);
+            let (a, b) = (find(first), find(second));
+            let mean = (n_a * a.mean + n_b * b.mean) / total;
+            // the squared deviations of both sets add up once the difference between their means is accounted for, a single trial has none
+            let squares = |statistics: &SpeciesStatistics, n: f64| if n > 1.0 {statistics.variance * (n - 1.0)} else {0.0};
+            let variance = (squares(&a, n_a) + squares(&b, n_b) + (a.mean - b.mean).powi(2) * n_a * n_b / total) / (total - 1.0);
+            // a weighted mean of two independent means, which keeps the smaller standard error of antithetic pairs and strata
+            let std_error = match a.std_error.is_finite() && b.std_error.is_finite() {
+                true => ((n_a * a.std_error).powi(2) + (n_b * b.std_error).powi(2)).sqrt() / total,
+                false => (variance / total).sqrt(),
+            };
+            (name.clone(), SpeciesStatistics::new(mean, variance, std_error))
+        })
+        .collect();
+}
+
+/// Bins the stable count of every species across the given trials, sorted by species name.
+/// Each trial counts once regardless of the weight of the stratum it was drawn from.
+pub(crate) fn histograms(simulation_results: &[&Solution], binning: Binning) -> Vec<(String, Histogram)> {
+    return average(simulation_results).into_iter()
+        .map(|(name, _)| {
+            let samples: Vec<Count> = simulation_results.iter().map(|solution| count_of(solution, &name)).collect();
+            (name, Histogram::new(&samples, binning))
+        })
+        .collect();
+}
+
+/// Computes the min, median, max and requested percentiles of every species across the given trials, sorted by species name.
+/// Like histograms each trial counts once regardless of its stratum.
+pub(crate) fn order_statistics(simulation_results: &[&Solution], percentiles: &[f64]) -> Vec<(String, OrderStatistics)> {
+    return average(simulation_results).into_iter()
+        .map(|(name, _)| {
+            let samples: Vec<Count> = simulation_results.iter().map(|solution| count_of(solution, &name)).collect();
+            (name, OrderStatistics::new(samples, percentiles))
+        })
+        .collect();
+}
+
+/// Correlates the stable counts of the given species across trials, each trial counting once regardless of its stratum
+pub(crate) fn correlations(simulation_results: &[&Solution], species: &[String]) -> CorrelationMatrix {
+    let samples: Vec<Vec<f64>> = species.iter()
+        .map(|name| simulation_results.iter().map(|solution| count_of(solution, name) as f64).collect())
+        .collect();
+    let means: Vec<f64> = samples.iter().map(|counts| counts.iter().sum::<f64>() / counts.len() as f64).collect();
+    let covariance = |i: usize, j: usize| -> f64 {
+        return samples[i].iter().zip(&samples[j]).map(|(x, y)| (x - means[i]) * (y - means[j])).sum();
+    };
+
+    let values = (0..species.len())
+        .map(|i| (0..species.len()).map(|j| covariance(i, j) / (covariance(i, i) * covariance(j, j)).sqrt()).collect())
+        .collect();
+    return CorrelationMatrix { species: species.to_vec(), values };
+}
+
+/// Returns the `p`th percentile of sorted samples, NaN if there are none
+fn percentile(sorted_samples: &[Count], p: f64) -> f64 {
+    return interpolate(&sorted_samples.iter().map(|sample| *sample as f64).collect::<Vec<f64>>(), p);
+}
+
+/// Returns the `p`th percentile of sorted values, interpolating linearly between the closest ranks
+fn interpolate(sorted_values: &[f64], p: f64) -> f64 {
+    if sorted_values.is_empty() {
+        return f64::NAN;
+    }
+    let rank = p / 100.0 * (sorted_values.len() - 1) as f64;
+    let lower = sorted_values[rank.floor() as usize];
+    let upper = sorted_values[rank.ceil() as usize];
+    return lower + (upper - lower) * rank.fract();
+}
+
+fn count_of(solution: &Solution, name: &str) -> Count {
+    return match solution.species_counts.get(&Species::Name(name.to_string())) {
+        Some(Species::Count(count)) => *count,
+        _ => 0,
+    };
+}
+
+fn sample_variance(samples: &[f64], mean: f64) -> f64 {
+    if samples.len() < 2 {
+        return f64::NAN;
+    }
+    return samples.iter().map(|sample| (sample - mean).powi(2)).sum::<f64>() / (samples.len() - 1) as f64;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trial::results::Termination;
+
+    fn solution(count: Count) -> Solution {
+        return Solution { species_counts: HashMap::from([(Species::Name("a".to_string()), Species::Count(count))]) };
+    }
+
+    #[test]
+    fn test_summarize() {
+        let solutions: Vec<Solution> = [2, 4, 4, 4, 5, 5, 7, 9].into_iter().map(solution).collect();
+        let statistics = summarize(&solutions.iter().collect::<Vec<&Solution>>(), false);
+        let (name, a) = &statistics[0];
+        assert_eq!(name, "a");
+        assert_eq!(a.mean, 5.0);
+        assert_eq!(a.variance, 32.0 / 7.0);
+        assert_eq!(a.std_error, (32.0f64 / 7.0 / 8.0).sqrt());
+        assert!(a.confidence_interval.0 < 5.0 && a.confidence_interval.1 > 5.0);
+    }
+
+    #[test]
+    fn test_combine_strata() {
+        let strata = vec![Stratum::new(1.0), Stratum::new(1.0)];
+        let low = summarize(&[&solution(0), &solution(2)], false);
+        let high = summarize(&[&solution(8), &solution(10)], false);
+        let (_, a) = combine_strata(&strata, vec![Some(low), Some(high)]).remove(0);
+        assert_eq!(a.mean, 5.0);
+        // within strata variance of 2 plus the spread of the stratum means around the overall mean
+        assert_eq!(a.variance, 2.0 + 16.0);
+    }
+
+    #[test]
+    fn test_order_statistics() {
+        let solutions: Vec<Solution> = [7, 1, 3, 100, 5].into_iter().map(solution).collect();
+        let (_, a) = order_statistics(&solutions.iter().collect::<Vec<&Solution>>(), &[25.0, 87.5]).remove(0);
+        assert_eq!((a.min, a.median, a.max), (1, 5.0, 100));
+        assert_eq!(a.percentiles, vec![(25.0, 3.0), (87.5, 53.5)]);
+    }
+
+    #[test]
+    fn test_precision_tracker() {
+        let summary = |id: usize, count: Count| (id, TrialSummary { id, seed: 0, steps: 0, reactions_fired: 0, reaction_firings: Vec::new(), tail_firings: Vec::new(), tail_steps: 0, termination: Termination::Stable, solution: solution(count) });
+        let mut tracker = PrecisionTracker::new("a".to_string(), 0.1, false);
+
+        // trial 0 is missing so nothing after it counts yet
+        let mut completed_trials: BTreeMap<usize, TrialSummary> = (1..20).map(|id| summary(id, 100)).collect();
+        assert_eq!(tracker.advance(&completed_trials), None);
+
+        completed_trials.extend([summary(0, 100)]);
+        assert_eq!(tracker.advance(&completed_trials), Some(10));
+    }
+
+    #[test]
+    fn test_correlations() {
+        let solutions: Vec<Solution> = [(1, 10, 5), (2, 8, 5), (3, 6, 5)].into_iter()
+            .map(|(a, b, c)| Solution { species_counts: HashMap::from([
+                (Species::Name("a".to_string()), Species::Count(a)),
+                (Species::Name("b".to_string()), Species::Count(b)),
+                (Species::Name("c".to_string()), Species::Count(c)),
+            ])})
+            .collect();
+        let species = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let matrix = correlations(&solutions.iter().collect::<Vec<&Solution>>(), &species);
+        assert_eq!(matrix.get("a", "a"), Some(1.0));
+        assert_eq!(matrix.get("a", "b"), Some(-1.0));
+        assert!(matrix.get("a", "c").unwrap().is_nan());
+        assert_eq!(matrix.get("a", "d"), None);
+    }
+
+    #[test]
+    fn test_student_t() {
+        // reference values of the two sided t distribution
+        assert!((student_t_two_sided(2.228, 10.0) - 0.05).abs() < 1e-3);
+        assert!((student_t_two_sided(1.96, 1e6) - 0.05).abs() < 1e-3);
+        assert_eq!(student_t_two_sided(0.0, 5.0), 1.0);
+    }
+
+    #[test]
+    fn test_bootstrap() {
+        let solutions: Vec<Solution> = (0..20).map(solution).collect();
+        let units = solutions.iter().map(|solution| vec![solution]).collect();
+        let mut rng = crate::trial::random::RngBackend::Pcg64.seeded(1);
+        let (name, estimate) = bootstrap(&[(1.0, units)], 200, rng.as_mut()).remove(0);
+        assert_eq!(name, "a");
+        assert!(estimate.confidence_interval.0 < 9.5 && estimate.confidence_interval.1 > 9.5);
+        assert!(estimate.std_error > 0.0);
+    }
+
+    #[test]
+    fn test_histograms() {
+        let solutions: Vec<Solution> = [3, 4, 12, 13, 14].into_iter().map(solution).collect();
+        let solutions: Vec<&Solution> = solutions.iter().collect();
+
+        let (_, by_width) = histograms(&solutions, Binning::Width(5)).remove(0);
+        assert_eq!((by_width.start, by_width.counts.clone()), (0, vec![2, 0, 3]));
+        assert_eq!(by_width.get_bin_range(1), (5, 9));
+
+        let (_, by_bins) = histograms(&solutions, Binning::Bins(2)).remove(0);
+        assert_eq!((by_bins.start, by_bins.bin_width, by_bins.counts), (3, 6, vec![2, 3]));
+    }
+}
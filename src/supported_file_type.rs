@@ -1,10 +1,12 @@
-use crate::trial::reaction_network::reaction::{Reaction, term::{Term, solution::Species}};
+use crate::trial::reaction_network::reaction::{Reaction, term::{Term, solution::{Count, Species}}};
 use csv::ReaderBuilder;
-use std::sync::mpsc::Receiver;
+use std::sync::{Arc, mpsc::Receiver};
 use std::path::Path;
-use std::collections::{HashMap, HashSet};
-use super::trial::reaction_network::reaction::term::solution::Solution;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use super::log::{self, Level};
+use super::trial::{results::TrialResult, reaction_network::reaction::term::solution::Solution};
 
+#[allow(clippy::upper_case_acronyms)]
 pub enum SupportedFileType {
 CSV(String),
 JSON(String),
@@ -12,94 +14,103 @@ XML(String),
 Unsuported(String),
 }
 
+/// Parses a reaction written `a + 2 b => c`, catalysts written in brackets like `[destruct]` are required but given back as products.
+/// A species named more than once on a side, including as a catalyst, has its coefficients added up
+pub(crate) fn parse_reaction(reaction: &str, rate: f64) -> Result<Reaction, String> {
+    if !rate.is_finite() || rate < 0.0 {
+        return Err(format!("Invalid reaction rate {} for [{}], rates must be finite and not negative", rate, reaction));
+    }
+    let mut reactants: BTreeMap<String, u64> = BTreeMap::new();
+    let mut products: BTreeMap<String, u64> = BTreeMap::new();
+    let add = |side: &mut BTreeMap<String, u64>, term: &Term| {
+        *side.entry(term.get_species_name().to_string()).or_insert(0) += term.get_coefficient();
+    };
+
+    // Find "=>" and split sides
+    let sides: Vec<&str> = reaction.split("=>").collect();
+    if sides.len() != 2 {
+        return Err(format!("Invalid reaction format - expected 'reactants => products' but received [{}]", reaction));
+    }
+
+    for term_string in sides[0].split('+') {
+        let trimmed = term_string.trim();
+        if let Some(catalyst) = trimmed.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+            if let Some(term) = Term::from(catalyst) {
+                add(&mut reactants, &term);
+                add(&mut products, &term);
+            }
+        } else if let Some(term) = Term::from(term_string) {add(&mut reactants, &term);}
+    }
+    for term_string in sides[1].split('+') {
+        if let Some(term) = Term::from(term_string) {add(&mut products, &term);}
+    }
+
+    let terms = |side: BTreeMap<String, u64>| -> Result<HashSet<Term>, String> {
+        return side.into_iter()
+            .map(|(name, coefficient)| match u8::try_from(coefficient) {
+                Ok(coefficient) => Ok(Term::new(name, coefficient)),
+                Err(_) => Err(format!("Coefficient {} of {} in [{}] is larger than {}", coefficient, name, reaction, u8::MAX)),
+            })
+            .collect();
+    };
+    return Ok(Reaction::new(terms(reactants)?, terms(products)?, rate));
+}
+
 impl SupportedFileType {
     pub fn from(file_path: String) -> Self{
+        return Self::try_from_path(file_path).unwrap_or_else(|error| panic!("{}", error));
+    }
+
+    /// The same as `from` but returns an error for a path without a file extension
+    pub fn try_from_path(file_path: String) -> Result<Self, String> {
         // get file extension to determine the type
         let extension = Path::new(&file_path)
             .extension()
             .and_then(|os_str| os_str.to_str());
 
         match extension {
-            Some("csv") => Self::CSV(file_path),
-            Some("json") => Self::JSON(file_path),
-            Some("xml") => Self::XML(file_path),
-            Some(other_file_type) => Self::Unsuported(other_file_type.to_string()),
-            _=> panic!("no_file_extension_found")
+            Some("csv") => Ok(Self::CSV(file_path)),
+            Some("json") => Ok(Self::JSON(file_path)),
+            Some("xml") => Ok(Self::XML(file_path)),
+            Some(other_file_type) => Ok(Self::Unsuported(other_file_type.to_string())),
+            _=> Err("no_file_extension_found".to_string())
         }
     }
 
-    // A function that parses a file into a `ReactionNetworkParts` enum Type
-    pub fn parse_reactions(&self) -> HashSet<Reaction> {
+    // A function that parses a file into a `ReactionNetworkParts` enum Type, 
+    // returns a message describing the first problem with the file
+    pub fn parse_reactions(&self) -> Result<HashSet<Reaction>, String> {
         // Handle different types of supported files, starts here with CSV
         match self {
             Self::CSV(path) => {
                 // Read and create CSVReader object
-                let reader = ReaderBuilder::new()
+                let mut reader = ReaderBuilder::new()
                     .has_headers(false)
                     .delimiter(b',')  
-                    .from_path(path); 
-                
-                match reader {
-                    Ok(mut reader) => { // If file exists and was opened successfully
-                        
-                        // Filter empty rows out of the returned reader and panic if reader encounters an error
-                        let records = reader.records().filter_map(
-                            |record|
-                            match record {
-                                Ok(mut string) => {
-                                    string.trim();
-                                    if string[0].is_empty() && string[1].is_empty() {
-                                        None
-                                    } else {
-                                        Some(string)
-                                    }
-                                }
-                                Err(error) => {panic!("{}", error)}
-                            }
-                        );
-                        let mut reactions = HashSet::new();
-                        
-                        for record in records {
-                            let mut reactants: HashSet<Term> = HashSet::new();
-                            let mut products: HashSet<Term> = HashSet::new();
-                            
-                            // Find "=>" and split sides
-                            let sides: Vec<&str> = record[0].split("=>").collect();
-                            if sides.len() != 2 {
-                                panic!("Invalid reaction format - expected 'reactants => products' but received [{}]", record[0].to_string());
-                            }
-                            let left_side: Vec<&str> = sides[0].split('+').collect();
-                            let right_side: Vec<&str> = sides[1].split('+').collect();
-
-
-                            
-                            // Split left side fields into space sign delimited sub fields and parse as reactants
-                            for term_string in left_side {
-                                if let Some(term) = Term::from(term_string) {reactants.insert(term);}
-
-                            }             
+                    .from_path(path)
+                    .map_err(|error| error.to_string())?; 
+                let mut reactions = HashSet::new();
 
-                            // Split right side fields into space delimited subfields and parse as products
-                            for term_string in right_side {
-                                if let Some(term) = Term::from(term_string) {products.insert(term);}
-                            }
+                for record in reader.records() {
+                    let mut record = record.map_err(|error| error.to_string())?;
+                    record.trim();
+                    // Skip empty rows
+                    let (reaction, rate_str) = (record.get(0).unwrap_or(""), record.get(1).unwrap_or(""));
+                    if reaction.is_empty() && rate_str.is_empty() {
+                        continue;
+                    }
 
-                            // Parse the last field as reaction_rate
-                            let rate_str = record[1].trim();
-                            let rate = rate_str.parse::<u64>().unwrap_or_else(|_| panic!("Invalid reaction rate '{}' provided", record[1].to_string()));
+                    // Parse the last field as reaction_rate
+                    let rate = rate_str.parse::<f64>().map_err(|_| format!("Invalid reaction rate '{}' provided", rate_str))?;
 
-                            reactions.insert(Reaction::new(reactants, products, rate));
-                        }
+                    reactions.insert(parse_reaction(reaction, rate)?);
+                }
 
-                        return reactions; 
-                        
-                    }
-                    Err(error) => panic!("{}", error),
-                } // end of inner match { OK(CSVReader) vs. Error(String) }  
+                return Ok(reactions);
             }, // End of handling CSV files
-            Self::JSON(_path) => todo!(),
-            Self::XML(_path) => todo!(), 
-            Self::Unsuported(file_type) => panic!("Unsupported file type: found {}, expects CSV", file_type), 
+            Self::JSON(_path) => Err("JSON network files are not supported yet, expects CSV".to_string()),
+            Self::XML(_path) => Err("XML network files are not supported yet, expects CSV".to_string()),
+            Self::Unsuported(file_type) => Err(format!("Unsupported file type: found {}, expects CSV", file_type)), 
             
 
         } //  End of outer match {Self} (SupportedFileType enum Type)
@@ -107,7 +118,8 @@ impl SupportedFileType {
     
     /// Parses initial solution from a reaction network based on the file type (CSV, JSON, XML) 
     /// Self: is a parsed set of reactions which will be added to solution with count of 0 if not specieifed in init data
-    pub fn parse_initial_solution(&self, initial_solution: &mut HashMap<Species, Species>) {
+    /// Returns a message describing why the file could not be read
+    pub fn parse_initial_solution(&self, initial_solution: &mut HashMap<Species, Species>) -> Result<(), String> {
 
         // Match and handle different file types
         match self {
@@ -138,55 +150,168 @@ impl SupportedFileType {
                         
                             // Ignoring spaces parse first non empty field as Species::name
                             let mut species_name = Species::Name(String::new());
-                            if let Some(name_str) = fields.get(0).map(|s| s.trim()).filter(|s| !s.is_empty()) {
+                            if let Some(name_str) = fields.first().map(|s| s.trim()).filter(|s| !s.is_empty()) {
                                 species_name = Species::Name(name_str.to_owned());
                             }
                         
                             // Ignoring spaces parse second non empty field as Species::count
                             let mut species_count = Species::Count(0);
                             if let Some(count_str) = fields.get(1).map(|s| s.trim()).filter(|s| !s.is_empty()) {
-                                if let Ok(count_int) = count_str.parse::<u64>() {
+                                if let Ok(count_int) = count_str.parse::<Count>() {
                                     species_count = Species::Count(count_int);
                                 }
                             }
                         
                             // If any is present, parse third non empty field as species threshold
-                            if let Some(_threshold_str) = fields.get(2).map(|s| s.trim()).filter(|s| !s.is_empty()) {
-                                unimplemented!()
+                            if let Some(threshold_str) = fields.get(2).map(|s| s.trim()).filter(|s| !s.is_empty()) {
+                                return Err(format!("Species thresholds are not supported yet: found '{}' for {}", threshold_str, species_name));
                             }
 
                             // Add species name and data t
                             initial_solution.entry(species_name)
                                 .and_modify(|count| *count = species_count);
                         }
+                        return Ok(());
                     },
-                    Err(error) => panic!("error occurred while reading csv file: {}", error), // Handle reader error here
+                    Err(error) => Err(format!("error occurred while reading csv file: {}", error)), // Handle reader error here
                 }
             }
-            Self::XML(_path) => unimplemented!(),
-            Self::JSON(_path) => unimplemented!(),
-            Self::Unsuported(file_type) => panic!("Unsupported file type: found {}, expects CSV", file_type), 
+            Self::XML(_path) => Err("XML initial solution files are not supported yet, expects CSV".to_string()),
+            Self::JSON(_path) => Err("JSON initial solution files are not supported yet, expects CSV".to_string()),
+            Self::Unsuported(file_type) => Err(format!("Unsupported file type: found {}, expects CSV", file_type)), 
         }
     }
 
+    /// Parses an input signal of `time, count` rows sorted by time, a first row which is not a number is taken as a header. 
+    /// Counts are rounded to whole molecules. Returns a message describing the first problem with the file
+    pub fn parse_signal(&self) -> Result<Vec<(f64, Count)>, String> {
+        match self {
+            Self::CSV(path) => {
+                let mut reader = ReaderBuilder::new()
+                    .has_headers(false)
+                    .delimiter(b',')
+                    .from_path(path)
+                    .map_err(|error| format!("error occurred while reading csv file: {}", error))?;
 
+                let mut signal = Vec::new();
+                for (index, record) in reader.records().enumerate() {
+                    let record = record.map_err(|error| error.to_string())?;
+                    let fields: Vec<&str> = record.iter().map(|field| field.trim()).collect();
+                    if fields.iter().all(|field| field.is_empty()) {
+                        continue;
+                    }
+                    let time = fields[0].parse::<f64>();
+                    if index == 0 && time.is_err() {
+                        continue;
+                    }
+                    let time = time.ok().filter(|time| time.is_finite())
+                        .ok_or_else(|| format!("Invalid time '{}' in input signal", fields[0]))?;
+                    // a count past the largest Count would silently saturate when rounded
+                    let count = fields.get(1).and_then(|count| count.parse::<f64>().ok())
+                        .filter(|count| count.is_finite() && *count >= 0.0 && *count <= Count::MAX as f64)
+                        .ok_or_else(|| format!("Invalid count '{}' in input signal, counts must be finite and not negative", fields.get(1).unwrap_or(&"")))?;
+                    signal.push((time, count.round() as Count));
+                }
+                signal.sort_by(|first, second| first.0.total_cmp(&second.0));
+                return Ok(signal);
+            }
+            Self::XML(_path) => Err("XML input signal files are not supported yet, expects CSV".to_string()),
+            Self::JSON(_path) => Err("JSON input signal files are not supported yet, expects CSV".to_string()),
+            Self::Unsuported(file_type) => Err(format!("Unsupported file type: found {}, expects CSV", file_type)),
+        }
+    }
 
-    pub fn write_solution(&self, stable_solution: Vec<(String, f64)>) {
+    /// Writes the averaged stable solution as `species,average` rows. 
+    /// Returns a message describing why the file could not be written
+    pub fn write_solution(&self, stable_solution: Vec<(String, f64)>) -> Result<(), String> {
         match self {
             Self::CSV(path) => {
-                let mut output_file = csv::WriterBuilder::new().from_path(path).unwrap();
+                let write_error = |error: csv::Error| format!("error occurred while writing csv file {}: {}", path, error);
+                let mut output_file = csv::WriterBuilder::new().from_path(path).map_err(write_error)?;
                 
                 for entry in stable_solution  {
-                    output_file.write_record([entry.0, entry.1.to_string()]).unwrap();
+                    output_file.write_record([entry.0, entry.1.to_string()]).map_err(write_error)?;
+                }
+                output_file.flush().map_err(|error| format!("error occurred while writing csv file {}: {}", path, error))?;
+                return Ok(());
+            },
+            Self::JSON(_path) => Err("JSON result files are not supported yet, expects CSV".to_string()),
+            Self::XML(_path) => Err("XML result files are not supported yet, expects CSV".to_string()),
+            Self::Unsuported(file_type) => Err(format!("Unsupported file type: found {}, expects CSV", file_type)),
+        }
+    }
+
+    /// Writes reactions in the same format `parse_reactions` reads, sorted so equal networks produce identical files. 
+    /// Returns a message describing why the file could not be written
+    pub fn write_reactions(&self, reactions: &BTreeSet<Reaction>) -> Result<(), String> {
+        match self {
+            Self::CSV(path) => {
+                let write_error = |error: csv::Error| format!("error occurred while writing csv file {}: {}", path, error);
+                let mut output_file = csv::WriterBuilder::new().from_path(path).map_err(write_error)?;
+
+                let mut records: Vec<[String; 2]> = reactions.iter()
+                    .map(|reaction| [reaction.to_string(), reaction.get_reaction_rate().to_string()])
+                    .collect();
+                records.sort();
+                for record in records {
+                    output_file.write_record(record).map_err(write_error)?;
                 }
+                output_file.flush().map_err(|error| format!("error occurred while writing csv file {}: {}", path, error))?;
+                return Ok(());
             },
-            Self::JSON(_path) => todo!(), // implement JSON writing
-            Self::XML(_path) => todo!(), // implement XML writing
-            Self::Unsuported(other_file_type) => panic!("tried to write unsuported file type {}", other_file_type),
+            Self::JSON(_path) => Err("JSON network files are not supported yet, expects CSV".to_string()),
+            Self::XML(_path) => Err("XML network files are not supported yet, expects CSV".to_string()),
+            Self::Unsuported(file_type) => Err(format!("Unsupported file type: found {}, expects CSV", file_type)),
+        }
+    }
+
+    /// Writes a solution in the same format `parse_initial_solution` reads. 
+    /// Returns a message describing why the file could not be written
+    pub fn write_initial_solution(&self, solution: &Solution) -> Result<(), String> {
+        match self {
+            Self::CSV(path) => {
+                let write_error = |error: csv::Error| format!("error occurred while writing csv file {}: {}", path, error);
+                let mut output_file = csv::WriterBuilder::new().from_path(path).map_err(write_error)?;
+
+                for (name, count) in solution.iter() {
+                    output_file.write_record([name.to_string(), count.to_string()]).map_err(write_error)?;
+                }
+                output_file.flush().map_err(|error| format!("error occurred while writing csv file {}: {}", path, error))?;
+                return Ok(());
+            },
+            Self::JSON(_path) => Err("JSON initial solution files are not supported yet, expects CSV".to_string()),
+            Self::XML(_path) => Err("XML initial solution files are not supported yet, expects CSV".to_string()),
+            Self::Unsuported(file_type) => Err(format!("Unsupported file type: found {}, expects CSV", file_type)),
+        }
+    }
+
+    /// Writes recorded trajectories in long format, one `trial,step,species,count` row per species and sample. 
+    /// Returns a message describing why the file could not be written
+    pub fn write_trajectories(&self, trajectories: &[(usize, Vec<(i32, Solution)>)]) -> Result<(), String> {
+        match self {
+            Self::CSV(path) => {
+                let write_error = |error: csv::Error| format!("error occurred while writing csv file {}: {}", path, error);
+                let mut output_file = csv::WriterBuilder::new().from_path(path).map_err(write_error)?;
+
+                output_file.write_record(["trial", "step", "species", "count"]).map_err(write_error)?;
+                for (id, samples) in trajectories {
+                    for (step, solution) in samples {
+                        for (name, count) in solution.iter() {
+                            output_file.write_record([id.to_string(), step.to_string(), name.to_string(), count.to_string()]).map_err(write_error)?;
+                        }
+                    }
+                }
+                output_file.flush().map_err(|error| format!("error occurred while writing csv file {}: {}", path, error))?;
+                return Ok(());
+            },
+            Self::JSON(_path) => Err("JSON trajectory files are not supported yet, expects CSV".to_string()),
+            Self::XML(_path) => Err("XML trajectory files are not supported yet, expects CSV".to_string()),
+            Self::Unsuported(file_type) => Err(format!("Unsupported file type: found {}, expects CSV", file_type)),
         }
     }
 }
 
+#[allow(clippy::upper_case_acronyms)]
 enum WriterType {
     CSV{writer: csv::Writer<std::fs::File>, header_written: bool},
 }
@@ -196,7 +321,7 @@ impl WriterType {
         match file {
             SupportedFileType::CSV(path) => {
                 let mut path_with_id = id.to_string();
-                path_with_id.push_str(&path);
+                path_with_id.push_str(path);
 
                 return WriterType::CSV{
                     writer: csv::WriterBuilder::new()
@@ -212,19 +337,43 @@ impl WriterType {
 pub struct TimelineWriter {
     timeline_file: SupportedFileType,
     temp_sub_files: HashMap<usize, WriterType>,
-    step_stream: Receiver<(Solution, usize)>,
+    running_solutions: HashMap<usize, Arc<Solution>>,
+    step_stream: Receiver<TrialResult>,
 }
 
 impl TimelineWriter {
-    pub fn new(file: SupportedFileType, step_stream: Receiver<(Solution, usize)>) -> Self {
+    pub fn new(file: SupportedFileType, step_stream: Receiver<TrialResult>) -> Self {
         let temp_sub_files = HashMap::new();
+        let running_solutions = HashMap::new();
 
-        return TimelineWriter {timeline_file: file, temp_sub_files, step_stream};
+        return TimelineWriter {timeline_file: file, temp_sub_files, running_solutions, step_stream};
     }
     pub fn begin_listen(mut self) {
         loop {
             match self.step_stream.recv() {
-                Ok((solution, id)) => {
+                Ok(entry) => {
+
+                    // reconstruct the running solution of the trial from full entries and deltas
+                    let id = match entry {
+                        TrialResult::TimelineEntry(solution, id) => {
+                            self.running_solutions.insert(id, solution);
+                            id
+                        }
+                        TrialResult::TimelineDelta(changes, id) => {
+                            if let Some(running_solution) = self.running_solutions.get_mut(&id) {
+                                let running_solution = Arc::make_mut(running_solution);
+                                for (name, count) in changes {
+                                    running_solution.species_counts.insert(name, count);
+                                }
+                            }
+                            id
+                        }
+                        TrialResult::StableSolution(..) | TrialResult::Trajectory(..) | TrialResult::FirstPassage(..) | TrialResult::Alarm(..) | TrialResult::Cycle(..) | TrialResult::Failed(..) | TrialResult::Interrupted(..) => continue,
+                    };
+                    let solution = match self.running_solutions.get(&id) {
+                        Some(solution) => solution,
+                        None => continue,
+                    };
 
                     // ensure that a wiriter has been generated for given ID
                     if !self.temp_sub_files.contains_key(&id) {
@@ -240,7 +389,7 @@ impl TimelineWriter {
                             if !*header_written {
                                 let mut names = Vec::new();
                                 let mut counts = Vec::new();
-                                for (species_name, species_count) in solution.into_iter() {
+                                for (species_name, species_count) in solution.iter() {
                                     names.push(species_name.to_string());
                                     counts.push(species_count.to_string());
                                 }
@@ -253,7 +402,7 @@ impl TimelineWriter {
                             // otherwise just ignore them completely and write the species as a new record
                             else {
                                 let mut counts = Vec::new();
-                                for (_species_name, species_count) in solution.into_iter() {
+                                for (_species_name, species_count) in solution.iter() {
                                     counts.push(species_count.to_string());
                                 }
                                 writer.write_record(counts).unwrap();
@@ -263,7 +412,7 @@ impl TimelineWriter {
                     });
                 }
                 Err(_msg) => {
-                    println!("Stream to Timeline Writer dropped\nShutting down...");
+                    log::log(Level::Debug, format_args!("stream to timeline writer dropped, shutting down"));
                     // combining files is unimplemented
                     return;
                 }
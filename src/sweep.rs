@@ -0,0 +1,152 @@
+use super::FinalReport;
+use super::trial::reaction_network::{ReactionNetwork, reaction::term::solution::Count};
+
+/// A network setting a sweep varies
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Parameter {
+    /// The initial count of the named species
+    InitialCount(String),
+    /// The rate of a reaction, written `a + 2 b => c` with terms sorted by name
+    ReactionRate(String),
+}
+
+/// The value a parameter is set to. Counts are kept whole rather than passing through f64, 
+/// which can not hold every count of the `wide_counts` feature
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParameterValue {
+    Count(Count),
+    Rate(f64),
+}
+
+impl ParameterValue {
+    /// returns the value as f64, rounding counts too large to hold exactly
+    pub fn as_f64(&self) -> f64 {
+        return match self {
+            ParameterValue::Count(count) => *count as f64,
+            ParameterValue::Rate(rate) => *rate,
+        };
+    }
+}
+
+impl std::fmt::Display for ParameterValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParameterValue::Count(count) => write!(f, "{}", count),
+            ParameterValue::Rate(rate) => write!(f, "{}", rate),
+        }
+    }
+}
+
+impl Parameter {
+    /// sets the parameter in the network, panics if the network has no such species or reaction 
+    /// or the value is of the wrong kind for the parameter
+    fn apply(&self, network: &mut ReactionNetwork, value: ParameterValue) {
+        let applied = match (self, value) {
+            (Parameter::InitialCount(species), ParameterValue::Count(count)) => network.set_count(species, count).is_some(),
+            (Parameter::ReactionRate(reaction), ParameterValue::Rate(rate)) => network.set_rate(reaction, rate).is_some(),
+            (parameter, value) => panic!("can not set {} to {}", parameter, value),
+        };
+        if !applied {
+            panic!("can not vary {} which is not in the network", self);
+        }
+    }
+}
+
+impl std::fmt::Display for Parameter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Parameter::InitialCount(species) => write!(f, "count({})", species),
+            Parameter::ReactionRate(reaction) => write!(f, "rate({})", reaction),
+        }
+    }
+}
+
+/// A grid of parameter values to run the full simulation at.
+///
+/// # Example
+/// ```no_run
+/// use marlea_engine::{Builder, Sweep};
+///
+/// let engine = Builder::from_files("network.csv".to_string(), None).trials(100).seed(1).build();
+/// let sweep = Sweep::new()
+///     .initial_count("a", vec![10, 20, 40])
+///     .reaction_rate("a + b => c", vec![0.5, 1.0, 10.0]);
+/// for point in engine.sweep(&sweep) {
+///     println!("{:?} {:?}", point.parameters, point.report.averages);
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Sweep {
+    axes: Vec<(Parameter, Vec<ParameterValue>)>,
+}
+
+impl Sweep {
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    /// Varies the initial count of a species over the given values
+    pub fn initial_count(mut self, species: &str, values: Vec<Count>) -> Self {
+        self.axes.push((Parameter::InitialCount(species.to_string()), values.into_iter().map(ParameterValue::Count).collect()));
+        return self;
+    }
+
+    /// Varies the rate of a reaction over the given values
+    pub fn reaction_rate(mut self, reaction: &str, values: Vec<f64>) -> Self {
+        self.axes.push((Parameter::ReactionRate(reaction.to_string()), values.into_iter().map(ParameterValue::Rate).collect()));
+        return self;
+    }
+
+    /// returns every combination of parameter values, the last added parameter varying fastest
+    pub fn grid(&self) -> Vec<Vec<(Parameter, ParameterValue)>> {
+        let mut grid = vec![Vec::new()];
+        for (parameter, values) in &self.axes {
+            grid = grid.into_iter()
+                .flat_map(|point: Vec<(Parameter, ParameterValue)>| values.iter().map(move |value| {
+                    let mut point = point.clone();
+                    point.push((parameter.clone(), *value));
+                    point
+                }))
+                .collect();
+        }
+        return grid;
+    }
+
+    /// returns a copy of the network with the parameters of a grid point applied
+    pub(crate) fn network_at(network: &ReactionNetwork, point: &[(Parameter, ParameterValue)]) -> ReactionNetwork {
+        let mut network = network.clone();
+        for (parameter, value) in point {
+            parameter.apply(&mut network, *value);
+        }
+        return network;
+    }
+}
+
+/// The report of a simulation run at one point of a sweep
+#[derive(Debug, Clone, PartialEq)]
+pub struct SweepPoint {
+    pub parameters: Vec<(Parameter, ParameterValue)>,
+    pub report: FinalReport,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grid() {
+        let sweep = Sweep::new().initial_count("a", vec![1, 2]).reaction_rate("a => b", vec![0.5, 6.0, 7.25]);
+        let grid = sweep.grid();
+        assert_eq!(grid.len(), 6);
+        assert_eq!(grid[1], vec![(Parameter::InitialCount("a".to_string()), ParameterValue::Count(1)), (Parameter::ReactionRate("a => b".to_string()), ParameterValue::Rate(6.0))]);
+    }
+
+    #[cfg(feature = "wide_counts")]
+    #[test]
+    fn test_wide_counts_are_swept_exactly() {
+        // 2^64 + 1 has no f64 of its own and would be set as 2^64
+        let count = u64::MAX as Count + 2;
+        let grid = Sweep::new().initial_count("a", vec![count]).grid();
+        assert_eq!(grid[0][0].1, ParameterValue::Count(count));
+    }
+}
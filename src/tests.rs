@@ -0,0 +1,1144 @@
+use std::collections::{HashMap, HashSet};
+use super::*;
+use trial::{reaction_network::reaction::term::{Term, solution::Count}, results::Termination, schedule::Perturbation, strata::Stratum};
+
+/// returns a path in the temporary directory unique to this test process, so concurrent test runs do not share files
+fn temp_path(name: &str) -> std::path::PathBuf {
+    return std::env::temp_dir().join(format!("marlea_test_{}_{}", std::process::id(), name));
+}
+
+/// Builds a small network where `a + b => c`, `c => a` and `2 a => d` compete, so trials do not all end the same way
+fn competing_network() -> ReactionNetwork {
+    let term = |name: &str, coefficient: u8| Term::new(name.to_string(), coefficient);
+    let mut reactions = HashSet::new();
+    reactions.insert(Reaction::new(HashSet::from([term("a", 1), term("b", 1)]), HashSet::from([term("c", 1)]), 1.0));
+    reactions.insert(Reaction::new(HashSet::from([term("c", 1)]), HashSet::from([term("a", 1)]), 1.0));
+    reactions.insert(Reaction::new(HashSet::from([term("a", 2)]), HashSet::from([term("d", 1)]), 2.0));
+
+    return ReactionNetwork::new(reactions, Solution::from_pairs(&[("a", 50), ("b", 30), ("c", 0), ("d", 0)]));
+}
+
+#[test]
+fn test_seeded_runs_are_reproducible() {
+    let first = Builder::new(competing_network()).trials(10).seed(42).build().run();
+    let second = Builder::new(competing_network()).trials(10).seed(42).build().run();
+    assert_eq!(first.averages, second.averages);
+    assert_eq!(first.trials, second.trials);
+    assert_eq!(first.total_reactions_fired, second.total_reactions_fired);
+    assert_eq!((first.num_trials, first.seed), (10, Some(42)));
+}
+
+#[test]
+fn test_seeded_runs_ignore_scheduling() {
+    // a memory limit of a single trial forces trials to run one after another rather than all at once
+    let single_trial = competing_network().estimated_size();
+    let sequential = Builder::new(competing_network()).trials(10).seed(7).memory_limit(single_trial).build().run();
+    let concurrent = Builder::new(competing_network()).trials(10).seed(7).build().run();
+    assert_eq!(sequential.averages, concurrent.averages);
+}
+
+#[test]
+fn test_memory_limit_counts_trajectories() {
+    let network_size = competing_network().estimated_size();
+    let unbounded = Builder::new(competing_network()).memory_limit(10 * network_size).trajectory(10).try_build();
+    assert_eq!(unbounded.err(), Some(BuildError::UnboundedRecording));
+
+    // the network alone fits, but a sample every step up to the step limit does not
+    let recording = Builder::new(competing_network()).trials(2).memory_limit(10 * network_size).trajectory(1).max_steps(1000).build();
+    assert!(matches!(recording.try_run(), Err(MarleaEngineError::MemoryLimit { trial_size, .. }) if trial_size > 10 * network_size));
+    assert!(Builder::new(competing_network()).trials(2).memory_limit(10 * network_size).max_steps(1000).build().try_run().is_ok());
+}
+
+#[test]
+fn test_pause_and_resume_matches_uninterrupted_run() {
+    let builder = || Builder::new(competing_network()).trials(300).seed(11);
+    // the first trial to finish holds the run up until the pause is requested
+    let (started_sender, started) = std::sync::mpsc::sync_channel(0);
+    let (proceed, proceed_receiver) = std::sync::mpsc::sync_channel(0);
+    let proceed_receiver = Mutex::new(proceed_receiver);
+    // with room for 2 live trials most of the run is still ahead when it pauses
+    let trial_size = builder().build().estimated_trial_size();
+    let engine = builder().memory_limit(2 * trial_size).on_progress(move |progress| {
+        if progress.trials_completed == 1 {
+            started_sender.send(()).unwrap();
+            proceed_receiver.lock().unwrap().recv().unwrap();
+        }
+    }).build();
+
+    let checkpoint = std::thread::scope(|scope| {
+        let run = scope.spawn(|| engine.run());
+        started.recv().unwrap();
+        let pause = scope.spawn(|| engine.pause());
+        while !engine.pause_state.lock().unwrap().requested {
+            std::thread::yield_now();
+        }
+        proceed.send(()).unwrap();
+        let checkpoint = pause.join().unwrap();
+        run.join().unwrap();
+        checkpoint
+    }).expect("run finished before it could be paused");
+    assert!(checkpoint.completed_trials.len() <= 2);
+
+    // round trip the checkpoint through a file like a preempted job would
+    let path = temp_path("checkpoint.csv");
+    checkpoint.write(path.to_str().unwrap());
+    let checkpoint = Checkpoint::read(path.to_str().unwrap());
+    std::fs::remove_file(&path).unwrap();
+
+    let resumed = engine.resume(checkpoint);
+    let uninterrupted = Builder::new(competing_network()).trials(300).seed(11).build().run();
+    assert_eq!(resumed.averages, uninterrupted.averages);
+    assert_eq!(resumed.reaction_firings, uninterrupted.reaction_firings);
+}
+
+#[test]
+fn test_observer_sees_every_trial() {
+    use std::sync::atomic::AtomicUsize;
+    use trial::observer::StepObserver;
+
+    #[derive(Default)]
+    struct Counter {
+        reactions: AtomicUsize,
+        completed: AtomicUsize,
+    }
+
+    impl StepObserver for Arc<Counter> {
+        fn on_reaction_fired(&self, _trial_id: usize, _reaction: &Reaction, _step: i32) {
+            self.reactions.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn on_trial_complete(&self, _trial_id: usize, _solution: &Solution) {
+            self.completed.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    let counter = Arc::new(Counter::default());
+    let report = Builder::new(competing_network()).trials(10).seed(3).observer(Box::new(counter.clone())).build().run();
+    assert_eq!(counter.completed.load(Ordering::Relaxed), 10);
+    assert_eq!(counter.reactions.load(Ordering::Relaxed) as u64, report.total_reactions_fired);
+}
+
+#[test]
+fn test_target_precision_stops_early_and_ignores_scheduling() {
+    let single_trial = competing_network().estimated_size();
+    let sequential = Builder::new(competing_network()).trials(1000).seed(5).target_precision("d", 0.05).memory_limit(single_trial).build().run();
+    let concurrent = Builder::new(competing_network()).trials(1000).seed(5).target_precision("d", 0.05).build().run();
+    assert!(sequential.num_trials < 1000);
+    assert_eq!(sequential.num_trials, concurrent.num_trials);
+    assert_eq!(sequential.averages, concurrent.averages);
+}
+
+#[test]
+fn test_trajectory_starts_at_initial_solution() {
+    let report = Builder::new(competing_network()).trials(10).seed(9).trajectory(5).build().run();
+    let longest = report.trials.iter().map(|summary| summary.steps).max().unwrap();
+    assert_eq!(report.trajectory.len() as i32, longest / 5 + 1);
+
+    let (name, a) = &report.trajectory[0][0];
+    assert_eq!((name.as_str(), a.mean, a.variance), ("a", 50.0, 0.0));
+}
+
+#[test]
+fn test_first_passage() {
+    use trial::passage::Comparison;
+
+    let report = Builder::new(competing_network()).trials(10).seed(4)
+        .first_passage("a", Comparison::LessOrEqual, 50)
+        .first_passage("b", Comparison::Equal, 0)
+        .build().run();
+
+    // the initial solution already meets the first target
+    assert_eq!(report.first_passage[0].steps, vec![Some(0); 10]);
+    for (passage, summary) in report.first_passage[1].steps.iter().zip(&report.trials) {
+        let b_used_up = summary.solution.species_counts[&Species::Name("b".to_string())] == Species::Count(0);
+        assert_eq!(passage.is_some(), b_used_up);
+    }
+}
+
+#[test]
+fn test_sweep_matches_separate_runs() {
+    let sweep = Sweep::new().initial_count("b", vec![0, 30]).reaction_rate("2 a => d", vec![2.0, 50.0]);
+    let engine = Builder::new(competing_network()).trials(10).seed(8).build();
+    let points = engine.sweep(&sweep);
+    assert_eq!(points.len(), 4);
+
+    // with no b nothing but 2 a => d can fire, so all 50 a end up as 25 d
+    let averages = &points[0].report.averages;
+    assert!(averages.contains(&("d".to_string(), 25.0)));
+
+    let mut network = competing_network();
+    network.set_count("b", 30);
+    network.set_rate("2 a => d", 50.0);
+    let separate = Builder::new(network).trials(10).seed(8).build().run();
+    assert_eq!(points[3].report.averages, separate.averages);
+}
+
+#[test]
+fn test_fit_improves_on_initial_rates() {
+    let engine = Builder::new(competing_network()).trials(10).seed(6).build();
+    let fit = Fit::new().rate("2 a => d", 2.0).target("b", 20.0).max_evaluations(20);
+    let initial = engine.build_report(engine.run_trials(&engine.prime_network, None));
+    let result = engine.fit(&fit);
+    assert!(result.distance < fit.distance(&initial));
+    assert!(result.evaluations <= 20);
+    // rates are searched continuously rather than rounded to whole numbers
+    assert!(result.rates[0].1.fract() != 0.0);
+}
+
+#[test]
+fn test_fit_keeps_rates_finite() {
+    let report = Builder::new(competing_network()).trials(2).seed(6).build().run();
+    let fit = Fit::new().rate("2 a => d", f64::MAX).rate("a + b => c", f64::MIN_POSITIVE).target("b", 20.0).max_evaluations(10);
+    let result = fit.minimize(|rates| {
+        assert!(rates.iter().all(|rate| rate.is_finite() && *rate > 0.0), "simulated rates {:?}", rates);
+        return report.clone();
+    });
+    assert!(result.rates.iter().all(|(_, rate)| rate.is_finite() && *rate > 0.0));
+}
+
+#[test]
+fn test_compare_distinguishes_networks() {
+    let reference = Builder::new(competing_network()).trials(40).seed(1).build().run();
+    let same = Builder::new(competing_network()).trials(40).seed(2).build().run();
+    let mut network = competing_network();
+    network.set_rate("2 a => d", 20.0);
+    let changed = Builder::new(network).trials(40).seed(1).build().run();
+
+    let p_value = |differences: Vec<(String, Difference)>| differences.into_iter().find(|(name, _)| name == "b").unwrap().1.p_value;
+    assert!(p_value(compare(&reference, &same)) > 0.01);
+    assert!(p_value(compare(&reference, &changed)) < 0.01);
+}
+
+#[test]
+fn test_get_timeline_replays_trial() {
+    let engine = Builder::new(competing_network()).trials(4).seed(12).build();
+    let report = engine.run();
+    for summary in &report.trials {
+        let timeline = engine.get_timeline(summary.id, summary.seed);
+        for (name, points) in timeline {
+            let last = points.last().unwrap();
+            assert_eq!(last.step, summary.steps);
+            assert_eq!(summary.solution.species_counts[&Species::Name(name)], Species::Count(last.count));
+        }
+    }
+}
+
+#[test]
+fn test_recorded_trajectories_end_on_stable_solution() {
+    let report = Builder::new(competing_network()).trials(6).seed(13).record_trajectories_of(7, &[1, 4]).build().run();
+    let ids: Vec<usize> = report.trajectories.iter().map(|(id, _)| *id).collect();
+    assert_eq!(ids, vec![1, 4]);
+    for (id, samples) in &report.trajectories {
+        let summary = &report.trials[*id];
+        assert_eq!(samples[0].0, 0);
+        assert_eq!(samples.last().unwrap(), &(summary.steps, summary.solution.clone()));
+    }
+}
+
+#[test]
+fn test_write_trajectories() {
+    let report = Builder::new(competing_network()).trials(2).seed(14).record_trajectories(10).build().run();
+    let path = temp_path("trajectories.csv");
+    report.write_trajectories(path.to_str().unwrap()).unwrap();
+    let written = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let mut lines = written.lines();
+    assert_eq!(lines.next(), Some("trial,step,species,count"));
+    assert_eq!(lines.next(), Some("0,0,a,50"));
+    let samples: usize = report.trajectories.iter().map(|(_, samples)| samples.len()).sum();
+    assert_eq!(lines.count() + 1, samples * 4);
+
+    assert!(report.write_trajectories(temp_path("trajectories.json").to_str().unwrap()).is_err());
+    assert!(report.write_trajectories(temp_path("missing_directory/trajectories.csv").to_str().unwrap()).is_err());
+}
+
+#[test]
+fn test_experiment_bundle() {
+    let directory = temp_path("experiment");
+    // timeline sub files are named by prefixing the trial id, so the timeline path has to be relative
+    let timeline = format!("marlea_test_{}_timeline.csv", std::process::id());
+    let engine = Builder::new(competing_network()).trials(3).seed(8).record_trajectories(10).out_timeline(timeline.clone()).build();
+    let report = engine.run_experiment(directory.to_str().unwrap()).unwrap();
+    let read = |file_name: &str| std::fs::read_to_string(directory.join(file_name)).unwrap();
+
+    let manifest = read("manifest.toml");
+    assert!(manifest.contains(&format!("network_fingerprint = \"{:016x}\"", engine.prime_network.fingerprint())));
+    assert!(manifest.contains("trials_started = 3\ntrials_completed = 3\n"));
+    assert!(manifest.contains("files = [\"network.csv\", \"initial_solution.csv\", \"config.toml\", \"seeds.csv\", \"results.csv\", \"trajectories.csv\", \"timelines/\"]"));
+    let config = read("config.toml");
+    assert!(config.starts_with("trials = 3\n"));
+    assert!(config.contains("seed = 8\n") && config.contains("recorded_trajectory_interval = 10\n"));
+
+    let seeds: Vec<String> = read("seeds.csv").lines().map(|line| line.to_string()).collect();
+    assert_eq!(seeds[0], "trial,seed,completed");
+    for (id, line) in seeds[1..].iter().enumerate() {
+        assert_eq!(line, &format!("{},{},true", id, random::trial_seed(8, id)));
+    }
+    assert_eq!(seeds.len(), 4);
+
+    let results: Vec<String> = read("results.csv").lines().map(|line| line.to_string()).collect();
+    let averages: Vec<String> = report.averages.iter().map(|(name, average)| format!("{},{}", name, average)).collect();
+    assert_eq!(results, averages);
+    assert_eq!(read("network.csv").lines().count(), 3);
+    assert_eq!(read("initial_solution.csv").lines().collect::<Vec<&str>>(), vec!["a,50", "b,30", "c,0", "d,0"]);
+    assert!(read("trajectories.csv").starts_with("trial,step,species,count\n"));
+    for id in 0..3 {
+        assert_eq!(read(&format!("timelines/{}.csv", id)), std::fs::read_to_string(format!("{}{}", id, timeline)).unwrap());
+        std::fs::remove_file(format!("{}{}", id, timeline)).unwrap();
+    }
+    std::fs::remove_dir_all(&directory).unwrap();
+
+    // a bundle which can not be written is reported rather than panicking
+    let blocked = temp_path("experiment_blocked");
+    std::fs::write(&blocked, "").unwrap();
+    let engine = Builder::new(competing_network()).trials(1).seed(8).build();
+    assert!(matches!(engine.run_experiment(blocked.to_str().unwrap()), Err(MarleaEngineError::ExportFailed(_))));
+    std::fs::remove_file(&blocked).unwrap();
+}
+
+#[test]
+fn test_replay_matches_trial() {
+    let engine = Builder::new(competing_network()).trials(4).seed(21).build();
+    let report = engine.run();
+    let summary = &report.trials[2];
+
+    let states: Vec<(i32, Solution)> = engine.replay(2).collect();
+    assert_eq!(states[0].0, 0);
+    let (last_step, last_solution) = states.last().unwrap();
+    assert_eq!(*last_step, summary.steps);
+    assert_eq!(last_solution, &summary.solution);
+}
+
+#[test]
+fn test_tracked_species() {
+    let engine = Builder::new(competing_network()).trials(3).seed(8).record_trajectories(5).tracked_species(&["c"]).build();
+    let report = engine.run();
+    for (_, samples) in &report.trajectories {
+        assert!(samples.iter().all(|(_, solution)| solution.species_counts.len() == 1));
+    }
+
+    let summary = &report.trials[0];
+    let timeline = engine.get_timeline(summary.id, summary.seed);
+    assert_eq!(timeline.iter().map(|(name, _)| name.as_str()).collect::<Vec<&str>>(), vec!["c"]);
+    assert_eq!(engine.get_timeline_of(summary.id, summary.seed, &["a", "b"]).len(), 2);
+}
+
+#[test]
+fn test_species_filters() {
+    let mut network = competing_network();
+    network.add_species("gate.index.0", 1);
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let sink = received.clone();
+    let report = Builder::new(network).trials(3).seed(8)
+        .include_species(&[SpeciesPattern::Wildcard("?".to_string()), SpeciesPattern::Prefix("gate.".to_string())])
+        .exclude_species(&[SpeciesPattern::Exact("b".to_string()), SpeciesPattern::Wildcard("*.index.*".to_string())])
+        .on_result(move |result| sink.lock().unwrap().push(result))
+        .build().run();
+
+    let names = |averages: &[(String, f64)]| averages.iter().map(|(name, _)| name.clone()).collect::<Vec<String>>();
+    assert_eq!(names(&report.averages), vec!["a", "c", "d"]);
+    assert!(received.lock().unwrap().iter().all(|result| match result {
+        MarleaResult::Intermediary(averages) => names(averages) == vec!["a", "c", "d"],
+        _ => true,
+    }));
+    // statistics still cover every species
+    assert_eq!(report.statistics.len(), 5);
+}
+
+#[test]
+fn test_average_order() {
+    let by_name = Builder::new(competing_network()).trials(3).seed(8).build().run().averages;
+    let descending = Builder::new(competing_network()).trials(3).seed(8).order_averages(SpeciesOrder::DescendingAverage).build().run().averages;
+    assert!(descending.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+
+    // outputs first, everything else by name
+    let outputs_first = SpeciesOrder::By(std::sync::Arc::new(|first: &(String, f64), second: &(String, f64)| (first.0 != "d").cmp(&(second.0 != "d"))));
+    let custom = Builder::new(competing_network()).trials(3).seed(8).order_averages(outputs_first).build().run().averages;
+    assert_eq!(custom[0], by_name[3]);
+    assert_eq!(custom[1..], by_name[..3]);
+}
+
+#[test]
+fn test_watched_trial_streams_points() {
+    let (result_sender, result_reciever) = std::sync::mpsc::sync_channel(0);
+    let engine = Builder::new(competing_network()).trials(3).seed(5).results(result_sender).watch(1).build();
+    let listener = std::thread::spawn(move || result_reciever.iter().collect::<Vec<MarleaResult>>());
+    let report = engine.run();
+    drop(engine);
+
+    let mut counts = HashMap::new();
+    let mut last_step = 0;
+    for result in listener.join().unwrap() {
+        if let MarleaResult::Point(name, step, count) = result {
+            assert!(step >= last_step);
+            last_step = step;
+            counts.insert(Species::Name(name), Species::Count(count));
+        }
+    }
+    assert!(last_step > 0 && last_step <= report.trials[1].steps);
+    assert_eq!(counts, report.trials[1].solution.species_counts);
+}
+
+#[test]
+fn test_response_modes() {
+    let results = |response: ResponseMode| {
+        let (result_sender, result_reciever) = std::sync::mpsc::sync_channel(0);
+        let engine = Builder::new(competing_network()).trials(3).seed(5).results(result_sender).response(response).build();
+        let listener = std::thread::spawn(move || result_reciever.iter().collect::<Vec<MarleaResult>>());
+        let report = engine.run();
+        drop(engine);
+        return (report, listener.join().unwrap());
+    };
+
+    let (_, silent) = results(ResponseMode::None);
+    assert!(matches!(silent.as_slice(), [MarleaResult::Final(_)]));
+
+    // every species of trial 0 at step 0, 10, 20 and so on
+    let (report, every_step) = results(ResponseMode::EveryStep { every_n: 10 });
+    let points: Vec<(String, i32)> = every_step.iter()
+        .filter_map(|result| match result {
+            MarleaResult::Point(name, step, _) => Some((name.clone(), *step)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(points.len(), 4 * (report.trials[0].steps / 10 + 1) as usize);
+    assert!(points.chunks(4).enumerate().all(|(index, chunk)| chunk.iter().all(|(_, step)| *step == 10 * index as i32)));
+    assert_eq!(every_step.iter().filter(|result| matches!(result, MarleaResult::Trial(_))).count(), 3);
+}
+
+#[test]
+fn test_on_result() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let sink = received.clone();
+    let engine = Builder::new(competing_network()).trials(3).seed(5).on_result(move |result| sink.lock().unwrap().push(result)).build();
+    let report = engine.run();
+
+    let received = received.lock().unwrap();
+    assert_eq!(received.iter().filter(|result| matches!(result, MarleaResult::Intermediary(_))).count(), 3);
+    assert_eq!(received.last(), Some(&MarleaResult::Final(Box::new(report))));
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn test_run_async() {
+    let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    let expected = Builder::new(competing_network()).trials(6).seed(4).build().run();
+    let received = runtime.block_on(async {
+        let mut results = Builder::new(competing_network()).trials(6).seed(4).build().run_async();
+        let mut received = Vec::new();
+        // recv only returns None once the engine finished and dropped its sender
+        while let Some(result) = results.recv().await {
+            received.push(result);
+        }
+        received
+    });
+
+    let intermediates = received.iter().filter(|result| matches!(result, MarleaResult::Intermediary(_))).count();
+    assert_eq!(intermediates, 6);
+    let Some(MarleaResult::Final(report)) = received.last() else {panic!("the stream did not end with the final report")};
+    assert_eq!(report.averages, expected.averages);
+    assert_eq!(received.iter().filter(|result| matches!(result, MarleaResult::Final(_))).count(), 1);
+
+    // a failing run ends the stream with its error rather than just closing it
+    let reactions = HashSet::from([Reaction::new(HashSet::new(), HashSet::from([Term::new("a".to_string(), 1)]), 1.0)]);
+    let overflowing = Builder::new(ReactionNetwork::new(reactions, Solution::from_pairs(&[("a", Count::MAX)]))).trials(2).seed(1).build();
+    let received = runtime.block_on(async {
+        let mut results = overflowing.run_async();
+        let mut received = Vec::new();
+        while let Some(result) = results.recv().await {
+            received.push(result);
+        }
+        received
+    });
+    assert!(matches!(received.last(), Some(MarleaResult::Failed(MarleaEngineError::TrialFailed { .. }))));
+    assert!(!received.iter().any(|result| matches!(result, MarleaResult::Final(_))));
+}
+
+#[test]
+fn test_report_every() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let sink = received.clone();
+    let report = Builder::new(competing_network()).trials(10).seed(5).report_every(4)
+        .on_result(move |result| sink.lock().unwrap().push(result))
+        .build().run();
+
+    let received = received.lock().unwrap();
+    let count = |matches: fn(&MarleaResult) -> bool| received.iter().filter(|result| matches(result)).count();
+    assert_eq!(count(|result| matches!(result, MarleaResult::Trial(_))), 10);
+    assert_eq!(count(|result| matches!(result, MarleaResult::Intermediary(_))), 2);
+    assert_eq!(count(|result| matches!(result, MarleaResult::Progress(_))), 2);
+    assert_eq!(received.last(), Some(&MarleaResult::Final(Box::new(report))));
+}
+
+#[test]
+fn test_multiple_subscribers() {
+    let (result_sender, result_reciever) = std::sync::mpsc::sync_channel(0);
+    let logged = Arc::new(Mutex::new(Vec::new()));
+    let log = logged.clone();
+    let engine = Builder::new(competing_network()).trials(3).seed(5)
+        .results(result_sender)
+        .on_result(move |result| log.lock().unwrap().push(result))
+        .build();
+    let listener = std::thread::spawn(move || result_reciever.iter().collect::<Vec<MarleaResult>>());
+    let report = engine.run();
+    drop(engine);
+
+    // a summary, a running mean and a progress snapshot per trial and the final report
+    let received = listener.join().unwrap();
+    assert_eq!(received.len(), 10);
+    assert_eq!(received, *logged.lock().unwrap());
+    let mut trials: Vec<TrialSummary> = received.into_iter()
+        .filter_map(|result| if let MarleaResult::Trial(summary) = result {Some(*summary)} else {None})
+        .collect();
+    trials.sort_by_key(|summary| summary.id);
+    assert_eq!(trials, report.trials);
+}
+
+#[test]
+fn test_latest_backpressure() {
+    // the receiver only starts reading once every trial finished, long after the channel filled up
+    let (result_sender, result_reciever) = std::sync::mpsc::sync_channel(2);
+    let (start_sender, start_reciever) = std::sync::mpsc::channel();
+    let start_sender = Mutex::new(start_sender);
+    let engine = Builder::new(competing_network()).trials(5).seed(8)
+        .results(result_sender)
+        .backpressure(Backpressure::Latest)
+        .on_progress(move |progress| if progress.trials_completed == 5 {start_sender.lock().unwrap().send(()).unwrap()})
+        .build();
+    let listener = std::thread::spawn(move || {
+        start_reciever.recv().unwrap();
+        return result_reciever.iter().collect::<Vec<MarleaResult>>();
+    });
+    let report = engine.run();
+    drop(engine);
+
+    // the first trial's summary and mean fill the channel, of the later snapshots only the last trial's are left
+    let received = listener.join().unwrap();
+    assert_eq!(received.len(), 6);
+    assert!(matches!(received[2], MarleaResult::Trial(_)));
+    assert_eq!(received[3], MarleaResult::Intermediary(report.averages.clone()));
+    assert!(matches!(received[4], MarleaResult::Progress(Progress { trials_completed: 5, trials_total: 5, .. })));
+    assert_eq!(received[5], MarleaResult::Final(Box::new(report)));
+}
+
+#[test]
+fn test_timeline_markers() {
+    let engine = Builder::new(competing_network()).trials(2).seed(30).build();
+    let report = engine.run();
+    let summary = &report.trials[0];
+    let (timeline, markers) = engine.get_timeline_with_markers(summary.id, summary.seed);
+
+    assert_eq!(timeline, engine.get_timeline(summary.id, summary.seed));
+    assert_eq!(markers.len() as u64, summary.reactions_fired);
+    assert!(markers.windows(2).all(|pair| pair[0].step < pair[1].step));
+}
+
+#[test]
+fn test_until_species() {
+    let report = Builder::new(competing_network()).trials(6).seed(3).until_species("a", 40).build().run();
+    let passage = &report.first_passage[0];
+    for summary in &report.trials {
+        assert_eq!(passage.steps[summary.id], Some(summary.steps));
+        assert!(summary.solution.species_counts[&Species::Name("a".to_string())] <= Species::Count(40));
+    }
+
+    let report = Builder::new(competing_network()).trials(2).until_species("a", 50).build().run();
+    assert!(report.trials.iter().all(|summary| summary.steps == 0));
+}
+
+#[test]
+fn test_max_steps() {
+    let report = Builder::new(competing_network()).trials(4).seed(2).max_steps(10).build().run();
+    assert!(report.trials.iter().all(|summary| summary.steps == 10 && summary.termination == Termination::StepLimit));
+
+    // the termination survives a checkpoint round trip
+    let checkpoint = Checkpoint { network_fingerprint: 0, trial_seeds: Vec::new(), completed_trials: report.trials.clone() };
+    let path = temp_path("capped_checkpoint.csv");
+    checkpoint.write(path.to_str().unwrap());
+    let read = Checkpoint::read(path.to_str().unwrap());
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(read, checkpoint);
+}
+
+// a and b turn into each other forever, so no trial ever stabilizes
+fn cycling_network() -> ReactionNetwork {
+    let term = |name: &str| Term::new(name.to_string(), 1);
+    let reactions = HashSet::from([
+        Reaction::new(HashSet::from([term("a")]), HashSet::from([term("b")]), 1.0),
+        Reaction::new(HashSet::from([term("b")]), HashSet::from([term("a")]), 1.0),
+    ]);
+    return ReactionNetwork::new(reactions, Solution::from_pairs(&[("a", 5), ("b", 5)]));
+}
+
+#[test]
+fn test_trial_time_limit() {
+    let report = Builder::new(cycling_network()).trials(3).trial_time_limit(std::time::Duration::from_millis(20)).build().run();
+    assert_eq!(report.trials.len(), 3);
+    assert!(report.trials.iter().all(|summary| summary.termination == Termination::TimedOut));
+    assert_eq!(report.num_trials, 0);
+}
+
+#[test]
+fn test_partial_report_on_runtime_expiry() {
+    let report = Builder::new(cycling_network()).trials(2).runtime(1).build().run();
+    assert!(report.partial);
+    assert!(report.trials.is_empty());
+
+    assert!(!Builder::new(competing_network()).trials(2).build().run().partial);
+}
+
+#[test]
+fn test_stability_detectors() {
+    // without null reactions a fixed point is only reached once no reaction is possible, like the default heuristic
+    let default = Builder::new(competing_network()).trials(4).seed(12).build().run();
+    let fixed_point = Builder::new(competing_network()).trials(4).seed(12)
+        .stability_detector(|| Box::new(trial::stability::FixedPoint::new(3)))
+        .build().run();
+    assert_eq!(fixed_point.trials, default.trials);
+
+    // the cycling network never halts but its counts stay put on average
+    let report = Builder::new(cycling_network()).trials(2).seed(1)
+        .stability_detector(|| Box::new(trial::stability::MovingWindow::new(200, 0.5)))
+        .build().run();
+    assert!(report.trials.iter().all(|summary| summary.termination == Termination::Stable));
+}
+
+#[test]
+fn test_oscillation_detection() {
+    // a single molecule passed around a ring of three species repeats every three steps
+    let term = |name: &str| Term::new(name.to_string(), 1);
+    let reactions = HashSet::from([
+        Reaction::new(HashSet::from([term("a")]), HashSet::from([term("b")]), 1.0),
+        Reaction::new(HashSet::from([term("b")]), HashSet::from([term("c")]), 1.0),
+        Reaction::new(HashSet::from([term("c")]), HashSet::from([term("a")]), 1.0),
+    ]);
+    let solution = MarleaEngine::solution_from(None, &reactions).unwrap();
+    let mut network = ReactionNetwork::new(reactions, solution);
+    network.set_count("a", 1);
+
+    let report = Builder::new(network).trials(2).seed(4)
+        .stability_detector(|| Box::new(trial::stability::Oscillation::new(30, 3)))
+        .build().run();
+    assert!(report.trials.iter().all(|summary| summary.termination == Termination::Oscillating));
+    assert_eq!(report.cycles.len(), 2);
+    let (_, cycle) = &report.cycles[0];
+    assert_eq!(cycle.period, 3);
+    assert_eq!(cycle.amplitudes, vec![("a".to_string(), 1), ("b".to_string(), 1), ("c".to_string(), 1)]);
+}
+
+#[test]
+fn test_stall_diagnostics() {
+    let report = Builder::new(competing_network()).trials(3).seed(6).stall_diagnostics(true).build().run();
+    assert_eq!(report.stalls.len(), 3);
+    for (id, blocked) in &report.stalls {
+        // every reaction of the competing network needs a or c, which are used up once it halts
+        assert_eq!(blocked.len(), 3);
+        assert!(blocked.iter().all(|reaction| !reaction.missing.is_empty()));
+        assert_eq!(report.trials[*id].termination, Termination::Stable);
+    }
+}
+
+#[test]
+fn test_extinction() {
+    let report = Builder::new(competing_network()).trials(8).seed(17).extinction("b", true).build().run();
+    let steps = &report.first_passage[0].steps;
+    for summary in &report.trials {
+        // trials which wiped out b stopped right there
+        if let Some(step) = steps[summary.id] {
+            assert_eq!(step, summary.steps);
+        }
+    }
+    let reached = steps.iter().filter(|step| step.is_some()).count() as f64 / steps.len() as f64;
+    assert_eq!(report.extinction_probability("b"), Some(reached));
+    assert_eq!(report.extinction_probability("a"), None);
+}
+
+#[test]
+fn test_catalyst_notation() {
+    let path = temp_path("catalyst.csv");
+    std::fs::write(&path, "a + [destruct] => b,1\n").unwrap();
+    let reactions = SupportedFileType::from(path.to_str().unwrap().to_string()).parse_reactions().unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let reaction = reactions.into_iter().next().unwrap();
+    assert_eq!(reaction.to_string(), "a + destruct => b + destruct");
+    assert_eq!(reaction.get_net_changes(), &[(Species::Name("a".to_string()), -1), (Species::Name("b".to_string()), 1)]);
+}
+
+#[test]
+fn test_catalyst_also_produced() {
+    let path = temp_path("catalyst_product.csv");
+    std::fs::write(&path, "a + [b] => b,1\n").unwrap();
+    let reactions = SupportedFileType::from(path.to_str().unwrap().to_string()).parse_reactions().unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    // the catalyst given back and the product add up rather than collapsing into one term
+    let reaction = reactions.into_iter().next().unwrap();
+    assert_eq!(reaction.to_string(), "a + b => 2 b");
+    assert_eq!(reaction.get_net_changes(), &[(Species::Name("a".to_string()), -1), (Species::Name("b".to_string()), 1)]);
+}
+
+#[test]
+fn test_clamped_species() {
+    let report = Builder::new(competing_network()).trials(3).seed(9).clamp(&["b"]).build().run();
+    for summary in &report.trials {
+        assert_eq!(summary.solution.species_counts[&Species::Name("b".to_string())], Species::Count(30));
+    }
+}
+
+#[test]
+fn test_dead_reactions() {
+    let mut network = competing_network();
+    network.add_species("typo", 0);
+    let report = Builder::new(network.clone()).trials(5).seed(2).build().run();
+    assert!(report.dead_reactions.is_empty());
+    assert_eq!(report.reaction_firings.iter().map(|(_, count)| count).sum::<u64>(), report.total_reactions_fired);
+
+    // a reaction whose reactant is never present can not fire in any trial
+    let dead = "typo => d";
+    let mut reactions: HashSet<Reaction> = network.get_reactions().iter().cloned().collect();
+    reactions.insert(Reaction::new(HashSet::from([Term::new("typo".to_string(), 1)]), HashSet::from([Term::new("d".to_string(), 1)]), 1.0));
+    let report = Builder::new(ReactionNetwork::new(reactions, network.get_solution().clone())).trials(5).seed(2).build().run();
+    assert_eq!(report.dead_reactions, vec![dead.to_string()]);
+}
+
+#[test]
+fn test_reaction_fluxes() {
+    let report = Builder::new(competing_network()).trials(5).seed(4).build().run();
+    let flux = |reaction: &str| report.reaction_fluxes.iter().find(|(name, _)| name == reaction).unwrap().1;
+    let rate = |species: &str| report.species_rates.iter().find(|(name, _)| name == species).unwrap().1;
+
+    // without null adjacent reactions there is no stable tail, so fluxes cover every reaction fired in every step
+    let steps: i32 = report.trials.iter().map(|summary| summary.steps).sum();
+    let fired = report.reaction_fluxes.iter().map(|(_, flux)| flux).sum::<f64>() * steps as f64;
+    assert!((fired - report.total_reactions_fired as f64).abs() < 1e-6);
+    assert_eq!(rate("d"), flux("2 a => d"));
+    assert_eq!(rate("b"), -flux("a + b => c"));
+}
+
+#[test]
+fn test_fractional_rates() {
+    let term = |name: &str| HashSet::from([Term::new(name.to_string(), 1)]);
+    let reactions = HashSet::from([Reaction::new(term("a"), term("b"), 0.5), Reaction::new(term("a"), term("c"), 1.5)]);
+    let solution = Solution::from_pairs(&[("a", 400), ("b", 0), ("c", 0)]);
+    let report = Builder::new(ReactionNetwork::new(reactions, solution)).trials(5).seed(3).build().run();
+
+    // a quarter of the weight goes to `a => b`
+    let average = |species: &str| report.averages.iter().find(|(name, _)| name == species).unwrap().1;
+    assert!((70.0..130.0).contains(&average("b")), "{}", average("b"));
+    assert_eq!(average("b") + average("c"), 400.0);
+}
+
+#[test]
+#[should_panic(expected = "would change the count")]
+fn test_failed_trial_stops_run() {
+    let reactions = HashSet::from([Reaction::new(HashSet::new(), HashSet::from([Term::new("a".to_string(), 1)]), 1.0)]);
+    Builder::new(ReactionNetwork::new(reactions, Solution::from_pairs(&[("a", Count::MAX)]))).trials(2).seed(1).build().run();
+}
+
+#[test]
+#[should_panic(expected = "can not analyse output e which is not in the network")]
+fn test_sensitivity_refuses_unknown_output() {
+    let engine = Builder::new(competing_network()).trials(2).seed(1).build();
+    engine.sensitivity(&Sensitivity::new(2).reaction_rate("2 a => d", 1.0, 3.0), &["d", "e"]);
+}
+
+#[test]
+fn test_run_for() {
+    let engine = Builder::new(competing_network()).trials(7).seed(4).build();
+    assert_eq!(engine.run_for(3), RunStatus::Running { trials_completed: 3, trials_total: 7 });
+    assert_eq!(engine.run_for(3), RunStatus::Running { trials_completed: 6, trials_total: 7 });
+    let RunStatus::Done(report) = engine.run_for(3) else {panic!("the last trial did not finish the run")};
+    // batches reach the same trials as a run in one go
+    assert_eq!(report.trials, engine.run().trials);
+    assert!(matches!(engine.run_for(3), RunStatus::Running { trials_completed: 3, .. }));
+}
+
+#[test]
+fn test_extend_trials() {
+    let engine = Builder::new(competing_network()).trials(6).seed(9).build();
+    let extended = engine.extend_trials(&engine.run(), 4);
+    let full = Builder::new(competing_network()).trials(10).seed(9).build().run();
+    assert_eq!(extended.trials, full.trials);
+    assert_eq!(extended.averages, full.averages);
+}
+
+#[test]
+fn test_extend_trials_keeps_strata() {
+    let strata = vec![Stratum::new(1.0).range("a", 10, 20), Stratum::new(1.0).range("a", 100, 110)];
+    let engine = Builder::new(competing_network()).trials(4).seed(3).strata(strata).build();
+    let initial_a = |engine: &MarleaEngine, id: usize| match engine.stepper(id).get_solution().species_counts.get(&Species::Name("a".to_string())) {
+        Some(Species::Count(count)) => *count,
+        _ => 0,
+    };
+    let before: Vec<Count> = (0..4).map(|id| initial_a(&engine, id)).collect();
+
+    // allocating all 8 trials at once would move trial 2 into the first stratum
+    let extended = engine.extend_trials(&engine.run(), 4);
+    assert_eq!(extended.trials.len(), 8);
+    assert_eq!((0..4).map(|id| initial_a(&engine, id)).collect::<Vec<Count>>(), before);
+    assert!(before[2] >= 100 && initial_a(&engine, 4) <= 20 && initial_a(&engine, 6) >= 100);
+}
+
+#[test]
+fn test_merge_reports() {
+    let first = Builder::new(competing_network()).trials(5).seed(1).histograms(Binning::Width(5)).build().run();
+    let second = Builder::new(competing_network()).trials(7).seed(2).histograms(Binning::Width(5)).build().run();
+    let merged = first.merge(&second);
+    assert_eq!((merged.num_trials, merged.trials.len()), (12, 12));
+    assert!(merged.trials.iter().enumerate().all(|(index, summary)| summary.id == index));
+
+    // pooling gives the mean and variance taken over every trial at once
+    let counts: Vec<f64> = merged.trials.iter().map(|summary| match summary.solution.species_counts.get(&Species::Name("d".to_string())) {
+        Some(Species::Count(count)) => *count as f64,
+        _ => 0.0,
+    }).collect();
+    let mean = counts.iter().sum::<f64>() / 12.0;
+    let variance = counts.iter().map(|count| (count - mean).powi(2)).sum::<f64>() / 11.0;
+    let (_, pooled) = merged.statistics.iter().find(|(name, _)| name == "d").unwrap();
+    assert!((pooled.mean - mean).abs() < 1e-9 && (pooled.variance - variance).abs() < 1e-9);
+    assert_eq!(merged.histograms.iter().find(|(name, _)| name == "d").unwrap().1.counts.iter().sum::<usize>(), 12);
+}
+
+#[test]
+fn test_merge_refuses_incompatible_reports() {
+    use trial::passage::Comparison;
+    let run = |builder: Builder| builder.trials(3).seed(1).build().run();
+    let watched = run(Builder::new(competing_network()).first_passage("d", Comparison::GreaterOrEqual, 1));
+    let other_target = run(Builder::new(competing_network()).first_passage("d", Comparison::GreaterOrEqual, 2));
+    assert_eq!(watched.try_merge(&other_target), Err(MergeError::PassageTargetMismatch));
+    assert_eq!(watched.try_merge(&run(Builder::new(competing_network()))), Err(MergeError::PassageTargetMismatch));
+
+    let plain = run(Builder::new(competing_network()));
+    assert_eq!(plain.try_merge(&run(Builder::new(competing_network()).max_steps(10))), Err(MergeError::ConfigMismatch));
+    let mut network = competing_network();
+    network.set_count("a", 40);
+    assert_eq!(plain.try_merge(&run(Builder::new(network))), Err(MergeError::NetworkMismatch));
+
+    // the merged averages keep the order of the first report
+    let descending = run(Builder::new(competing_network()).order_averages(SpeciesOrder::DescendingAverage));
+    let merged = descending.try_merge(&Builder::new(competing_network()).trials(3).seed(2).build().run()).unwrap();
+    assert!(merged.averages.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+}
+
+#[test]
+fn test_merge_refuses_overlapping_trials() {
+    let report = Builder::new(competing_network()).trials(3).seed(1).build().run();
+    assert_eq!(report.try_merge(&report), Err(MergeError::OverlappingTrials));
+    let rerun = Builder::new(competing_network()).trials(5).seed(1).build().run();
+    assert_eq!(report.try_merge(&rerun), Err(MergeError::OverlappingTrials));
+
+    // trials seeded from entropy have no master seed but still share their trial seeds
+    let mut unseeded = report.clone();
+    unseeded.seed = None;
+    assert_eq!(unseeded.try_merge(&report), Err(MergeError::OverlappingTrials));
+}
+
+#[test]
+fn test_status_accessors() {
+    let engine = Builder::new(competing_network()).trials(5).seed(2).build();
+    assert_eq!(engine.progress(), None);
+    let report = engine.run();
+    assert_eq!((engine.trials_completed(), engine.trials_in_progress()), (5, 0));
+    assert_eq!(engine.total_steps(), report.trials.iter().map(|summary| summary.steps as u64).sum::<u64>());
+    // the elapsed time stops with the run
+    assert_eq!(engine.elapsed(), engine.elapsed());
+
+    let engine = Arc::new(Builder::new(cycling_network()).trials(2).runtime(1).build());
+    let running = engine.clone();
+    let run = std::thread::spawn(move || running.run());
+    while engine.trials_in_progress() == 0 {
+        std::thread::yield_now();
+    }
+    assert_eq!(engine.trials_completed(), 0);
+    assert!(run.join().unwrap().partial);
+    assert_eq!(engine.trials_in_progress(), 0);
+}
+
+#[test]
+fn test_antithetic_pairs_share_seeds() {
+    // an odd trial count is rounded up so the last trial still has its partner
+    let report = Builder::new(competing_network()).trials(5).seed(3).antithetic(true).build().run();
+    assert_eq!((report.num_trials, report.trials.len()), (6, 6));
+    for (pair, trials) in report.trials.chunks(2).enumerate() {
+        assert_eq!((trials[0].id, trials[1].id), (2 * pair, 2 * pair + 1));
+        assert_eq!(trials[0].seed, trials[1].seed);
+        assert_eq!(trials[0].seed, random::trial_seed(3, pair));
+    }
+}
+
+#[test]
+fn test_antithetic_pairs_reduce_variance() {
+    // every step x turns into a or b with equal chance
+    let term = |name: &str| Term::new(name.to_string(), 1);
+    let reactions = HashSet::from([
+        Reaction::new(HashSet::from([term("x")]), HashSet::from([term("a")]), 1.0),
+        Reaction::new(HashSet::from([term("x")]), HashSet::from([term("b")]), 1.0),
+    ]);
+    let network = ReactionNetwork::new(reactions, Solution::from_pairs(&[("x", 40), ("a", 0), ("b", 0)]));
+    let run = |antithetic: bool| Builder::new(network.clone()).trials(40).seed(12).antithetic(antithetic).build().run();
+    let std_error = |report: &FinalReport| report.statistics.iter().find(|(name, _)| name == "a").unwrap().1.std_error;
+    let (plain, paired) = (run(false), run(true));
+
+    // partners make the opposite choice at every step, so between them every x of one trial turns into a
+    let count_of = |summary: &TrialSummary| match summary.solution.species_counts[&Species::Name("a".to_string())] {
+        Species::Count(count) => count,
+        Species::Name(_) => unreachable!(),
+    };
+    for pair in paired.trials.chunks(2) {
+        assert_eq!(count_of(&pair[0]) + count_of(&pair[1]), 40);
+    }
+    assert!(std_error(&paired) < std_error(&plain) / 2.0, "antithetic {} plain {}", std_error(&paired), std_error(&plain));
+}
+
+#[test]
+fn test_try_run_returns_errors() {
+    let reactions = HashSet::from([Reaction::new(HashSet::new(), HashSet::from([Term::new("a".to_string(), 1)]), 1.0)]);
+    let engine = Builder::new(ReactionNetwork::new(reactions, Solution::from_pairs(&[("a", Count::MAX)]))).trials(2).seed(1).build();
+    let Err(MarleaEngineError::TrialFailed { failure, .. }) = engine.try_run() else {panic!("the overflowing trial was not reported")};
+    assert_eq!((failure.species.as_str(), failure.count), ("a", Count::MAX));
+    // the failed run must not leave the engine thinking it is still running
+    assert!(engine.pause().is_none());
+
+    let engine = Builder::new(competing_network()).memory_limit(1).build();
+    assert!(matches!(engine.try_run(), Err(MarleaEngineError::MemoryLimit { limit: 1, .. })));
+    let checkpoint = Checkpoint { network_fingerprint: 0, trial_seeds: Vec::new(), completed_trials: Vec::new() };
+    assert_eq!(engine.try_resume(checkpoint).unwrap_err(), MarleaEngineError::CheckpointMismatch);
+}
+
+#[test]
+fn test_try_build_rejects_invalid_settings() {
+    let empty = ReactionNetwork::new(HashSet::new(), Solution::from_pairs(&[("a", 1)]));
+    assert_eq!(Builder::new(empty).try_build().err(), Some(BuildError::EmptyNetwork));
+    assert_eq!(Builder::new(competing_network()).trials(0).try_build().err(), Some(BuildError::NoTrials));
+    let mismatched = Builder::new(competing_network()).trajectory(10).record_trajectories(5).try_build();
+    assert_eq!(mismatched.err(), Some(BuildError::TrajectoryIntervalMismatch { mean: 10, recorded: 5 }));
+    let unbounded = Builder::new(competing_network()).schedule_every(0, 10, "a", Perturbation::Add(1)).try_build();
+    assert_eq!(unbounded.err(), Some(BuildError::UnboundedSchedule));
+    assert!(Builder::new(competing_network()).trials(1).try_build().is_ok());
+
+    // setters given a value out of range do not panic, try_build reports the first of them
+    let invalid_setting = |builder: Builder| match builder.try_build() {
+        Err(BuildError::InvalidSetting { setting, .. }) => setting,
+        _ => panic!("an invalid setting was accepted"),
+    };
+    let builder = || Builder::new(competing_network());
+    assert_eq!(invalid_setting(builder().max_steps(0)), "max_steps");
+    assert_eq!(invalid_setting(builder().threads(0)), "threads");
+    assert_eq!(invalid_setting(builder().report_every(0)), "report_every");
+    assert_eq!(invalid_setting(builder().histograms(Binning::Width(0))), "histograms");
+    assert_eq!(invalid_setting(builder().histograms(Binning::Bins(0))), "histograms");
+    assert_eq!(invalid_setting(builder().percentiles(vec![50.0, 101.0])), "percentiles");
+    assert_eq!(invalid_setting(builder().target_precision("a", 0.0)), "target_precision");
+    assert_eq!(invalid_setting(builder().trajectory(0)), "trajectory");
+    assert_eq!(invalid_setting(builder().record_trajectories_of(0, &[1])), "record_trajectories");
+    assert_eq!(invalid_setting(builder().bootstrap(1)), "bootstrap");
+    assert_eq!(invalid_setting(builder().tracked_species(&["a", "e"])), "tracked_species");
+    assert_eq!(invalid_setting(builder().until_species("e", 1)), "until_species");
+    assert_eq!(invalid_setting(builder().extinction("e", true)), "extinction");
+    assert_eq!(invalid_setting(builder().schedule(-1, "a", Perturbation::Add(1))), "schedule");
+    assert_eq!(invalid_setting(builder().max_steps(10).schedule_every(0, 0, "a", Perturbation::Add(1))), "schedule_every");
+    assert_eq!(invalid_setting(builder().clamp(&["a", "e"])), "clamp");
+    assert_eq!(invalid_setting(builder().response(ResponseMode::EveryStep { every_n: 0 })), "response");
+    assert_eq!(invalid_setting(builder().schedule(10, "e", Perturbation::Add(1))), "schedule");
+    assert_eq!(invalid_setting(builder().strata(vec![Stratum::new(1.0).range("e", 0, 5)])), "strata");
+    assert_eq!(invalid_setting(builder().trajectory(0).threads(0)), "trajectory");
+}
+
+#[test]
+fn test_zero_rate_network_is_stable() {
+    let reactions = HashSet::from([Reaction::new(HashSet::from([Term::new("a".to_string(), 1)]), HashSet::from([Term::new("b".to_string(), 1)]), 0.0)]);
+    let report = Builder::new(ReactionNetwork::new(reactions, Solution::from_pairs(&[("a", 3), ("b", 0)]))).trials(2).seed(1).build().run();
+    assert!(report.trials.iter().all(|summary| summary.steps == 1 && summary.reactions_fired == 0 && summary.termination == Termination::Stable));
+}
+
+#[test]
+fn test_thread_count() {
+    let engine = Builder::new(competing_network()).trials(4).seed(3).threads(2).build();
+    assert_eq!(engine.computation_threads.get().map(|pool| pool.max_count()), Some(2));
+    let shared = threadpool::Builder::new().num_threads(1).build();
+    let report = Builder::new(competing_network()).trials(4).seed(3).thread_pool(shared.clone()).build().run();
+    assert_eq!(report.trials, engine.run().trials);
+    assert_eq!(shared.max_count(), 1);
+}
+
+#[test]
+fn test_sparse_solutions() {
+    let mut network = competing_network();
+    network.add_species("flag", 0);
+    let dense = Builder::new(network.clone()).trials(5).seed(8).build().run();
+    let sparse = Builder::new(network).trials(5).seed(8).sparse_solutions(true).build().run();
+
+    assert!(dense.averages.iter().any(|(name, _)| name == "flag"));
+    // species at 0 in every trial, like the flag, are left out and everything else is unchanged
+    let nonzero: Vec<(String, f64)> = dense.averages.into_iter().filter(|(_, average)| *average != 0.0).collect();
+    assert_eq!(sparse.averages, nonzero);
+    assert!(sparse.trials.iter().all(|summary| summary.solution.nonzero().count() == summary.solution.species_counts.len()));
+}
+
+#[test]
+fn test_alarms() {
+    use std::sync::{Arc, Mutex};
+    use trial::passage::Comparison;
+    let raised = Arc::new(Mutex::new(Vec::new()));
+    let sink = raised.clone();
+    let report = Builder::new(competing_network())
+        .trials(10)
+        .seed(6)
+        .alarm("d", Comparison::GreaterOrEqual, 5)
+        .first_passage("d", Comparison::GreaterOrEqual, 5)
+        .on_alarm(move |alarm| sink.lock().unwrap().push(alarm))
+        .build()
+        .run();
+
+    // a trial raises the alarm at most once, at the step the same threshold is first passed
+    let mut raised = raised.lock().unwrap().clone();
+    raised.sort_by_key(|alarm| alarm.trial);
+    assert_eq!(raised, report.alarms);
+    let steps: Vec<Option<i32>> = (0..10).map(|id| report.alarms.iter().find(|alarm| alarm.trial == id).map(|alarm| alarm.step)).collect();
+    assert_eq!(steps, report.first_passage[0].steps);
+    assert!(!report.alarms.is_empty());
+}
+
+#[test]
+fn test_stepper_matches_run() {
+    let engine = Builder::new(competing_network()).trials(4).seed(13).build();
+    let mut stepper = engine.stepper(3);
+    while !stepper.advance() {}
+
+    let report = engine.run();
+    assert_eq!(stepper.get_solution(), &report.trials[3].solution);
+    assert_eq!(stepper.get_step_count(), report.trials[3].steps);
+}
+
+#[test]
+fn test_logger_receives_warnings_and_trials() {
+    let logged = Arc::new(Mutex::new(Vec::new()));
+    let log = logged.clone();
+    // the logger is global, so it also hears the tests running alongside this one
+    set_logger(Level::Debug, move |level, message| if let Ok(mut log) = log.lock() {log.push((level, message.to_string()))});
+
+    let term = |name: &str| Term::new(name.to_string(), 1);
+    let reactions = HashSet::from([Reaction::new(HashSet::from([term("e")]), HashSet::from([term("f")]), 1.0)]);
+    Builder::new(ReactionNetwork::new(reactions, Solution::from_pairs(&[("e", 0), ("f", 0)]))).trials(1).seed(1).build().run();
+
+    let logged = logged.lock().unwrap().clone();
+    assert!(logged.contains(&(Level::Warn, "reaction e => f can never fire from the initial solution".to_string())));
+    assert!(logged.iter().any(|(level, message)| *level == Level::Debug && message.starts_with("trial 0 finished after 1 steps and 0 reactions")));
+    assert!(logged.iter().all(|(level, _)| *level != Level::Trace));
+}
+
+#[test]
+fn test_result_cache() {
+    let cache_dir = temp_path("cache");
+    let _ = std::fs::remove_dir_all(&cache_dir);
+    let engine = || Builder::new(competing_network()).trials(5).seed(4).cache_dir(cache_dir.to_str().unwrap().to_string()).build();
+    assert_eq!(engine().fingerprint(), engine().fingerprint());
+    assert_ne!(engine().fingerprint(), Builder::new(competing_network()).trials(5).seed(5).build().fingerprint());
+    // strata keep their ranges in a hash map, whose iteration order differs between otherwise equal engines
+    let stratified = || Builder::new(competing_network()).trials(4).seed(4)
+        .strata(vec![Stratum::new(1.0).range("a", 10, 20).range("b", 5, 6).range("c", 0, 3).range("d", 1, 2)]).build();
+    assert_eq!(stratified().fingerprint(), stratified().fingerprint());
+    let precision = |relative_ci: f64| Builder::new(competing_network()).trials(5).seed(4).target_precision("a", relative_ci).build().fingerprint();
+    assert_ne!(precision(0.1), precision(0.1 + 1e-12));
+    // the settings hash is pinned, a change here invalidates every cache written before
+    assert_eq!(format!("{:016x}", Builder::new(competing_network()).trials(5).seed(4).build().get_config().fingerprint()), "ba533d18a964b46b");
+
+    let first = engine().run();
+    assert!(cache_dir.join(format!("{:016x}.csv", engine().fingerprint())).exists());
+    let cached_engine = engine();
+    let cached = cached_engine.run();
+    assert_eq!(cached_engine.progress().unwrap().trials_resumed, 5);
+    assert_eq!((cached.trials, cached.averages), (first.trials, first.averages));
+    std::fs::remove_dir_all(&cache_dir).unwrap();
+}
+
+#[test]
+fn test_corrupt_result_cache() {
+    let cache_dir = temp_path("corrupt_cache");
+    let _ = std::fs::remove_dir_all(&cache_dir);
+    std::fs::create_dir_all(&cache_dir).unwrap();
+    let engine = || Builder::new(competing_network()).trials(5).seed(4).cache_dir(cache_dir.to_str().unwrap().to_string()).build();
+    let cache_file = cache_dir.join(format!("{:016x}.csv", engine().fingerprint()));
+    // trial ids far past the seeds in the file, or given twice, are refused rather than allocated for
+    for corrupt in ["seed,18446744073709551615,1\n", "seed,1000000000000,1\n", "seed,0,1\nseed,0,2\n", "seed,1,1\n"] {
+        std::fs::write(&cache_file, format!("network,12\n{}", corrupt)).unwrap();
+        assert!(Checkpoint::try_read(cache_file.to_str().unwrap()).is_err(), "{} was read", corrupt);
+    }
+    std::fs::write(&cache_file, "network,12\nseed,1,7\nseed,0,5\n").unwrap();
+    assert_eq!(Checkpoint::try_read(cache_file.to_str().unwrap()).unwrap().trial_seeds, vec![5, 7]);
+    std::fs::write(&cache_file, "network,12\nseed,not a number\n").unwrap();
+    assert!(Checkpoint::try_read(cache_file.to_str().unwrap()).is_err());
+
+    // the unreadable entry counts as a miss and is replaced by the fresh run
+    let fresh = engine();
+    let report = fresh.try_run().unwrap();
+    assert_eq!(fresh.progress().unwrap().trials_resumed, 0);
+    assert_eq!(report.trials.len(), 5);
+    assert_eq!(Checkpoint::try_read(cache_file.to_str().unwrap()).unwrap().completed_trials.len(), 5);
+    std::fs::remove_dir_all(&cache_dir).unwrap();
+}
+
+#[test]
+fn test_scheduled_events() {
+    let term = |name: &str| Term::new(name.to_string(), 1);
+    let network = ReactionNetwork::new(HashSet::from([Reaction::new(HashSet::from([term("a")]), HashSet::from([term("b")]), 1.0)]), Solution::from_pairs(&[("a", 10), ("b", 0)]));
+    let report = Builder::new(network).trials(3).seed(1)
+        .schedule(1000, "a", Perturbation::Add(5))
+        .schedule(0, "a", Perturbation::Set(3))
+        .build().run();
+
+    // every trial settles after 3 steps, waits for the event at step 1000 and converts the 5 added
+    for summary in &report.trials {
+        assert_eq!(summary.solution.species_counts.get(&Species::Name("b".to_string())), Some(&Species::Count(8)));
+        assert!(summary.steps > 1000);
+    }
+}
+
+#[test]
+fn test_repeating_events() {
+    let term = |name: &str| Term::new(name.to_string(), 1);
+    let network = ReactionNetwork::new(HashSet::from([Reaction::new(HashSet::from([term("a")]), HashSet::from([term("b")]), 1.0)]), Solution::from_pairs(&[("a", 0), ("b", 0)]));
+    let report = Builder::new(network).trials(2).seed(3).max_steps(1000)
+        .schedule_every(0, 100, "a", Perturbation::Add(2))
+        .build().run();
+
+    // doses at steps 0 through 900 are all converted, the trial settles for good after the last one
+    for summary in &report.trials {
+        assert_eq!(summary.solution.species_counts.get(&Species::Name("b".to_string())), Some(&Species::Count(20)));
+        assert_eq!(summary.termination, Termination::Stable);
+    }
+}
+
+#[test]
+fn test_input_signal() {
+    let path = temp_path("input_signal.csv");
+    std::fs::write(&path, "time,count\n0,0\n5,1\n20,0\n").unwrap();
+    let term = |name: &str| Term::new(name.to_string(), 1);
+    let network = ReactionNetwork::new(HashSet::from([Reaction::new(HashSet::from([term("s"), term("a")]), HashSet::from([term("b")]), 1.0)]), Solution::from_pairs(&[("s", 7), ("a", 100), ("b", 0)]));
+    let report = Builder::new(network).trials(2).seed(5)
+        .input_signal("s", path.to_str().unwrap().to_string(), 10.0)
+        .build().run();
+
+    // the input holds at 1 from step 50 to 200 without being used up, so all of the fuel is converted
+    for summary in &report.trials {
+        assert_eq!(summary.solution.species_counts.get(&Species::Name("b".to_string())), Some(&Species::Count(100)));
+        assert_eq!(summary.solution.species_counts.get(&Species::Name("s".to_string())), Some(&Species::Count(0)));
+        assert!(summary.steps >= 200);
+    }
+
+    // unreadable or malformed signals are reported by try_build rather than panicking in the setter
+    let signal = |species: &str, path: &std::path::Path, steps_per_time_unit: f64| Builder::new(competing_network())
+        .input_signal(species, path.to_str().unwrap().to_string(), steps_per_time_unit)
+        .try_build().err();
+    assert!(matches!(signal("a", &temp_path("missing_signal.csv"), 1.0), Some(BuildError::UnreadableFile { .. })));
+    assert!(matches!(signal("a", &temp_path("input_signal.json"), 1.0), Some(BuildError::UnreadableFile { .. })));
+    assert!(matches!(signal("e", &path, 1.0), Some(BuildError::InvalidSetting { setting: "input_signal", .. })));
+    assert!(matches!(signal("a", &path, 0.0), Some(BuildError::InvalidSetting { setting: "input_signal", .. })));
+    for rows in ["time,count\n0,inf\n", "0,-1\n", "time,count\n"] {
+        std::fs::write(&path, rows).unwrap();
+        assert!(matches!(signal("a", &path, 1.0), Some(BuildError::UnreadableFile { .. })), "accepted signal {:?}", rows);
+    }
+    std::fs::remove_file(&path).unwrap();
+}
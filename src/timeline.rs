@@ -0,0 +1,97 @@
+use super::trial::reaction_network::reaction::term::solution::{Count, Species, Solution};
+
+/// The count of a species at a step of a trial
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Point {
+    pub step: i32,
+    pub count: Count,
+}
+
+/// A reaction which fired at a step of a trial, written `a + 2 b => c`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Marker {
+    pub step: i32,
+    pub reaction: String,
+}
+
+/// Drops every point lying on the straight line between its neighbours, keeping only those where the slope changes
+struct Series {
+    kept: Vec<Point>,
+    pending: Option<Point>,
+}
+
+impl Series {
+    fn push(&mut self, point: Point) {
+        if let (Some(last), Some(pending)) = (self.kept.last(), self.pending) {
+            // compare slopes by cross multiplying so no precision is lost
+            let rise_before = pending.count as i128 - last.count as i128;
+            let run_before = (pending.step - last.step) as i128;
+            let rise_after = point.count as i128 - pending.count as i128;
+            let run_after = (point.step - pending.step) as i128;
+            if rise_before * run_after != rise_after * run_before {
+                self.kept.push(pending);
+            }
+        } else if let Some(pending) = self.pending {
+            self.kept.push(pending);
+        }
+        self.pending = Some(point);
+    }
+
+    fn finish(mut self) -> Vec<Point> {
+        if let Some(pending) = self.pending {
+            self.kept.push(pending);
+        }
+        return self.kept;
+    }
+}
+
+/// Compresses the trajectory of every species of a solution as a trial steps through it
+pub(crate) struct Compressor {
+    series: Vec<(Species, Series)>,
+}
+
+impl Compressor {
+    /// Starts every species of the initial solution at step 0, or only the tracked species if any are given
+    pub(crate) fn new(initial: &Solution, tracked_species: Option<&[String]>) -> Self {
+        let mut compressor = Self {
+            series: initial.iter()
+                .filter(|(name, _)| tracked_species.is_none_or(|tracked| tracked.contains(&name.to_string())))
+                .map(|(name, _)| (name.clone(), Series { kept: Vec::new(), pending: None }))
+                .collect(),
+        };
+        compressor.push(0, initial);
+        return compressor;
+    }
+
+    pub(crate) fn push(&mut self, step: i32, solution: &Solution) {
+        for (name, series) in &mut self.series {
+            let count = match solution.species_counts.get(name) {
+                Some(Species::Count(count)) => *count,
+                _ => 0,
+            };
+            series.push(Point { step, count });
+        }
+    }
+
+    /// returns the compressed points of every species sorted by species name
+    pub(crate) fn finish(self) -> Vec<(String, Vec<Point>)> {
+        return self.series.into_iter()
+            .map(|(name, series)| (name.to_string(), series.finish()))
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_series_keeps_slope_changes() {
+        let mut series = Series { kept: Vec::new(), pending: None };
+        for (step, count) in [(0, 10), (1, 9), (2, 8), (3, 7), (4, 7), (5, 7), (6, 8)] {
+            series.push(Point { step, count });
+        }
+        let steps: Vec<i32> = series.finish().iter().map(|point| point.step).collect();
+        assert_eq!(steps, vec![0, 3, 5, 6]);
+    }
+}
@@ -1,35 +1,77 @@
-/// Author: Marceline Sorensen 
-/// Email: nadaso8th@gmail.com 
-/// Date: 08/03/2023
-/// 
-/// # Description
-/// This code provides a Trial struct and its implementation for simulating a reaction network.
-///  The ReactionNetwork module contains all the necessary details about this network, including species, reactions, and terms.
-/// The simulation process is performed using the Simulate() function in the implementation of Trial. 
-/// It takes a mutable reference to a trial, evaluates its current status, and runs reactions until the network reaches a stable state.
-/// 
-/// # Usage
-/// To use this code, you need to import the ReactionNetwork module and create an instance of ReactionNetwork<'trial>, then create an instance of Trial by passing this instance as an argument.
-/// You can then run simulations on this Trial instance using the simulate() function.
-/// It returns a HashMap containing all the species keyd by their references in the stable network solution.
-
-use reaction_network::{ReactionNetwork, reaction::term::solution::{Solution}};
-use results::TrialResult;
-use std::sync::mpsc::SyncSender;
+//! Author: Marceline Sorensen 
+//! Email: nadaso8th@gmail.com 
+//! Date: 08/03/2023
+//! 
+//! # Description
+//! This code provides a Trial struct and its implementation for simulating a reaction network.
+//!  The ReactionNetwork module contains all the necessary details about this network, including species, reactions, and terms.
+//! The simulation process is performed using the Simulate() function in the implementation of Trial. 
+//! It takes a mutable reference to a trial, evaluates its current status, and runs reactions until the network reaches a stable state.
+//! 
+//! # Usage
+//! To use this code, you need to import the ReactionNetwork module and create an instance of ReactionNetwork<'trial>, then create an instance of Trial by passing this instance as an argument.
+//! You can then run simulations on this Trial instance using the simulate() function.
+//! It returns a HashMap containing all the species keyd by their references in the stable network solution.
 
+use reaction_network::{CountOutOfRange, ReactionNetwork, reaction::{Reaction, term::solution::Solution}};
+use rand::RngCore;
+use random::{Antithetic, RngBackend, RngStream};
+use observer::StepObserver;
+use passage::PassageTarget;
+use schedule::ScheduledEvent;
+use stability::{NullAdjacent, StabilityDetector, Verdict};
+use results::{Firings, Termination, TrialResult};
+use std::sync::{Arc, atomic::{AtomicBool, Ordering}, mpsc::SyncSender};
+use std::time::{Duration, Instant};
+use crate::log::{self, Level};
+
+pub mod observer;
+pub mod passage;
+pub mod random;
 pub mod reaction_network; 
 pub mod results;
+pub mod schedule;
+pub mod stability;
+pub mod strata;
 
 pub struct Trial {
     reaction_network: ReactionNetwork,
-    stability: Stability, 
-    max_semi_stable_steps: i32,
+    stable: bool,
+    stability_detector: Box<dyn StabilityDetector>,
     id: usize,
+    seed: u64,
+    rng: Box<dyn RngCore + Send>,
+    step_count: i32,
+    reactions_fired: u64,
+    last_reaction: Option<Reaction>,
+    firings: Firings,
+    observer: Option<Arc<dyn StepObserver>>,
+    trajectory_interval: Option<i32>,
+    trajectory: Vec<Solution>,
+    tracked_species: Option<Vec<String>>,
+    passage_targets: Vec<PassageTarget>,
+    passage_steps: Vec<Option<i32>>,
+    stop_targets: Vec<PassageTarget>,
+    // perturbations and the next step each is due at, None once it has been applied for the last time
+    events: Vec<ScheduledEvent>,
+    event_steps: Vec<Option<i32>>,
+    alarms: Vec<PassageTarget>,
+    // whether each alarm was raised already, and the alarms raised since they were last sent with their step
+    alarms_raised: Vec<bool>,
+    pending_alarms: Vec<(usize, i32)>,
+    max_steps: Option<i32>,
+    time_limit: Option<Duration>,
+    deadline: Option<Instant>,
+    termination: Termination,
+    // the error which stopped the trial, if a reaction would have taken a count out of range
+    failure: Option<CountOutOfRange>,
+    // whether solutions sent to the engine leave out species at 0
+    sparse: bool,
 }
 
-impl <'trial_runtime> Trial {
+impl Trial {
 
-    pub fn from(reaction_network: ReactionNetwork, max_semi_stable_steps_setting: Option<i32>, id: usize) -> Self {
+    pub fn from(reaction_network: ReactionNetwork, max_semi_stable_steps_setting: Option<i32>, id: usize, seed: u64, rng_backend: RngBackend, antithetic: bool) -> Self {
         let max_semi_stable_steps: i32; 
         if let Some(number) = max_semi_stable_steps_setting {
             max_semi_stable_steps = number;
@@ -39,106 +81,365 @@ impl <'trial_runtime> Trial {
         
         Self {
             reaction_network,
-            stability: Stability::Initial,
-            max_semi_stable_steps,
+            stable: false,
+            stability_detector: Box::new(NullAdjacent::new(max_semi_stable_steps)),
             id, 
+            seed,
+            rng: match antithetic {
+                true => Box::new(Antithetic::new(rng_backend.stream(seed, RngStream::ReactionSelection))),
+                false => rng_backend.stream(seed, RngStream::ReactionSelection),
+            },
+            step_count: 0,
+            reactions_fired: 0,
+            last_reaction: None,
+            firings: Firings::default(),
+            observer: None,
+            trajectory_interval: None,
+            trajectory: Vec::new(),
+            tracked_species: None,
+            passage_targets: Vec::new(),
+            passage_steps: Vec::new(),
+            stop_targets: Vec::new(),
+            events: Vec::new(),
+            event_steps: Vec::new(),
+            alarms: Vec::new(),
+            alarms_raised: Vec::new(),
+            pending_alarms: Vec::new(),
+            max_steps: None,
+            time_limit: None,
+            deadline: None,
+            termination: Termination::Stable,
+            failure: None,
+            sparse: false,
         }
     }
 
-    pub fn simulate_with_timeline (&mut self, trial_tx: SyncSender<TrialResult>)  {
-        let mut step_count = 0; 
-        loop{
-            step_count += 1; 
-            self.step();
-            trial_tx.send(TrialResult::TimelineEntry(self.reaction_network.get_solution().clone(), self.id))
-                .expect("Reciever thread for trial {} dropped\nShutting down...");
-            if let Stability::Stable = self.stability {
-                trial_tx.send(TrialResult::StableSolution(self.reaction_network.get_solution().clone(), step_count))
-                .expect("Reciever thread for trial {} dropped\nShutting down...");
-                return;
-            }
-        }   
+    /// returns the seed all of this trial's random streams are derived from
+    pub fn get_seed(&self) -> u64 {
+        return self.seed;
     }
 
-    pub fn simulate(&mut self, trial_tx: SyncSender<TrialResult>) {
-        let mut step_count = 0; 
-        loop{
-            step_count += 1; 
-            self.step();
-            if let Stability::Stable = self.stability {
-                trial_tx.send(TrialResult::StableSolution(self.reaction_network.get_solution().clone(), step_count))
-                .expect("Reciever thread for trial {} dropped\nShutting down...");
-                return;
+    /// attaches an observer which is notified of every reaction fired and of the trial completing
+    pub fn observe(&mut self, observer: Arc<dyn StepObserver>) {
+        self.observer = Some(observer);
+    }
+
+    /// records the solution before the first step and after every `interval` steps, 
+    /// the samples are sent to the engine before the stable solution
+    pub fn record_trajectory(&mut self, interval: i32) {
+        self.trajectory_interval = Some(interval);
+        self.trajectory.push(self.sample());
+    }
+
+    /// only keeps the named species in trajectory samples, including any already taken
+    pub fn track_species(&mut self, species: Vec<String>) {
+        for sample in &mut self.trajectory {
+            *sample = sample.select(&species);
+        }
+        self.tracked_species = Some(species);
+    }
+
+    // copies the tracked species of the current solution, or all of them if none were chosen
+    fn sample(&self) -> Solution {
+        let sample = match &self.tracked_species {
+            Some(species) => self.reaction_network.get_solution().select(species),
+            None => self.reaction_network.get_solution().clone(),
+        };
+        return match self.sparse {
+            true => sample.sparse(),
+            false => sample,
+        };
+    }
+
+    /// leaves species at 0 out of the trajectory samples and stable solution sent to the engine
+    pub fn sparse_solutions(&mut self) {
+        self.sparse = true;
+    }
+
+    /// records the first step each target holds at, step 0 being the initial solution. 
+    /// The steps are sent to the engine before the stable solution
+    pub fn watch_passage(&mut self, targets: Vec<PassageTarget>) {
+        self.passage_steps = targets.iter()
+            .map(|target| if target.is_met(self.reaction_network.get_solution()) {Some(0)} else {None})
+            .collect();
+        self.passage_targets = targets;
+    }
+
+    /// raises each threshold as an alarm the first step it holds, step 0 being the initial solution. 
+    /// `simulate` sends alarms to the engine right after the step raising them
+    pub fn watch_alarms(&mut self, thresholds: Vec<PassageTarget>) {
+        self.alarms_raised = vec![false; thresholds.len()];
+        self.alarms = thresholds;
+        self.check_alarms();
+    }
+
+    // queues every alarm whose threshold holds for the first time
+    fn check_alarms(&mut self) {
+        for (index, threshold) in self.alarms.iter().enumerate() {
+            if !self.alarms_raised[index] && threshold.is_met(self.reaction_network.get_solution()) {
+                self.alarms_raised[index] = true;
+                self.pending_alarms.push((index, self.step_count));
             }
-        }   
+        }
     }
 
-    fn step(&mut self) {
-        match self.stability {
-            Stability::Initial => {
-                self.reaction_network.react();
+    fn send_alarms(&mut self, trial_tx: &SyncSender<TrialResult>) -> Option<()> {
+        for (index, step) in self.pending_alarms.drain(..) {
+            trial_tx.send(TrialResult::Alarm(index, step, self.id)).ok()?;
+        }
+        return Some(());
+    }
 
-                if self.reaction_network.get_possible_reactions().is_empty() {
-                    self.stability = Stability::Stable;
-                }
+    /// replaces the default null adjacent heuristic deciding when the trial is stable
+    pub fn detect_stability(&mut self, detector: Box<dyn StabilityDetector>) {
+        self.stability_detector = detector;
+    }
 
-                else if self.reaction_network.get_possible_reactions().is_subset(&self.reaction_network.get_null_adjacent_reactions()) {
-                    self.stability = Stability::SemiStable(0);
-                } 
-                
-                else {
-                    self.stability = Stability::Unstable;
-                }
-            } 
+    /// ends the trial at the first step the target holds, which may be before the first step. 
+    /// With several targets the trial ends at the first step any of them holds
+    pub fn stop_at(&mut self, target: PassageTarget) {
+        if target.is_met(self.reaction_network.get_solution()) {
+            self.stable = true;
+        }
+        self.stop_targets.push(target);
+    }
 
-            Stability::Unstable => {
-                self.reaction_network.react();
+    /// applies each event after the step it is scheduled at and repeating events again after every period, 
+    /// events at step 0 or before are applied right away. A trial which settles before its next event skips ahead 
+    /// to the step of the event and carries on from there, so every trial is perturbed at the same steps
+    pub fn schedule(&mut self, events: Vec<ScheduledEvent>) {
+        self.event_steps = events.iter().map(|event| Some(event.get_step())).collect();
+        self.events = events;
+        self.apply_events();
+    }
 
-                if self.reaction_network.get_possible_reactions().is_empty() {
-                    self.stability = Stability::Stable;
+    // applies every event due by the current step, or skips ahead to the next event if the trial settled
+    fn apply_events(&mut self) {
+        if self.failure.is_some() {
+            return;
+        }
+        while let Some(next_step) = self.event_steps.iter().flatten().min().copied() {
+            if next_step > self.step_count {
+                // nothing would react to an event at the step limit, so a settled trial ends rather than waiting for it
+                if !self.stable || self.max_steps.is_some_and(|max_steps| next_step >= max_steps) {
+                    break;
                 }
-
-                else if self.reaction_network.get_possible_reactions().is_subset(self.reaction_network.get_null_adjacent_reactions()) {
-                    self.stability = Stability::SemiStable(0);
+                // the solution does not change while the trial waits, so the skipped samples repeat it
+                if let Some(interval) = self.trajectory_interval {
+                    let first = (self.step_count + interval - 1) / interval;
+                    for _ in first..=(next_step - 1) / interval {
+                        let sample = self.sample();
+                        self.trajectory.push(sample);
+                    }
                 }
-
-                else {
-                    self.stability = Stability::Unstable;
+                self.step_count = next_step;
+            }
+            // events due at the same step are applied in the order they were scheduled
+            for (event, event_step) in self.events.iter().zip(self.event_steps.iter_mut()) {
+                if *event_step == Some(next_step) {
+                    event.apply(&mut self.reaction_network);
+                    *event_step = event.get_period().map(|period| next_step + period);
                 }
             }
+            self.stable = false;
+            self.stability_detector.reset();
+        }
+    }
+
+    /// ends the trial after `max_steps` steps even if it has not stabilized yet, flagging it as cut off
+    pub fn limit_steps(&mut self, max_steps: i32) {
+        self.max_steps = Some(max_steps);
+    }
 
-            Stability::SemiStable(count) => {
-                self.reaction_network.react();
+    /// abandons the trial once `simulate` has been running it for longer than `time_limit`, flagging it as timed out. 
+    /// Stepping through the trial with `advance` is never timed out
+    pub fn limit_time(&mut self, time_limit: Duration) {
+        self.time_limit = Some(time_limit);
+    }
 
-                if self.reaction_network.get_possible_reactions().is_empty() {
-                    self.stability = Stability::Stable;
+    /// returns the number of steps taken so far
+    pub fn get_step_count(&self) -> i32 {
+        return self.step_count;
+    }
 
+    /// returns the reaction the last step fired, None before the first step or if no reaction was possible
+    pub fn get_last_reaction(&self) -> Option<&Reaction> {
+        return self.last_reaction.as_ref();
+    }
 
-                } else if self.reaction_network.get_possible_reactions().is_subset(self.reaction_network.get_null_adjacent_reactions()) && count < self.max_semi_stable_steps {
-                        self.reaction_network.react();
-                        self.stability = Stability::SemiStable(count + 1);
-                
+    pub fn get_solution(&self) -> &Solution {
+        return self.reaction_network.get_solution();
+    }
 
-                } else if self.reaction_network.get_possible_reactions().is_subset(self.reaction_network.get_null_adjacent_reactions()) && count >= self.max_semi_stable_steps {
-                        self.reaction_network.react();
-                        self.stability = Stability::Stable;
-                
+    /// takes a single step the same way `simulate` does and returns true once the trial is stable,
+    /// panics if a reaction would have taken a count out of range
+    pub fn advance(&mut self) -> bool {
+        if self.stable {
+            return true;
+        }
+        self.step_count += 1;
+        self.step();
+        if let Some(failure) = &self.failure {
+            panic!("trial {} failed: {}", self.id, failure);
+        }
+        self.record_step();
+        return self.stable;
+    }
 
-                } else {
-                    self.stability = Stability::Unstable;
-                }
+    pub fn simulate_with_timeline (&mut self, trial_tx: SyncSender<TrialResult>, interrupt: Arc<AtomicBool>)  {
+        self.deadline = self.time_limit.map(|time_limit| Instant::now() + time_limit);
+        // a send only fails once the engine stopped listening, leaving nobody to report to
+        loop{
+            if self.send_alarms(&trial_tx).is_none() {
+                return;
+            }
+            if self.stable {
+                let _ = self.finish(trial_tx);
+                return;
+            }
+            if interrupt.load(Ordering::Relaxed) {
+                let _ = trial_tx.send(TrialResult::Interrupted(self.id));
+                return;
+            }
+            self.step_count += 1; 
+            self.step();
+            self.record_step();
+
+            // send the full solution once then only the species which changed in each following step
+            let changes = self.reaction_network.take_changes();
+            let entry = match self.step_count {
+                1 => TrialResult::TimelineEntry(self.reaction_network.get_solution_snapshot(), self.id),
+                _ => TrialResult::TimelineDelta(changes, self.id),
+            };
+            if trial_tx.send(entry).is_err() {
+                return;
+            }
+        }   
+    }
+
+    pub fn simulate(&mut self, trial_tx: SyncSender<TrialResult>, interrupt: Arc<AtomicBool>) {
+        self.deadline = self.time_limit.map(|time_limit| Instant::now() + time_limit);
+        loop{
+            if self.send_alarms(&trial_tx).is_none() {
+                return;
+            }
+            if self.stable {
+                let _ = self.finish(trial_tx);
+                return;
+            }
+            if interrupt.load(Ordering::Relaxed) {
+                let _ = trial_tx.send(TrialResult::Interrupted(self.id));
+                return;
             }
+            self.step_count += 1; 
+            self.step();
+            self.record_step();
+        }   
+    }
 
-            Stability::Stable => {
-                self.stability = Stability::Stable;
+    // applies scheduled events, samples the trajectory and checks passage targets after a step
+    fn record_step(&mut self) {
+        if !self.events.is_empty() {
+            self.apply_events();
+        }
+        if let Some(interval) = self.trajectory_interval {
+            if self.step_count % interval == 0 {
+                let sample = self.sample();
+                self.trajectory.push(sample);
+            }
+        }
+        for (target, passage_step) in self.passage_targets.iter().zip(self.passage_steps.iter_mut()) {
+            if passage_step.is_none() && target.is_met(self.reaction_network.get_solution()) {
+                *passage_step = Some(self.step_count);
             }
         }
+        if !self.alarms.is_empty() {
+            self.check_alarms();
+        }
+        if self.stop_targets.iter().any(|target| target.is_met(self.reaction_network.get_solution())) {
+            self.stable = true;
+        }
+        if !self.stable && self.max_steps.is_some_and(|max_steps| self.step_count >= max_steps) {
+            self.stable = true;
+            self.termination = Termination::StepLimit;
+        }
+        if !self.stable && self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            self.stable = true;
+            self.termination = Termination::TimedOut;
+        }
     }
-}
 
-enum Stability {
-    Initial, 
-    Unstable,
-    SemiStable(i32),
-    Stable,
-}
\ No newline at end of file
+    // reports the stable solution along with the trajectory and passage steps if they were recorded, None once the engine stopped listening
+    fn finish(&mut self, trial_tx: SyncSender<TrialResult>) -> Option<()> {
+        if let Some(failure) = self.failure.take() {
+            return trial_tx.send(TrialResult::Failed(failure, self.id)).ok();
+        }
+        if let Some(observer) = &self.observer {
+            observer.on_trial_complete(self.id, self.reaction_network.get_solution());
+        }
+        if self.trajectory_interval.is_some() {
+            trial_tx.send(TrialResult::Trajectory(std::mem::take(&mut self.trajectory), self.id)).ok()?;
+        }
+        if !self.passage_targets.is_empty() {
+            trial_tx.send(TrialResult::FirstPassage(std::mem::take(&mut self.passage_steps), self.id)).ok()?;
+        }
+        if let Some(cycle) = self.stability_detector.get_cycle() {
+            self.termination = Termination::Oscillating;
+            trial_tx.send(TrialResult::Cycle(cycle, self.id)).ok()?;
+        }
+        let solution = match self.sparse {
+            true => self.reaction_network.get_solution().sparse(),
+            false => self.reaction_network.get_solution().clone(),
+        };
+        trial_tx.send(TrialResult::StableSolution(solution, self.step_count, self.reactions_fired, std::mem::take(&mut self.firings), self.termination, self.id)).ok()?;
+        return Some(());
+    }
+
+    // fires a single reaction and reports it to the observer if there is one
+    fn react(&mut self) {
+        let fired = match self.reaction_network.try_react(self.rng.as_mut()) {
+            Ok(fired) => fired,
+            Err(failure) => {
+                self.failure = Some(failure);
+                self.stable = true;
+                None
+            }
+        };
+        if let Some(reaction) = &fired {
+            self.reactions_fired += 1;
+            *self.firings.counts.entry(reaction.get_fingerprint()).or_insert(0) += 1;
+            log::log(Level::Trace, format_args!("trial {} fired {} at step {}", self.id, reaction, self.step_count));
+            if let Some(observer) = &self.observer {
+                observer.on_reaction_fired(self.id, reaction, self.step_count);
+            }
+        }
+        self.last_reaction = fired;
+    }
+
+    fn step(&mut self) {
+        self.react();
+        // nothing fired, either no reaction is possible or all possible ones have a rate of 0 so none ever will
+        if self.last_reaction.is_none() {
+            self.stable = true;
+            return;
+        }
+        let first_fingerprint = self.last_reaction.as_ref().map(|reaction| reaction.get_fingerprint());
+        match self.stability_detector.check(&self.reaction_network, self.last_reaction.as_ref()) {
+            Verdict::Continue => {
+                // the detector was not confirming stability after all
+                self.firings.tail.clear();
+                self.firings.tail_steps = 0;
+            }
+            Verdict::Stable => self.stable = true,
+            Verdict::FireAgain { stable } => {
+                self.react();
+                self.stable = stable || self.last_reaction.is_none();
+                let second_fingerprint = self.last_reaction.as_ref().map(|reaction| reaction.get_fingerprint());
+                for fingerprint in [first_fingerprint, second_fingerprint].into_iter().flatten() {
+                    *self.firings.tail.entry(fingerprint).or_insert(0) += 1;
+                }
+                self.firings.tail_steps += 1;
+            }
+        }
+    }
+}
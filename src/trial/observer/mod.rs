@@ -0,0 +1,14 @@
+use super::reaction_network::reaction::{Reaction, term::solution::Solution};
+
+/// Hooks into a trial as it runs.
+///
+/// Every trial in a run shares the same observer and trials run on multiple threads,
+/// so implementations must be thread safe. Both callbacks do nothing by default
+/// which allows an observer to only implement the events it cares about.
+pub trait StepObserver: Send + Sync {
+    /// called from the trial's thread each time a reaction fires
+    fn on_reaction_fired(&self, _trial_id: usize, _reaction: &Reaction, _step: i32) {}
+
+    /// called with the stable solution once a trial finishes
+    fn on_trial_complete(&self, _trial_id: usize, _solution: &Solution) {}
+}
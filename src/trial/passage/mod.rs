@@ -0,0 +1,66 @@
+use super::reaction_network::reaction::term::solution::{Count, Species, Solution};
+
+/// How a species count is compared against the count of a passage target
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    Greater,
+}
+
+/// A condition on a single species count, such as `return >= 55`.
+/// Trials record the first step at which each target holds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PassageTarget {
+    species: Species,
+    comparison: Comparison,
+    count: Count,
+}
+
+impl PassageTarget {
+    pub fn new(species: &str, comparison: Comparison, count: Count) -> Self {
+        return Self { species: Species::Name(species.to_string()), comparison, count };
+    }
+
+    pub fn get_species(&self) -> &Species {
+        return &self.species;
+    }
+
+    pub fn get_comparison(&self) -> Comparison {
+        return self.comparison;
+    }
+
+    pub fn get_count(&self) -> Count {
+        return self.count;
+    }
+
+    /// returns true if the solution satisfies the target, a species missing from the solution has a count of 0
+    pub fn is_met(&self, solution: &Solution) -> bool {
+        let current = match solution.species_counts.get(&self.species) {
+            Some(Species::Count(count)) => *count,
+            _ => 0,
+        };
+        return match self.comparison {
+            Comparison::Less => current < self.count,
+            Comparison::LessOrEqual => current <= self.count,
+            Comparison::Equal => current == self.count,
+            Comparison::GreaterOrEqual => current >= self.count,
+            Comparison::Greater => current > self.count,
+        };
+    }
+}
+
+impl std::fmt::Display for PassageTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let comparison = match self.comparison {
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Equal => "==",
+            Comparison::GreaterOrEqual => ">=",
+            Comparison::Greater => ">",
+        };
+        write!(f, "{} {} {}", self.species, comparison, self.count)
+    }
+}
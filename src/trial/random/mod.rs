@@ -0,0 +1,169 @@
+use rand::{RngCore, SeedableRng, rngs::StdRng};
+use rand_pcg::Pcg64;
+use rand_xoshiro::Xoshiro256PlusPlus;
+
+/// Selects the pseudo random number generator each trial draws reactions from.
+/// StdRng is the default as it is cryptographically strong, 
+/// Xoshiro256++ and Pcg64 are considerably faster and are usually the better fit for large monte carlo runs.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RngBackend {
+    #[default]
+    Std,
+    Xoshiro256PlusPlus,
+    Pcg64,
+}
+
+impl RngBackend {
+    /// Creates a new generator of the selected type seeded from the given value
+    pub fn seeded(&self, seed: u64) -> Box<dyn RngCore + Send> {
+        match self {
+            Self::Std => Box::new(StdRng::seed_from_u64(seed)),
+            Self::Xoshiro256PlusPlus => Box::new(Xoshiro256PlusPlus::seed_from_u64(seed)),
+            Self::Pcg64 => Box::new(Pcg64::seed_from_u64(seed)),
+        }
+    }
+
+    /// Creates the generator for one labelled stream of a trial
+    pub fn stream(&self, seed: u64, stream: RngStream) -> Box<dyn RngCore + Send> {
+        return self.seeded(stream.derive_seed(seed));
+    }
+}
+
+/// Each source of randomness in a trial draws from its own stream, all derived from the trial seed.
+/// Adding draws to one stream (e.g. noise on initial counts) therefore never shifts the sequence seen by another,
+/// so the reaction sequence of an existing seed stays the same.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RngStream {
+    /// Draws of initial species counts, such as the counts of a stratum
+    InitialSolution,
+    /// Draws of reaction rates, such as noise on the rates of a network
+    ReactionRates,
+    /// The choice of which reaction fires at every step
+    ReactionSelection,
+}
+
+impl RngStream {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::InitialSolution => "initial_solution",
+            Self::ReactionRates => "reaction_rates",
+            Self::ReactionSelection => "reaction_selection",
+        }
+    }
+
+    /// Mixes the stream label into a seed using SplitMix64 so that neighbouring seeds produce unrelated streams
+    pub fn derive_seed(&self, seed: u64) -> u64 {
+        let mut label_hash: u64 = 0xcbf29ce484222325;
+        for byte in self.label().as_bytes() {
+            label_hash ^= *byte as u64;
+            label_hash = label_hash.wrapping_mul(0x100000001b3);
+        }
+
+        return split_mix(seed ^ label_hash);
+    }
+}
+
+/// Wraps a generator and returns the bitwise complement of every value it produces.
+/// A uniform draw `u` therefore becomes roughly `1 - u`, which is the antithetic counterpart used when pairing trials.
+pub struct Antithetic {
+    inner: Box<dyn RngCore + Send>,
+}
+
+impl Antithetic {
+    pub fn new(inner: Box<dyn RngCore + Send>) -> Self {
+        return Self { inner };
+    }
+}
+
+impl RngCore for Antithetic {
+    fn next_u32(&mut self) -> u32 {
+        return !self.inner.next_u32();
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        return !self.inner.next_u64();
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.inner.fill_bytes(dest);
+        for byte in dest.iter_mut() {
+            *byte = !*byte;
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        return Ok(());
+    }
+}
+
+/// Derives the seed of the trial at `index` from a master seed. 
+/// This is the `index`th output of a SplitMix64 sequence started at the master seed, 
+/// so seeds are independent of how many trials are run or in which order they are created.
+pub fn trial_seed(master_seed: u64, index: usize) -> u64 {
+    return split_mix(master_seed.wrapping_add((index as u64).wrapping_mul(0x9e3779b97f4a7c15)));
+}
+
+/// A single round of SplitMix64
+pub fn split_mix(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    return z ^ (z >> 31);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_streams_are_independent() {
+        let seed = 42;
+        assert_eq!(RngStream::ReactionSelection.derive_seed(seed), RngStream::ReactionSelection.derive_seed(seed));
+        assert_ne!(RngStream::ReactionSelection.derive_seed(seed), RngStream::InitialSolution.derive_seed(seed));
+        assert_ne!(RngStream::ReactionSelection.derive_seed(seed), RngStream::ReactionRates.derive_seed(seed));
+        assert_ne!(RngStream::InitialSolution.derive_seed(seed), RngStream::ReactionRates.derive_seed(seed));
+    }
+
+    #[test]
+    fn test_draws_from_one_stream_do_not_shift_another() {
+        let selection = |initial_draws: usize, rate_draws: usize| -> Vec<u64> {
+            let mut initial_solution = RngBackend::Xoshiro256PlusPlus.stream(9, RngStream::InitialSolution);
+            let mut reaction_rates = RngBackend::Xoshiro256PlusPlus.stream(9, RngStream::ReactionRates);
+            let mut reaction_selection = RngBackend::Xoshiro256PlusPlus.stream(9, RngStream::ReactionSelection);
+            for _ in 0..initial_draws {
+                initial_solution.next_u64();
+            }
+            for _ in 0..rate_draws {
+                reaction_rates.next_u64();
+            }
+            return (0..8).map(|_| reaction_selection.next_u64()).collect();
+        };
+        // adding noise to the initial counts or the rates leaves the reaction sequence of the seed as it was
+        assert_eq!(selection(0, 0), selection(5, 0));
+        assert_eq!(selection(0, 0), selection(0, 3));
+        assert_eq!(selection(0, 0), selection(100, 100));
+    }
+
+    #[test]
+    fn test_antithetic_draws_complemented_uniforms() {
+        use rand::Rng;
+        let mut plain = RngBackend::Pcg64.stream(3, RngStream::ReactionSelection);
+        let mut mirrored = Antithetic::new(RngBackend::Pcg64.stream(3, RngStream::ReactionSelection));
+        for _ in 0..100 {
+            let (u, mirrored_u) = (plain.gen::<f64>(), mirrored.gen::<f64>());
+            assert!((u + mirrored_u - 1.0).abs() < 1e-15, "{} is not mirrored by {}", u, mirrored_u);
+        }
+        let (mut bytes, mut mirrored_bytes) = ([0u8; 8], [0u8; 8]);
+        plain.fill_bytes(&mut bytes);
+        mirrored.fill_bytes(&mut mirrored_bytes);
+        assert_eq!(bytes.map(|byte| !byte), mirrored_bytes);
+    }
+
+    #[test]
+    fn test_stream_is_reproducible() {
+        let mut first = RngBackend::Pcg64.stream(7, RngStream::ReactionSelection);
+        let mut second = RngBackend::Pcg64.stream(7, RngStream::ReactionSelection);
+        assert_eq!(first.next_u64(), second.next_u64());
+    }
+}
@@ -0,0 +1,188 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use super::ReactionNetwork;
+use super::reaction::{Reaction, term::{Term, solution::{Count, Species, Solution}}};
+
+/// Separates a species name from the name of the compartment it lives in within the flattened network
+pub const SEPARATOR: char = '@';
+
+/// returns the name a species in a compartment goes by in the flattened network, `species@compartment`
+pub fn qualified(species: &str, compartment: &str) -> String {
+    return format!("{}{}{}", species, SEPARATOR, compartment);
+}
+
+/// Builds a reaction network out of named compartments, such as the droplets of a DNA circuit.
+///
+/// Every compartment holds its own copy of each species. Local reactions only involve species of one compartment
+/// and transport reactions move a species from one compartment to another. 
+/// Molecules meet less often in a larger compartment, so the rate of a local reaction with `n` reactant molecules 
+/// is scaled by `volume^(1 - n)`, leaving unimolecular reactions and transport as they are.
+/// The result is an ordinary `ReactionNetwork` where each species is named `species@compartment`, 
+/// so everything the engine reports can be split back by compartment, e.g. with `FinalReport::compartment`.
+///
+/// # Example
+/// ```no_run
+/// use std::collections::HashSet;
+/// use marlea_engine::{Builder, trial::reaction_network::{compartments::Compartments, reaction::{Reaction, term::Term}}};
+///
+/// let decay = Reaction::new(HashSet::from([Term::new("a".to_string(), 1)]), HashSet::new(), 1.0);
+/// let network = Compartments::new()
+///     .compartment("left", 1.0, &[("a", 100)])
+///     .compartment("right", 2.0, &[])
+///     .local(&HashSet::from([decay]))
+///     .transport("a", "left", "right", 2.0)
+///     .build();
+/// let report = Builder::new(network).build().run();
+/// println!("{:?}", report.compartment("right"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Compartments {
+    // volume and initial counts of each compartment in the order they were added
+    compartments: Vec<(String, f64, HashMap<String, Count>)>,
+    reactions: HashSet<Reaction>,
+    // reactions added with `local` are applied to every compartment on build, including those declared after them
+    shared_reactions: Vec<Reaction>,
+}
+
+impl Compartments {
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    /// Adds a compartment with its volume relative to the others and the initial counts of its species, species not listed start at 0. 
+    /// Panics unless the volume is positive and finite
+    pub fn compartment(mut self, name: &str, volume: f64, counts: &[(&str, Count)]) -> Self {
+        if name.contains(SEPARATOR) {
+            panic!("compartment name {} may not contain {}", name, SEPARATOR);
+        }
+        if !(volume.is_finite() && volume > 0.0) {
+            panic!("compartment {} needs a positive volume, found {}", name, volume);
+        }
+        if self.has_compartment(name) {
+            panic!("compartment {} was added twice", name);
+        }
+        let counts = counts.iter().map(|(species, count)| (species.to_string(), *count)).collect();
+        self.compartments.push((name.to_string(), volume, counts));
+        return self;
+    }
+
+    /// Adds reactions which take place inside every compartment
+    pub fn local(mut self, reactions: &HashSet<Reaction>) -> Self {
+        self.shared_reactions.extend(reactions.iter().cloned());
+        return self;
+    }
+
+    /// Adds reactions which only take place inside the named compartment
+    pub fn local_in(mut self, compartment: &str, reactions: &HashSet<Reaction>) -> Self {
+        self.expect_compartment(compartment);
+        let volume = self.volume(compartment);
+        for reaction in reactions {
+            self.reactions.insert(Self::localize(reaction, compartment, volume));
+        }
+        return self;
+    }
+
+    /// Adds a reaction moving one molecule of `species` from one compartment to another at the given rate
+    pub fn transport(mut self, species: &str, from: &str, to: &str, rate: f64) -> Self {
+        self.expect_compartment(from);
+        self.expect_compartment(to);
+        self.reactions.insert(Reaction::new(
+            HashSet::from([Term::new(qualified(species, from), 1)]), 
+            HashSet::from([Term::new(qualified(species, to), 1)]), 
+            rate,
+        ));
+        return self;
+    }
+
+    /// Flattens the compartments into a single network
+    pub fn build(self) -> ReactionNetwork {
+        let mut reactions = self.reactions;
+        for (compartment, volume, _) in &self.compartments {
+            for reaction in &self.shared_reactions {
+                reactions.insert(Self::localize(reaction, compartment, *volume));
+            }
+        }
+
+        // every species appearing in a reaction exists, starting at 0 unless a count was given
+        let mut species_counts: BTreeMap<String, Count> = BTreeMap::new();
+        for reaction in &reactions {
+            for term in reaction.get_reactants().iter().chain(reaction.get_products()) {
+                species_counts.insert(term.get_species_name().to_string(), 0);
+            }
+        }
+        for (compartment, _, counts) in &self.compartments {
+            for (species, count) in counts {
+                species_counts.insert(qualified(species, compartment), *count);
+            }
+        }
+
+        let solution = Solution { species_counts: species_counts.into_iter()
+            .map(|(name, count)| (Species::Name(name), Species::Count(count)))
+            .collect() };
+        return ReactionNetwork::new(reactions, solution);
+    }
+
+    fn has_compartment(&self, name: &str) -> bool {
+        return self.compartments.iter().any(|(compartment, _, _)| compartment == name);
+    }
+
+    fn volume(&self, name: &str) -> f64 {
+        return self.compartments.iter().find(|(compartment, _, _)| compartment == name).map_or(1.0, |(_, volume, _)| *volume);
+    }
+
+    fn expect_compartment(&self, name: &str) {
+        if !self.has_compartment(name) {
+            panic!("no compartment {}, compartments have to be added before reactions refer to them", name);
+        }
+    }
+
+    // returns the reaction on the species of the compartment, its rate scaled by `volume^(1 - order)`
+    fn localize(reaction: &Reaction, compartment: &str, volume: f64) -> Reaction {
+        let localize_terms = |terms: &HashSet<Term>| -> HashSet<Term> {
+            terms.iter()
+                .map(|term| Term::new(qualified(&term.get_species_name().to_string(), compartment), term.get_coefficient() as u8))
+                .collect()
+        };
+        let order: u64 = reaction.get_reactants().iter().map(|term| term.get_coefficient()).sum();
+        let rate = reaction.get_reaction_rate() * volume.powf(1.0 - order as f64);
+        return Reaction::new(localize_terms(reaction.get_reactants()), localize_terms(reaction.get_products()), rate);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_qualifies_species() {
+        let decay = Reaction::new(HashSet::from([Term::new("a".to_string(), 1)]), HashSet::new(), 1.0);
+        let network = Compartments::new()
+            .compartment("left", 1.0, &[("a", 10)])
+            .compartment("right", 1.0, &[])
+            .local(&HashSet::from([decay]))
+            .transport("a", "left", "right", 1.0)
+            .build();
+
+        assert_eq!(network.get_reactions().len(), 3);
+        let counts = &network.get_solution().species_counts;
+        assert_eq!(counts[&Species::Name("a@left".to_string())], Species::Count(10));
+        assert_eq!(counts[&Species::Name("a@right".to_string())], Species::Count(0));
+    }
+
+    #[test]
+    fn test_volume_scales_local_rates() {
+        let term = |name: &str, coefficient: u8| Term::new(name.to_string(), coefficient);
+        let dimerize = Reaction::new(HashSet::from([term("a", 2)]), HashSet::from([term("b", 1)]), 6.0);
+        let decay = Reaction::new(HashSet::from([term("b", 1)]), HashSet::new(), 1.0);
+        let network = Compartments::new()
+            .compartment("small", 1.0, &[("a", 10)])
+            .local(&HashSet::from([dimerize, decay]))
+            // declared after the shared reactions and still gets them
+            .compartment("large", 3.0, &[("a", 10)])
+            .build();
+
+        let rate = |reaction: &str| network.get_reactions().iter().find(|found| found.to_string() == reaction).unwrap().get_reaction_rate();
+        assert_eq!(rate("2 a@small => b@small"), 6.0);
+        assert!((rate("2 a@large => b@large") - 2.0).abs() < 1e-12);
+        assert_eq!(rate("b@large => "), 1.0);
+    }
+}
@@ -1,8 +1,85 @@
-use std::collections::HashSet;
-use rand::Rng;
-use reaction::{Reaction, term::solution::{Species, Solution}};
+use std::{collections::{BTreeSet, HashSet}, sync::Arc};
+use rand::{Rng, RngCore};
+use super::random::split_mix;
+use reaction::{Reaction, term::{Term, solution::{apply_change, Count, Species, Solution}}};
 
+pub mod compartments;
 pub mod reaction; 
+pub mod reduction;
+
+/// How `ReactionNetwork::merge` resolves species which both networks contain
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Collision {
+    /// Refuses to merge, naming the colliding species
+    Reject,
+    /// Treats species of the same name as one species, as if the two solutions were mixed, so their counts add up.
+    /// This is how sub-circuits are wired together through shared signal species.
+    Share,
+    /// Keeps the colliding species of the merged network apart by appending a suffix to their names
+    Rename(String),
+}
+
+/// A reaction which would have taken a species count out of range, the network is left as it was before the reaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CountOutOfRange {
+    pub reaction: String,
+    pub species: String,
+    pub count: Count,
+    pub change: i64,
+}
+
+impl std::fmt::Display for CountOutOfRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "reaction {} would change the count {} of {} by {}", self.reaction, self.count, self.species, self.change)
+    }
+}
+
+impl std::error::Error for CountOutOfRange {}
+
+/// Reactions naming species which are not in the solution, each with the species it is missing sorted by name.
+/// A species only ever named in reactions is often a typo of one which is in the solution.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownSpecies {
+    pub reactions: Vec<(String, Vec<String>)>,
+}
+
+impl std::fmt::Display for UnknownSpecies {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let reactions: Vec<String> = self.reactions.iter()
+            .map(|(reaction, species)| format!("{} (missing {})", reaction, species.join(", ")))
+            .collect();
+        write!(f, "reactions reference species absent from the solution: {}", reactions.join("; "))
+    }
+}
+
+impl std::error::Error for UnknownSpecies {}
+
+/// Reasons two networks can not be merged, returned by `ReactionNetwork::try_merge`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NetworkMergeError {
+    /// Species both networks contain sorted by name, under `Collision::Reject` or still after renaming
+    Collision(Vec<String>),
+    /// The counts of a shared species add up past the largest count
+    CountOutOfRange(CountOutOfRange),
+}
+
+impl std::fmt::Display for NetworkMergeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Collision(species) => write!(f, "species {} are in both networks", species.join(", ")),
+            Self::CountOutOfRange(out_of_range) => write!(f, "{}", out_of_range),
+        }
+    }
+}
+
+impl std::error::Error for NetworkMergeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        return match self {
+            Self::CountOutOfRange(out_of_range) => Some(out_of_range),
+            Self::Collision(_) => None,
+        };
+    }
+}
 
 #[derive(Clone)]
 /// A `ReactionNetwork` represents a computational netowork of chemical reactions.
@@ -11,44 +88,134 @@ pub mod reaction;
 ///
 /// - `reactions`: a set of all the reactions in the network, represented as instances of `Reaction`.
 /// - `possible_reactions`: a subset of `reactions` that are currently possible to occur based on the current state
-///                        of the system (i.e. the concentration of Species in solution). This is updated at each time step.
+///   of the system (i.e. the concentration of Species in solution). This is updated at each time step.
 /// - `null_adjacent_reactions`: a subset of `reactions` that involve only products, 
-///                              or involve reactants produced by `reactions` only involving products.
-///                              i.e. they are adjacent to null species.
-///                              This is used to speed up computations.
+///   or involve reactants produced by `reactions` only involving products.
+///   i.e. they are adjacent to null species.
+///   This is used to speed up computations.
 /// - `solution`: a dictionary that maps Species::Names to their Species::counts
+///   It is held behind an `Arc` and copied on write, so snapshots taken for timelines 
+///   and clones of the prime network share it until one of them reacts.
+/// - `changed_species`: names of species whose counts changed since changes were last taken, used for delta reporting.
+/// - `clamped_species`: names of species held at a constant count, such as buffers and fuel strands, 
+///   which reactions may require but never deplete or add to.
+/// - `initialized_species`: names of species only named in reactions which `new` added to the solution with a count of 0.
 ///
 /// The lifetime parameter `'reaction_network` is used to tie the struct to the lifetime of its dependencies,
 /// such as instances of `Reaction` and `Species`.
 pub struct ReactionNetwork {
-    reactions: HashSet<Reaction>,
-    possible_reactions: HashSet<Reaction>, 
-    null_adjacent_reactions: HashSet<Reaction>,
-    solution: Solution,
+    reactions: BTreeSet<Reaction>,
+    possible_reactions: BTreeSet<Reaction>, 
+    null_adjacent_reactions: BTreeSet<Reaction>,
+    solution: Arc<Solution>,
+    changed_species: HashSet<Species>,
+    clamped_species: HashSet<Species>,
+    // whether every rate is a whole number, in which case reactions are selected with exact integer arithmetic
+    integer_rates: bool,
+    initialized_species: Vec<String>,
 }
 
 impl ReactionNetwork {
 
     pub fn new(reactions: HashSet<Reaction>, solution: Solution)-> Self {
-        // Initialize null_adjacent_reactions and possible_reactions as empty HashSet
-        let null_adjacent_reactions = HashSet::new();
-        let possible_reactions = HashSet::new();
+        // Reactions are kept in ordered sets so that weighted selection walks them in the same order in every process,
+        // which is what makes seeded runs reproducible.
+        let reactions: BTreeSet<Reaction> = reactions.into_iter().collect();
+
+        // species only named in reactions start at 0, otherwise missing reactants would never limit a reaction
+        let mut solution = solution;
+        let mut initialized_species = BTreeSet::new();
+        for term in reactions.iter().flat_map(|reaction| reaction.get_reactants().iter().chain(reaction.get_products())) {
+            if !solution.species_counts.contains_key(term.get_species_name()) {
+                solution.species_counts.insert(term.get_species_name().clone(), Species::Count(0));
+                initialized_species.insert(term.get_species_name().to_string());
+            }
+        }
+
+        let null_adjacent_reactions = BTreeSet::new();
+        let possible_reactions = BTreeSet::new();
 
         // Make a new instance of Self with the provided arguments and initialized fields.
-        let mut new_netowrk = Self{reactions, solution, null_adjacent_reactions, possible_reactions};
+        let mut new_netowrk = Self{reactions, solution: Arc::new(solution), null_adjacent_reactions, possible_reactions, changed_species: HashSet::new(), clamped_species: HashSet::new(), integer_rates: true, initialized_species: initialized_species.into_iter().collect()};
 
         // Generate and cache null adjacent reactions up front
-        new_netowrk.gen_null_adjacent_reactions();
+        new_netowrk.regenerate();
 
         return new_netowrk;
     }
 
-    pub fn get_null_adjacent_reactions(&self) -> &HashSet<Reaction> {
-        // Returns a reference to the null_adjacent_reactions HashSet
+    // the same as `new` but refuses reactions with reactants or products absent from the solution
+    // rather than starting those species at 0
+    pub fn new_strict(reactions: HashSet<Reaction>, solution: Solution) -> Result<Self, UnknownSpecies> {
+        let network = Self::new(reactions, solution);
+        if network.initialized_species.is_empty() {
+            return Ok(network);
+        }
+
+        let mut reactions: Vec<(String, Vec<String>)> = network.reactions.iter()
+            .filter_map(|reaction| {
+                let missing: BTreeSet<String> = reaction.get_reactants().iter().chain(reaction.get_products())
+                    .map(|term| term.get_species_name().to_string())
+                    .filter(|name| network.initialized_species.contains(name))
+                    .collect();
+                match missing.is_empty() {
+                    true => None,
+                    false => Some((reaction.to_string(), missing.into_iter().collect())),
+                }
+            })
+            .collect();
+        reactions.sort();
+        return Err(UnknownSpecies { reactions });
+    }
+
+    // returns the species only named in reactions which were added to the solution with a count of 0, sorted by name
+    pub fn get_initialized_species(&self) -> &[String] {
+        return &self.initialized_species;
+    }
+
+    pub fn get_reactions(&self) -> &BTreeSet<Reaction> {
+        return &self.reactions;
+    }
+
+    // Combines the fingerprints of all reactions and the current solution into one value identifying the network
+    pub fn fingerprint(&self) -> u64 {
+        let mut reaction_fingerprints: Vec<u64> = self.reactions.iter().map(|reaction| reaction.get_fingerprint()).collect();
+        reaction_fingerprints.sort();
+
+        let mut fingerprint: u64 = 0;
+        for reaction_fingerprint in reaction_fingerprints {
+            fingerprint = split_mix(fingerprint ^ reaction_fingerprint);
+        }
+        for (name, count) in self.solution.iter() {
+            for byte in name.to_string().bytes().chain([0]) {
+                fingerprint = split_mix(fingerprint ^ byte as u64);
+            }
+            if let Species::Count(count) = count {
+                // a wide count mixes in one 8 byte word at a time
+                for word in count.to_le_bytes().chunks(8) {
+                    fingerprint = split_mix(fingerprint ^ u64::from_le_bytes(word.try_into().unwrap()));
+                }
+            }
+            if self.clamped_species.contains(name) {
+                fingerprint = split_mix(fingerprint ^ 1);
+            }
+        }
+        return fingerprint;
+    }
+
+    pub fn get_null_adjacent_reactions(&self) -> &BTreeSet<Reaction> {
+        // Returns a reference to the null_adjacent_reactions set
         return &self.null_adjacent_reactions;
     }
 
-    // Clears the null_adjacent_reactions HashSet and generates a new set.
+    // Regenerates everything cached about the set of reactions, has to be called whenever it changes
+    fn regenerate(&mut self) {
+        self.possible_reactions.clear();
+        self.gen_null_adjacent_reactions();
+        self.integer_rates = self.reactions.iter().all(|reaction| reaction.has_integer_rate());
+    }
+
+    // Clears the null_adjacent_reactions set and generates a new set.
     fn gen_null_adjacent_reactions(&mut self) {
 
         self.null_adjacent_reactions.clear();
@@ -57,7 +224,7 @@ impl ReactionNetwork {
             // Check for reactions that only have products (null adjacent).
             if reaction.get_reactants().is_empty() {
 
-                // Insert the reaction into the null_adjacent_reactions HashSet and access its corresponding product(s)
+                // Insert the reaction into the null_adjacent_reactions set and access its corresponding product(s)
                 if self.null_adjacent_reactions.insert(reaction.clone()) {
                     for product in reaction.get_products() {
                         let null_generated_species = product.get_species_name();
@@ -67,7 +234,7 @@ impl ReactionNetwork {
                             for secondary_reactant in secondary_reaction.get_reactants() {
 
                                 if null_generated_species == secondary_reactant.get_species_name() {
-                                    // Insert the reaction into the null_adjacent_reactions HashSet.
+                                    // Insert the reaction into the null_adjacent_reactions set.
                                     self.null_adjacent_reactions.insert(secondary_reaction.clone());
                                 }
                             }
@@ -79,11 +246,11 @@ impl ReactionNetwork {
     }
 
 
-    pub fn get_possible_reactions(&self) -> &HashSet<Reaction> {
+    pub fn get_possible_reactions(&self) -> &BTreeSet<Reaction> {
         return &self.possible_reactions;
     }
 
-    fn find_possible_reactions<'finding>(&'finding mut self) {
+    fn find_possible_reactions(&mut self) {
         self.possible_reactions.clear();
         
         // loop over all reactions and check if it's possible for them to occur based on current species concentration
@@ -94,66 +261,544 @@ impl ReactionNetwork {
         }
     }
 
-    fn sum_reaction_rates (&self) -> u128 {
+    // None if the sum does not fit in a u128, wrapping around would bias the selection without a trace
+    fn sum_reaction_rates (&self) -> Option<u128> {
         let mut sum: u128 = 0; 
         // loop over all possible reactions and sum their reaction rates
         for reaction in &self.possible_reactions {
-            sum += reaction.get_reaction_rate();
+            sum = sum.checked_add(reaction.get_reaction_rate() as u128)?;
         }
-        return sum;
+        return Some(sum);
     }
 
 
-    // Get a possible reaction from the set of possible reactions with weighted probability
-    pub fn get_next_reaction<'getting> (&'getting self) -> Option<Reaction> {
-        let mut index = rand::thread_rng().gen_range(0.. self.sum_reaction_rates());
+    // Get a possible reaction from the set of possible reactions with weighted probability, 
+    // None if there is none or every possible reaction has a rate of 0
+    pub fn get_next_reaction (&self, rng: &mut dyn RngCore) -> Option<Reaction> {
+        if !self.integer_rates {
+            return self.get_next_reaction_fractional(rng);
+        }
+
+        // integer rates stay below u64::MAX so this takes more reactions than fit in memory, select in floating point all the same
+        let Some(sum) = self.sum_reaction_rates() else {
+            return self.get_next_reaction_fractional(rng);
+        };
+        if sum == 0 {
+            return None;
+        }
+        // scaling a single draw rather than rejection sampling keeps the choice monotonic in the draw, 
+        // so the complemented draw of an antithetic partner picks the mirrored reaction. The bias is below sum / 2^64
+        let mut index = match u64::try_from(sum) {
+            Ok(_) => (rng.next_u64() as u128 * sum) >> 64,
+            Err(_) => rng.gen_range(0..sum),
+        };
         let mut next_reaction: Option<Reaction>= None;
 
         // iterate through all possible valid reactions and pick one based on its probability 
         for reaction in self.get_possible_reactions() {
-            if reaction.get_reaction_rate() > index {
+            let rate = reaction.get_reaction_rate() as u128;
+            if rate > index {
                 next_reaction = Some(reaction.clone());
                 break;
             } else {
-                index -= reaction.get_reaction_rate();
+                index -= rate;
             }
         }
 
         return next_reaction;
     }
 
+    // The same weighted selection in floating point for networks with fractional rates
+    fn get_next_reaction_fractional (&self, rng: &mut dyn RngCore) -> Option<Reaction> {
+        let mut scale = 1.0;
+        let mut sum: f64 = self.possible_reactions.iter().map(|reaction| reaction.get_reaction_rate()).sum();
+        if sum.is_infinite() {
+            // rates close to f64::MAX overflow the sum, relative to the largest rate it stays below the number of reactions
+            scale = self.possible_reactions.iter().map(|reaction| reaction.get_reaction_rate()).fold(0.0, f64::max);
+            sum = self.possible_reactions.iter().map(|reaction| reaction.get_reaction_rate() / scale).sum();
+        }
+        if sum <= 0.0 {
+            return None;
+        }
+        let mut index = rng.gen_range(0.0..sum);
+
+        for reaction in self.get_possible_reactions() {
+            let rate = reaction.get_reaction_rate() / scale;
+            if rate > index {
+                return Some(reaction.clone());
+            }
+            index -= rate;
+        }
+
+        // rounding can leave a sliver of the sum past the last reaction
+        return self.possible_reactions.iter().rev().find(|reaction| reaction.get_reaction_rate() > 0.0).cloned();
+    }
+
     // This function reacts based on the randomly selected Reaction instance
-    pub fn react<'reacting> (&'reacting mut self) {
+    // returns the reaction which fired or None if no reaction could fire, panics if a count would go out of range
+    pub fn react (&mut self, rng: &mut dyn RngCore) -> Option<Reaction> {
+        return self.try_react(rng).unwrap_or_else(|error| panic!("{}", error));
+    }
+
+    // The same as `react` but returns an error rather than underflowing or overflowing a count,
+    // which can only happen if the possible reactions are out of date with the solution
+    pub fn try_react (&mut self, rng: &mut dyn RngCore) -> Result<Option<Reaction>, CountOutOfRange> {
         // update the list of possible reactions. 
         self.find_possible_reactions();
 
-        if !self.possible_reactions.is_empty() {
-            if let Some(reaction) = self.get_next_reaction() {
-                for reactant in reaction.get_reactants() {
-                    self.solution.species_counts.entry(reactant.get_species_name().clone())
-                        .and_modify(|species_count|
-                            if let Species::Count(current_count) =species_count{
-                                *current_count -= reactant.get_coefficient();
-                            });
+        // no reaction fires when none is possible or all possible ones have a rate of 0, the network has stalled
+        if let Some(reaction) = self.get_next_reaction(rng) {
+            // check every change before applying any so a failed reaction leaves the solution untouched
+            for (name, change) in reaction.get_net_changes() {
+                if let (false, Some(Species::Count(count))) = (self.clamped_species.contains(name), self.solution.species_counts.get(name)) {
+                    if apply_change(*count, *change).is_none() {
+                        return Err(CountOutOfRange { reaction: reaction.to_string(), species: name.to_string(), count: *count, change: *change });
+                    }
                 }
+            }
 
-                for product in reaction.get_products() {
-                    self.solution.species_counts.entry(product.get_species_name().clone())
-                        .and_modify(|species_count|
-                            if let Species::Count(current_count) = species_count {
-                                *current_count += product.get_coefficient();
-                            });
+            // only copies the species map if a snapshot of it is still alive elsewhere
+            let solution = Arc::make_mut(&mut self.solution);
+            for (name, change) in reaction.get_net_changes() {
+                if self.clamped_species.contains(name) {
+                    continue;
+                }
+                self.changed_species.insert(name.clone());
+                if let Some(Species::Count(current_count)) = solution.species_counts.get_mut(name) {
+                    *current_count = apply_change(*current_count, *change).unwrap();
                 }
-            } 
-            else {
-                panic!("failed to get next reaction in react()");
             }
+            return Ok(Some(reaction));
         }
+        return Ok(None);
     }
 
     // returns a reference to the map containing the current state of the reaction network 
     pub fn get_solution(&self) -> &Solution {
         return &self.solution;
     }
+
+    // returns a shared snapshot of the current state without copying the species map
+    pub fn get_solution_snapshot(&self) -> Arc<Solution> {
+        return self.solution.clone();
+    }
+
+    // returns the current counts of every species changed since the last call and resets the change set
+    pub fn take_changes(&mut self) -> Vec<(Species, Species)> {
+        let mut changes = Vec::new();
+        for name in self.changed_species.drain() {
+            if let Some(count) = self.solution.species_counts.get(&name) {
+                changes.push((name, count.clone()));
+            }
+        }
+        return changes;
+    }
+
+    // returns a rough estimate in bytes of the memory one copy of this network occupies
+    pub fn estimated_size(&self) -> usize {
+        let name_size = |name: &Species| match name {
+            Species::Name(name) => name.len(),
+            Species::Count(_) => 0,
+        };
+        let term_size = |term: &Term| std::mem::size_of::<Term>() + name_size(term.get_species_name());
+        let reaction_size: usize = self.reactions.iter()
+            .map(|reaction| std::mem::size_of::<Reaction>() 
+                + reaction.get_reactants().iter().map(term_size).sum::<usize>() 
+                + reaction.get_products().iter().map(term_size).sum::<usize>())
+            .sum();
+
+        // the possible and null adjacent sets may each hold a copy of every reaction,
+        // and hash tables keep roughly one spare slot per entry
+        return 6 * reaction_size + self.solution.estimated_size();
+    }
+
+    // sets the count of a species in solution and returns its previous count, 
+    // or None leaving the network unchanged if the species is not in solution, see `add_species`
+    pub fn set_count(&mut self, name: &str, count: Count) -> Option<Count> {
+        let species = Species::Name(name.to_string());
+        let previous = match self.solution.species_counts.get(&species)? {
+            Species::Count(previous) => *previous,
+            Species::Name(_) => 0,
+        };
+        Arc::make_mut(&mut self.solution).species_counts.insert(species.clone(), Species::Count(count));
+        self.changed_species.insert(species);
+        return Some(previous);
+    }
+
+    // adds a species to solution which no reaction needs to name, such as a flag read by the frontend. 
+    // returns false leaving its count unchanged if the network already held the species
+    pub fn add_species(&mut self, name: &str, count: Count) -> bool {
+        let species = Species::Name(name.to_string());
+        if self.solution.species_counts.contains_key(&species) {
+            return false;
+        }
+        Arc::make_mut(&mut self.solution).species_counts.insert(species.clone(), Species::Count(count));
+        self.changed_species.insert(species);
+        return true;
+    }
+
+    // holds the count of a species constant for every reaction, panics if the species is not in solution
+    pub fn clamp(&mut self, name: &str) {
+        let species = Species::Name(name.to_string());
+        if !self.solution.species_counts.contains_key(&species) {
+            panic!("can not clamp species {} which is not in the network", name);
+        }
+        self.clamped_species.insert(species);
+    }
+
+    // returns the names of all clamped species
+    pub fn get_clamped_species(&self) -> &HashSet<Species> {
+        return &self.clamped_species;
+    }
+
+    // returns the names of species both networks contain, sorted by name
+    pub fn collisions(&self, other: &ReactionNetwork) -> Vec<String> {
+        let mut collisions: Vec<String> = self.species_names().intersection(&other.species_names())
+            .map(|name| name.to_string())
+            .collect();
+        collisions.sort();
+        return collisions;
+    }
+
+    // returns a network with the reactions, species and clamps of both networks, resolving shared species names as given.
+    // Identical reactions are kept once. Panics if the networks can not be merged, see `try_merge`
+    pub fn merge(&self, other: &ReactionNetwork, collision: Collision) -> ReactionNetwork {
+        return self.try_merge(other, collision).unwrap_or_else(|error| panic!("{}", error));
+    }
+
+    // The same as `merge` but returns an error for species which still collide after resolving them 
+    // or a shared species whose counts add up past the largest count
+    pub fn try_merge(&self, other: &ReactionNetwork, collision: Collision) -> Result<ReactionNetwork, NetworkMergeError> {
+        let collisions = self.collisions(other);
+        let other = match collision {
+            Collision::Reject if !collisions.is_empty() => return Err(NetworkMergeError::Collision(collisions)),
+            Collision::Rename(ref suffix) => other.renamed(&|name: &str| match collisions.iter().any(|collision| collision == name) {
+                true => format!("{}{}", name, suffix),
+                false => name.to_string(),
+            }),
+            _ => other.clone(),
+        };
+        let still_colliding = self.collisions(&other);
+        if !still_colliding.is_empty() && collision != Collision::Share {
+            return Err(NetworkMergeError::Collision(still_colliding));
+        }
+
+        let mut solution = (*self.solution).clone();
+        for (name, count) in other.solution.iter() {
+            let count = match count {
+                Species::Count(count) => *count,
+                Species::Name(_) => 0,
+            };
+            match solution.species_counts.get_mut(name) {
+                Some(Species::Count(current_count)) => match current_count.checked_add(count) {
+                    Some(sum) => *current_count = sum,
+                    None => return Err(NetworkMergeError::CountOutOfRange(CountOutOfRange { 
+                        reaction: "merge".to_string(), species: name.to_string(), count: *current_count, change: i64::try_from(count).unwrap_or(i64::MAX) 
+                    })),
+                },
+                _ => { solution.species_counts.insert(name.clone(), Species::Count(count)); },
+            }
+        }
+        let reactions = self.reactions.iter().chain(other.reactions.iter()).cloned().collect();
+
+        let mut merged = ReactionNetwork::new(reactions, solution);
+        merged.clamped_species = self.clamped_species.union(&other.clamped_species).cloned().collect();
+        return Ok(merged);
+    }
+
+    // returns the names of every species the initial solution holds or some sequence of reactions can produce.
+    // Reactant counts are only checked against the initial solution, produced species are assumed to build up
+    // to whatever count a reaction needs, so this may over estimate what is reachable but never under estimates it.
+    pub fn reachable_species(&self) -> BTreeSet<String> {
+        let initial = self.solution.species_counts.iter()
+            .filter(|(_, count)| matches!(count, Species::Count(count) if *count > 0))
+            .map(|(name, _)| name);
+        let produced = self.fireable_reactions().into_iter()
+            .flat_map(|reaction| reaction.get_products().iter().map(|product| product.get_species_name()));
+        return initial.chain(produced).map(|name| name.to_string()).collect();
+    }
+
+    // returns every reaction which can never fire because some reactant is unreachable or never reaches the needed count
+    pub fn unreachable_reactions(&self) -> Vec<&Reaction> {
+        let fireable = self.fireable_reactions();
+        return self.reactions.iter().filter(|reaction| !fireable.contains(reaction)).collect();
+    }
+
+    // removes every reaction `unreachable_reactions` finds and returns them
+    pub fn prune_dead(&mut self) -> Vec<Reaction> {
+        let dead: Vec<Reaction> = self.unreachable_reactions().into_iter().cloned().collect();
+        for reaction in &dead {
+            self.reactions.remove(reaction);
+        }
+        self.regenerate();
+        return dead;
+    }
+
+    // fires reactions on paper until no more become possible, see `reachable_species`
+    fn fireable_reactions(&self) -> HashSet<&Reaction> {
+        let mut produced: HashSet<&Species> = HashSet::new();
+        let mut fired: HashSet<&Reaction> = HashSet::new();
+        loop {
+            let newly_fired: Vec<&Reaction> = self.reactions.iter()
+                .filter(|reaction| !fired.contains(reaction))
+                .filter(|reaction| reaction.get_reactants().iter().all(|reactant| produced.contains(reactant.get_species_name())
+                    || matches!(self.solution.species_counts.get(reactant.get_species_name()), Some(Species::Count(count)) if *count >= reactant.get_coefficient() as Count)))
+                .collect();
+            if newly_fired.is_empty() {
+                return fired;
+            }
+            for reaction in newly_fired {
+                produced.extend(reaction.get_products().iter().map(|product| product.get_species_name()));
+                fired.insert(reaction);
+            }
+        }
+    }
+
+    // returns a copy of the network with `prefix` put in front of every species name except the given ports,
+    // so one gate template can be instantiated several times and wired together through its ports with `merge`
+    pub fn namespaced(&self, prefix: &str, ports: &[&str]) -> ReactionNetwork {
+        return self.renamed(&|name: &str| match ports.contains(&name) {
+            true => name.to_string(),
+            false => format!("{}{}", prefix, name),
+        });
+    }
+
+    // returns every species in solution or in any reaction
+    fn species_names(&self) -> HashSet<&Species> {
+        let mut names: HashSet<&Species> = self.solution.species_counts.keys().collect();
+        for reaction in &self.reactions {
+            for term in reaction.get_reactants().iter().chain(reaction.get_products()) {
+                names.insert(term.get_species_name());
+            }
+        }
+        return names;
+    }
+
+    // returns a copy of the network with every species name passed through `rename`
+    fn renamed(&self, rename: &dyn Fn(&str) -> String) -> ReactionNetwork {
+        let rename_terms = |terms: &HashSet<Term>| -> HashSet<Term> {
+            terms.iter()
+                .map(|term| Term::new(rename(&term.get_species_name().to_string()), term.get_coefficient() as u8))
+                .collect()
+        };
+        let reactions = self.reactions.iter()
+            .map(|reaction| Reaction::new(rename_terms(reaction.get_reactants()), rename_terms(reaction.get_products()), reaction.get_reaction_rate()))
+            .collect();
+        let solution = Solution { species_counts: self.solution.species_counts.iter()
+            .map(|(name, count)| (Species::Name(rename(&name.to_string())), count.clone()))
+            .collect() };
+
+        let mut renamed = ReactionNetwork::new(reactions, solution);
+        renamed.clamped_species = self.clamped_species.iter().map(|name| Species::Name(rename(&name.to_string()))).collect();
+        return renamed;
+    }
+
+    // adds a reaction to the network, species it involves which are not yet in solution start at 0.
+    // returns false if the network already held the reaction
+    pub fn add_reaction(&mut self, reaction: Reaction) -> bool {
+        for term in reaction.get_reactants().iter().chain(reaction.get_products()) {
+            if !self.solution.species_counts.contains_key(term.get_species_name()) {
+                Arc::make_mut(&mut self.solution).species_counts.insert(term.get_species_name().clone(), Species::Count(0));
+            }
+        }
+        let added = self.reactions.insert(reaction);
+        self.regenerate();
+        return added;
+    }
+
+    // removes the reaction written as `reaction`, e.g. "a + 2 b => c", and returns it, 
+    // or None if the network holds no such reaction. Species stay in solution
+    pub fn remove_reaction(&mut self, reaction: &str) -> Option<Reaction> {
+        let removed = self.reactions.iter().find(|current| current.to_string() == reaction).cloned()?;
+        self.reactions.remove(&removed);
+        self.regenerate();
+        return Some(removed);
+    }
+
+    // sets the rate of the reaction written as `reaction`, e.g. "a + 2 b => c", and returns its previous rate, 
+    // or None leaving the network unchanged if it holds no such reaction
+    pub fn set_rate(&mut self, reaction: &str, rate: f64) -> Option<f64> {
+        let previous = self.reactions.iter().find(|current| current.to_string() == reaction)?.get_reaction_rate();
+        self.reactions = std::mem::take(&mut self.reactions).into_iter()
+            .map(|current| match current.to_string() == reaction {
+                true => Reaction::new(current.get_reactants().clone(), current.get_products().clone(), rate),
+                false => current,
+            })
+            .collect();
+
+        // the ordered sets hold copies of reactions, so those with the old rate have to go
+        self.regenerate();
+        return Some(previous);
+    }
 }
 
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use super::*;
+
+    fn network(reaction: &str, counts: &[(&str, Count)]) -> ReactionNetwork {
+        let (reactants, products) = reaction.split_once("=>").unwrap();
+        let terms = |side: &str| -> HashSet<Term> {side.split('+').filter_map(|term| Term::from(term.trim())).collect()};
+        return ReactionNetwork::new(HashSet::from([Reaction::new(terms(reactants), terms(products), 1.0)]), Solution::from_pairs(counts));
+    }
+
+    fn count<'a>(network: &'a ReactionNetwork, name: &str) -> Option<&'a Species> {
+        return network.get_solution().species_counts.get(&Species::Name(name.to_string()));
+    }
+
+    #[test]
+    fn test_merge_resolves_collisions() {
+        let first = network("a => b", &[("a", 5), ("b", 0)]);
+        let second = network("b => c", &[("b", 2), ("c", 0)]);
+        assert_eq!(first.collisions(&second), vec!["b".to_string()]);
+
+        let shared = first.merge(&second, Collision::Share);
+        assert_eq!(shared.get_reactions().len(), 2);
+        assert_eq!(count(&shared, "b"), Some(&Species::Count(2)));
+
+        let renamed = first.merge(&second, Collision::Rename("'".to_string()));
+        assert_eq!(count(&renamed, "b"), Some(&Species::Count(0)));
+        assert_eq!(count(&renamed, "b'"), Some(&Species::Count(2)));
+        assert!(renamed.get_reactions().iter().any(|reaction| reaction.to_string() == "b' => c"));
+    }
+
+    #[test]
+    fn test_merge_refuses_overflowing_shared_count() {
+        let first = network("a => b", &[("a", 5), ("b", Count::MAX - 1)]);
+        let second = network("b => c", &[("b", 2), ("c", 0)]);
+        let Err(NetworkMergeError::CountOutOfRange(error)) = first.try_merge(&second, Collision::Share) else {
+            panic!("merged counts past the largest count");
+        };
+        assert_eq!((error.species, error.count, error.change), ("b".to_string(), Count::MAX - 1, 2));
+    }
+
+    #[test]
+    fn test_namespaced_instances_share_ports() {
+        let gate = network("x + y => z", &[("x", 1), ("y", 1), ("z", 0)]);
+        let first = gate.namespaced("gate1.", &["x", "z"]);
+        let second = gate.namespaced("gate2.", &["x", "z"]);
+        assert_eq!(first.collisions(&second), vec!["x".to_string(), "z".to_string()]);
+
+        let circuit = first.merge(&second, Collision::Share);
+        assert_eq!(count(&circuit, "x"), Some(&Species::Count(2)));
+        assert_eq!(count(&circuit, "gate1.y"), Some(&Species::Count(1)));
+        assert!(circuit.get_reactions().iter().any(|reaction| reaction.to_string() == "gate2.y + x => z"));
+    }
+
+    #[test]
+    fn test_reachability() {
+        let mut network = network("a => b", &[("a", 1), ("b", 0), ("typo", 0)]);
+        network.reactions.insert(Reaction::new(HashSet::from([Term::new("typo".to_string(), 1)]), HashSet::from([Term::new("c".to_string(), 1)]), 1.0));
+        network.reactions.insert(Reaction::new(HashSet::from([Term::new("a".to_string(), 2)]), HashSet::from([Term::new("d".to_string(), 1)]), 1.0));
+
+        assert_eq!(network.reachable_species(), BTreeSet::from(["a".to_string(), "b".to_string()]));
+        let unreachable: Vec<String> = network.unreachable_reactions().iter().map(|reaction| reaction.to_string()).collect();
+        assert_eq!(unreachable, vec!["2 a => d".to_string(), "typo => c".to_string()]);
+
+        assert_eq!(network.prune_dead().len(), 2);
+        assert_eq!(network.get_reactions().len(), 1);
+        assert!(network.unreachable_reactions().is_empty());
+    }
+
+    #[test]
+    fn test_add_and_remove_reactions() {
+        let mut network = network("a => b", &[("a", 5), ("b", 0)]);
+        let source = Reaction::new(HashSet::new(), HashSet::from([Term::new("c".to_string(), 1)]), 1.0);
+        assert!(network.add_reaction(source.clone()));
+        assert!(!network.add_reaction(source));
+        assert_eq!(count(&network, "c"), Some(&Species::Count(0)));
+        assert_eq!(network.get_null_adjacent_reactions().len(), 1);
+
+        assert!(network.remove_reaction(" => c").is_some());
+        assert!(network.get_null_adjacent_reactions().is_empty());
+        assert_eq!(network.get_reactions().len(), 1);
+        assert_eq!(network.remove_reaction(" => c"), None);
+    }
+
+    #[test]
+    fn test_set_rate_and_count() {
+        let mut network = network("a => b", &[("a", 5), ("b", 0)]);
+        assert_eq!(network.set_rate("a => b", 3.0), Some(1.0));
+        assert_eq!(network.get_reactions().iter().next().unwrap().get_reaction_rate(), 3.0);
+        assert_eq!(network.set_rate("a => c", 3.0), None);
+
+        assert_eq!(network.set_count("a", 7), Some(5));
+        assert_eq!(count(&network, "a"), Some(&Species::Count(7)));
+        // a misspelled species is refused rather than added
+        assert_eq!(network.set_count("aa", 7), None);
+        assert_eq!(count(&network, "aa"), None);
+        assert!(network.add_species("flag", 1));
+        assert!(!network.add_species("flag", 2));
+        assert_eq!(count(&network, "flag"), Some(&Species::Count(1)));
+    }
+
+    #[test]
+    fn test_try_react_refuses_overflow() {
+        let mut network = network(" => a", &[("a", Count::MAX)]);
+        let error = network.try_react(&mut rand::thread_rng()).unwrap_err();
+        assert_eq!((error.species.as_str(), error.change), ("a", 1));
+        assert_eq!(count(&network, "a"), Some(&Species::Count(Count::MAX)));
+    }
+
+    #[test]
+    fn test_huge_rates_do_not_overflow() {
+        let term = |name: &str| HashSet::from([Term::new(name.to_string(), 1)]);
+        let reactions = HashSet::from([
+            Reaction::new(term("a"), term("b"), f64::MAX),
+            Reaction::new(term("a"), term("c"), f64::MAX),
+            Reaction::new(term("a"), term("d"), 0.5),
+        ]);
+        let mut network = ReactionNetwork::new(reactions, Solution::from_pairs(&[("a", 100), ("b", 0), ("c", 0), ("d", 0)]));
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            let fired = network.react(&mut rng).unwrap();
+            assert!(fired.get_reaction_rate() == f64::MAX);
+        }
+        // both huge reactions keep an equal share rather than the first summed one taking everything
+        assert!(matches!(count(&network, "b"), Some(Species::Count(count)) if *count > 20));
+        assert!(matches!(count(&network, "c"), Some(Species::Count(count)) if *count > 20));
+    }
+
+    #[test]
+    fn test_zero_rate_reactions_stall() {
+        let reaction = Reaction::new(HashSet::from([Term::new("a".to_string(), 1)]), HashSet::from([Term::new("b".to_string(), 1)]), 0.0);
+        let mut network = ReactionNetwork::new(HashSet::from([reaction]), Solution::from_pairs(&[("a", 5), ("b", 0)]));
+        assert_eq!(network.try_react(&mut rand::thread_rng()), Ok(None));
+        assert_eq!(count(&network, "a"), Some(&Species::Count(5)));
+    }
+
+    #[cfg(feature = "wide_counts")]
+    #[test]
+    fn test_wide_counts_pass_u64() {
+        let mut network = network(" => a", &[("a", u64::MAX as Count)]);
+        network.react(&mut rand::thread_rng());
+        assert_eq!(count(&network, "a"), Some(&Species::Count(u64::MAX as Count + 1)));
+    }
+
+    #[test]
+    fn test_missing_species_start_at_zero_unless_strict() {
+        let valid = network("a => b", &[("a", 1), ("b", 0)]);
+        assert!(valid.get_initialized_species().is_empty());
+        assert!(ReactionNetwork::new_strict(valid.reactions.into_iter().collect(), (*valid.solution).clone()).is_ok());
+
+        let typo = network("a + tpyo => b", &[("a", 1)]);
+        assert_eq!(typo.get_initialized_species(), ["b".to_string(), "tpyo".to_string()]);
+        assert_eq!(count(&typo, "tpyo"), Some(&Species::Count(0)));
+        let solution = Solution { species_counts: HashMap::from([(Species::Name("a".to_string()), Species::Count(1))]) };
+        let Err(error) = ReactionNetwork::new_strict(typo.reactions.into_iter().collect(), solution) else {panic!("typo was accepted")};
+        assert_eq!(error.reactions, vec![("a + tpyo => b".to_string(), vec!["b".to_string(), "tpyo".to_string()])]);
+    }
+
+    #[test]
+    fn test_merge_rejects_collisions() {
+        let first = network("a => b", &[("a", 5)]);
+        let rejected = first.try_merge(&network("b => c", &[]), Collision::Reject);
+        assert_eq!(rejected.err(), Some(NetworkMergeError::Collision(vec!["b".to_string()])));
+
+        // renaming b to b' still collides with a b' the first network already holds
+        let first = network("a => b'", &[("a", 5), ("b", 0)]);
+        let renamed = first.try_merge(&network("b => c", &[]), Collision::Rename("'".to_string()));
+        assert_eq!(renamed.err(), Some(NetworkMergeError::Collision(vec!["b'".to_string()])));
+    }
+}
@@ -1,23 +1,36 @@
+
 pub mod term;
 
-use std::{collections::{HashSet, HashMap}, hash::{Hash, Hasher}};
-use std::collections::hash_map::DefaultHasher;
-use term::{Term, solution::Species};
+use std::{cmp::Ordering, collections::{BTreeMap, HashSet, HashMap}, fmt::Display, hash::{Hash, Hasher}, sync::Arc};
+use term::{Term, solution::{Count, Species}};
 
 /// Stores a set for the reaction reactants and products. 
 /// Eeach element contains the variable key used by a Solution struct as well as a reaction rate. 
 /// This struct should only be used inside of the Reaction_Network Struct 
-#[derive(Debug, Eq, PartialEq,Clone)]
+/// 
+/// A 64 bit fingerprint of the reaction is computed once at construction and is used for hashing and ordering,
+/// so inserting a reaction into a set never has to walk its terms again.
+/// Rates may be fractional, a rate which is a whole number fingerprints exactly as the integer rates of earlier versions did.
+/// The net change of every species is precomputed as well, so catalysts appearing on both sides 
+/// are never subtracted and added back when the reaction fires.
+#[derive(Debug, Clone)]
 pub struct Reaction {
     reactants: HashSet<Term>,
     products: HashSet<Term>,
-    reaction_rate: u64,
+    reaction_rate: f64,
+    fingerprint: u64,
+    net_changes: Arc<[(Species, i64)]>,
 }
 
 impl Reaction {
 
-    pub fn new (reactants: HashSet<Term>, products: HashSet<Term>, reaction_rate: u64) -> Self {
-        return Self { reactants: reactants, products: products, reaction_rate: reaction_rate};
+    pub fn new (reactants: HashSet<Term>, products: HashSet<Term>, reaction_rate: f64) -> Self {
+        if !reaction_rate.is_finite() || reaction_rate < 0.0 {
+            panic!("invalid reaction rate {}, rates must be finite and not negative", reaction_rate);
+        }
+        let fingerprint = Self::gen_fingerprint(&reactants, &products, reaction_rate);
+        let net_changes = Self::gen_net_changes(&reactants, &products);
+        return Self { reactants, products, reaction_rate, fingerprint, net_changes};
     }
     
     /// returns a reference to the reactants set within a reaction
@@ -31,16 +44,32 @@ impl Reaction {
     }
 
     /// returns the reaction rate
-    pub fn get_reaction_rate (&self) -> u128 {
-        return self.reaction_rate as u128;
+    pub fn get_reaction_rate (&self) -> f64 {
+        return self.reaction_rate;
+    }
+
+    /// returns whether the rate is a whole number
+    pub fn has_integer_rate (&self) -> bool {
+        return self.reaction_rate.fract() == 0.0 && self.reaction_rate < u64::MAX as f64;
+    }
+
+    /// returns the precomputed fingerprint of the reaction
+    pub fn get_fingerprint (&self) -> u64 {
+        return self.fingerprint;
+    }
+
+    /// returns the change in count of every species the reaction alters, sorted by name. 
+    /// Species whose count the reaction leaves unchanged, like catalysts, are left out
+    pub fn get_net_changes (&self) -> &[(Species, i64)] {
+        return &self.net_changes;
     }
 
     pub fn is_possible (&self, solution: &HashMap<Species,Species>) -> bool {
         let mut reaction_possible = true;
 
         for reactant in &self.reactants {
-            if let Some(Species::Count(current_count)) = solution.get(&reactant.get_species_name()) {
-                if reactant.get_coefficient() > *current_count {
+            if let Some(Species::Count(current_count)) = solution.get(reactant.get_species_name()) {
+                if reactant.get_coefficient() as Count > *current_count {
                     reaction_possible = false;
                     break;
                 }
@@ -49,18 +78,141 @@ impl Reaction {
         
         return reaction_possible;
     }
+
+    /// Generates an FNV-1a fingerprint over the sorted reactants, sorted products and rate. 
+    /// Terms are sorted first since the iteration order of two equal HashSets is not guaranteed to match, 
+    /// and FNV is used rather than the std hasher so the value does not change between builds.
+    fn gen_fingerprint (reactants: &HashSet<Term>, products: &HashSet<Term>, reaction_rate: f64) -> u64 {
+        const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+        const FNV_PRIME: u64 = 0x100000001b3;
+
+        let mut fingerprint = FNV_OFFSET;
+        let mut feed = |bytes: &[u8]| {
+            for byte in bytes {
+                fingerprint ^= *byte as u64;
+                fingerprint = fingerprint.wrapping_mul(FNV_PRIME);
+            }
+        };
+
+        for (side_marker, side) in [(b'<', reactants), (b'>', products)] {
+            feed(&[side_marker]);
+            for term in Self::sorted_terms(side) {
+                feed(term.get_species_name().to_string().as_bytes());
+                feed(&[0]);
+                feed(&term.get_coefficient().to_le_bytes());
+            }
+        }
+        match reaction_rate.fract() == 0.0 && reaction_rate < u64::MAX as f64 {
+            true => feed(&(reaction_rate as u64).to_le_bytes()),
+            false => feed(&reaction_rate.to_bits().to_le_bytes()),
+        }
+
+        return fingerprint;
+    }
+
+    fn gen_net_changes (reactants: &HashSet<Term>, products: &HashSet<Term>) -> Arc<[(Species, i64)]> {
+        let mut net_changes: BTreeMap<Species, i64> = BTreeMap::new();
+        for reactant in reactants {
+            *net_changes.entry(reactant.get_species_name().clone()).or_default() -= reactant.get_coefficient() as i64;
+        }
+        for product in products {
+            *net_changes.entry(product.get_species_name().clone()).or_default() += product.get_coefficient() as i64;
+        }
+        return net_changes.into_iter().filter(|(_, change)| *change != 0).collect();
+    }
+
+    fn sorted_terms (terms: &HashSet<Term>) -> Vec<&Term> {
+        let mut sorted: Vec<&Term> = terms.iter().collect();
+        sorted.sort();
+        return sorted;
+    }
+}
+
+/// Formats the reaction in the same `reactants => products` notation the CSV parser reads, with terms sorted by name
+impl Display for Reaction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let join = |terms: &HashSet<Term>| Self::sorted_terms(terms).iter()
+            .map(|term| term.to_string())
+            .collect::<Vec<String>>()
+            .join(" + ");
+        write!(f, "{} => {}", join(&self.reactants), join(&self.products))
+    }
 }
 
+impl PartialEq for Reaction {
+    fn eq(&self, other: &Self) -> bool {
+        // fingerprints differing is enough to rule out equality without comparing the sets
+        return self.fingerprint == other.fingerprint 
+            && self.reaction_rate == other.reaction_rate
+            && self.reactants == other.reactants 
+            && self.products == other.products;
+    }
+}
+
+impl Eq for Reaction {}
+
 impl Hash for Reaction {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        let mut hasher = DefaultHasher::new();
-        for term in &self.reactants {
-            term.hash(&mut hasher);
-        }
-        for term in &self.products {
-            term.hash(&mut hasher);
-        }
-        self.reaction_rate.hash(&mut hasher);
-        hasher.finish().hash(state);
+        self.fingerprint.hash(state);
+    }
+}
+
+impl PartialOrd for Reaction {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        return Some(self.cmp(other));
+    }
+}
+
+impl Ord for Reaction {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // the full comparison only runs in the rare case of a fingerprint collision
+        return self.fingerprint.cmp(&other.fingerprint)
+            .then_with(|| self.reaction_rate.total_cmp(&other.reaction_rate))
+            .then_with(|| Self::sorted_terms(&self.reactants).cmp(&Self::sorted_terms(&other.reactants)))
+            .then_with(|| Self::sorted_terms(&self.products).cmp(&Self::sorted_terms(&other.products)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_ignores_term_order() {
+        let mut reactants_1 = HashSet::new();
+        reactants_1.insert(Term::new(String::from("a"), 1));
+        reactants_1.insert(Term::new(String::from("b"), 2));
+
+        let mut reactants_2 = HashSet::new();
+        reactants_2.insert(Term::new(String::from("b"), 2));
+        reactants_2.insert(Term::new(String::from("a"), 1));
+
+        let reaction_1 = Reaction::new(reactants_1, HashSet::new(), 5.0);
+        let reaction_2 = Reaction::new(reactants_2, HashSet::new(), 5.0);
+
+        assert_eq!(reaction_1.get_fingerprint(), reaction_2.get_fingerprint());
+        assert_eq!(reaction_1, reaction_2);
+        assert_eq!(reaction_1.cmp(&reaction_2), Ordering::Equal);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_fingerprint_distinguishes_sides() {
+        let mut terms = HashSet::new();
+        terms.insert(Term::new(String::from("a"), 1));
+
+        let forward = Reaction::new(terms.clone(), HashSet::new(), 1.0);
+        let backward = Reaction::new(HashSet::new(), terms, 1.0);
+
+        assert_ne!(forward.get_fingerprint(), backward.get_fingerprint());
+        assert_ne!(forward, backward);
+    }
+
+    #[test]
+    fn test_net_changes_skip_catalysts() {
+        let reactants = HashSet::from([Term::new(String::from("a"), 2), Term::new(String::from("destruct"), 1)]);
+        let products = HashSet::from([Term::new(String::from("a"), 1), Term::new(String::from("destruct"), 1), Term::new(String::from("b"), 1)]);
+        let reaction = Reaction::new(reactants, products, 1.0);
+
+        assert_eq!(reaction.get_net_changes(), &[(Species::Name(String::from("a")), -1), (Species::Name(String::from("b")), 1)]);
+    }
+}
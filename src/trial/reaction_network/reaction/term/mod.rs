@@ -1,11 +1,13 @@
 pub mod solution;
 
+use std::fmt::Display;
 use solution::Species;
+use crate::log::{self, Level};
 
 /// Contains the data for a single term within a larger reaction.
 /// Species is a reference to a named value in solution which will be added to or subtracted from. 
 /// Coefficient is the value to add or subtract
-#[derive(Debug, Hash, Eq, PartialEq, Clone)]
+#[derive(Debug, Hash, Eq, PartialEq, PartialOrd, Ord, Clone)]
 pub struct  Term {
     species_name: Species,
     coefficient: u8,
@@ -28,14 +30,14 @@ impl Term {
 
             match possible_coefficient {
                 Ok(value) => {
-                    if let None = coefficient {coefficient = Some(value)}
+                    if coefficient.is_none() {coefficient = Some(value)}
                     else {panic!("more than one numeric value provided: it is unclear which is desired coefficient")}
                 }
                 Err(_) => {
-                    if let None = species_name {species_name = Some(possible_name)}
+                    if species_name.is_none() {species_name = Some(possible_name)}
                     else {
-                        // non catastrophic error warn user with a print to console. 
-                        print!("more than one possible name found in Term {}.\n{} was used as parsed name and coefficient was assumed to be 1.", term, species_name.clone().unwrap());
+                        // non catastrophic error, warn the user
+                        log::log(Level::Warn, format_args!("more than one possible name found in Term {}, {} was used as parsed name and coefficient was assumed to be 1", term, species_name.clone().unwrap()));
                     }
                 }
             }
@@ -48,7 +50,7 @@ impl Term {
                     return Some(Term::new(parsed_name, parsed_value));
                 },
                 None => {
-                    return Some(Term::new(parsed_name, 1 as u8));
+                    return Some(Term::new(parsed_name, 1));
                 }
             }
         } else {return None}
@@ -70,6 +72,15 @@ impl Term {
 
 }
 
+impl Display for Term {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.coefficient {
+            1 => write!(f, "{}", self.species_name),
+            coefficient => write!(f, "{} {}", coefficient, self.species_name),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -107,11 +118,11 @@ fn test_from() {
     let expected_4 = Some(Term::new(String::from("2water"), 1));
     let expected_5 = None;
 
-    assert_eq!(Term::from(&term_1), expected_1);
-    assert_eq!(Term::from(&term_2), expected_2);
-    assert_eq!(Term::from(&term_3), expected_3);
-    assert_eq!(Term::from(&term_4), expected_4);
-    assert_eq!(Term::from(&term_5), expected_5);
+    assert_eq!(Term::from(term_1), expected_1);
+    assert_eq!(Term::from(term_2), expected_2);
+    assert_eq!(Term::from(term_3), expected_3);
+    assert_eq!(Term::from(term_4), expected_4);
+    assert_eq!(Term::from(term_5), expected_5);
 
 }
 
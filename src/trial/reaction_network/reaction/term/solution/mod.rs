@@ -1,10 +1,25 @@
 use std::{collections::HashMap, fmt::Display};
 
+/// The count of a species, u64 unless the `wide_counts` feature widens it to u128 for networks
+/// whose amplification would overflow a u64
+#[cfg(not(feature = "wide_counts"))]
+pub type Count = u64;
+#[cfg(feature = "wide_counts")]
+pub type Count = u128;
+
 /// Contains a Name, or count for some species
 #[derive(Debug, Hash, Eq, PartialEq, PartialOrd, Ord, Clone)]
 pub enum Species {
     Name(String),
-    Count(u64),
+    Count(Count),
+}
+
+/// returns the count after a reaction changes it by `change`, None if it would leave the range of a count
+pub(crate) fn apply_change(count: Count, change: i64) -> Option<Count> {
+    return match change < 0 {
+        true => count.checked_sub(change.unsigned_abs() as Count),
+        false => count.checked_add(change as Count),
+    };
 }
 
 impl Display for Species {
@@ -16,7 +31,7 @@ impl Display for Species {
     }
 }
 
-#[derive(Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone)]
 pub struct Solution {
     pub species_counts: HashMap<Species, Species>,
 }
@@ -29,6 +44,95 @@ impl std::hash::Hash for Solution {
     }
 }
 
+impl Solution {
+    /// Builds a solution from species names and their counts, e.g. `Solution::from_pairs(&[("a", 5), ("b", 0)])`
+    pub fn from_pairs(pairs: &[(&str, Count)]) -> Solution {
+        return pairs.iter()
+            .map(|(name, count)| (Species::Name(name.to_string()), Species::Count(*count)))
+            .collect();
+    }
+
+    /// Iterates over the species in name order without taking ownership of or copying the solution
+    pub fn iter(&self) -> std::vec::IntoIter<(&Species, &Species)> {
+        let mut sorted_entries: Vec<(&Species, &Species)> = self.species_counts.iter().collect();
+        sorted_entries.sort();
+
+        return sorted_entries.into_iter();
+    }
+
+    /// returns a rough estimate in bytes of the memory one copy of this solution occupies
+    pub fn estimated_size(&self) -> usize {
+        let entries: usize = self.species_counts.keys()
+            .map(|name| 2 * std::mem::size_of::<Species>() + match name {
+                Species::Name(name) => name.len(),
+                Species::Count(_) => 0,
+            })
+            .sum();
+        // hash tables keep roughly one spare slot per entry
+        return 2 * entries;
+    }
+
+    /// returns a copy holding only the named species
+    pub fn select(&self, species: &[String]) -> Solution {
+        let species_counts = self.species_counts.iter()
+            .filter(|(name, _)| species.iter().any(|selected| name.to_string() == *selected))
+            .map(|(name, count)| (name.clone(), count.clone()))
+            .collect();
+        return Solution { species_counts };
+    }
+
+    /// Iterates over the species with a count other than 0 in name order
+    pub fn nonzero(&self) -> impl Iterator<Item = (&Species, &Species)> {
+        return self.iter().filter(|(_, count)| **count != Species::Count(0));
+    }
+
+    /// returns a copy without the species at 0, which everything reading a solution treats the same as a species at 0
+    pub fn sparse(&self) -> Solution {
+        let species_counts = self.nonzero()
+            .map(|(name, count)| (name.clone(), count.clone()))
+            .collect();
+        return Solution { species_counts };
+    }
+
+    /// returns how the count of every species differs in `other` from this solution, sorted by name.
+    /// Species missing from either solution count as 0 there and species with equal counts are left out.
+    /// Changes too large for an i64 saturate at i64::MAX or i64::MIN.
+    pub fn diff(&self, other: &Solution) -> Vec<(Species, i64)> {
+        let count = |solution: &Solution, name: &Species| -> Count {
+            match solution.species_counts.get(name) {
+                Some(Species::Count(count)) => *count,
+                _ => 0,
+            }
+        };
+        let change = |before: Count, after: Count| -> i64 {
+            if after >= before {
+                return i64::try_from(after - before).unwrap_or(i64::MAX);
+            }
+            return i64::try_from(before - after).map(|decrease| -decrease).unwrap_or(i64::MIN);
+        };
+        let mut names: Vec<&Species> = self.species_counts.keys().chain(other.species_counts.keys()).collect();
+        names.sort();
+        names.dedup();
+        return names.into_iter()
+            .map(|name| (name.clone(), change(count(self, name), count(other, name))))
+            .filter(|(_, change)| *change != 0)
+            .collect();
+    }
+}
+
+impl FromIterator<(Species, Species)> for Solution {
+    fn from_iter<I: IntoIterator<Item = (Species, Species)>>(iter: I) -> Self {
+        return Solution { species_counts: iter.into_iter().collect() };
+    }
+}
+
+/// Adds species to the solution, replacing the counts of any already present
+impl Extend<(Species, Species)> for Solution {
+    fn extend<I: IntoIterator<Item = (Species, Species)>>(&mut self, iter: I) {
+        self.species_counts.extend(iter);
+    }
+}
+
 impl IntoIterator for Solution {
     type Item = (Species, Species);
     type IntoIter = std::vec::IntoIter<Self::Item>;
@@ -54,10 +158,39 @@ impl Display for Solution {
         // Format the ordered vector as a string
         let mut formatted_string = String::new();
         for entry in self.clone().into_iter() {
-            formatted_string.push_str(&format!("{},{},",entry.0.to_string(),entry.1.to_string()));
+            formatted_string.push_str(&format!("{},{},",entry.0,entry.1));
         }
 
         // Write the formatted string to the provided Formatter
         write!(f, "{}", formatted_string)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff() {
+        let before = Solution::from_pairs(&[("a", 5), ("b", 2), ("c", 1)]);
+        let mut after = Solution::from_pairs(&[("a", 3), ("b", 2)]);
+        after.extend([(Species::Name("d".to_string()), Species::Count(4))]);
+        let name = |name: &str| Species::Name(name.to_string());
+        assert_eq!(before.diff(&after), vec![(name("a"), -2), (name("c"), -1), (name("d"), 4)]);
+        assert!(after.diff(&after).is_empty());
+    }
+
+    #[test]
+    fn test_diff_saturates() {
+        let name = |name: &str| Species::Name(name.to_string());
+        let edge = i64::MAX as Count;
+        let low = Solution::from_iter([(name("a"), Species::Count(0)), (name("b"), Species::Count(1))]);
+        let high = Solution::from_iter([(name("a"), Species::Count(edge)), (name("b"), Species::Count(edge + 1))]);
+        assert_eq!(low.diff(&high), vec![(name("a"), i64::MAX), (name("b"), i64::MAX)]);
+        assert_eq!(high.diff(&low), vec![(name("a"), -i64::MAX), (name("b"), -i64::MAX)]);
+
+        let max = Solution::from_iter([(name("a"), Species::Count(Count::MAX))]);
+        assert_eq!(Solution::from_pairs(&[]).diff(&max), vec![(name("a"), i64::MAX)]);
+        assert_eq!(max.diff(&Solution::from_pairs(&[])), vec![(name("a"), i64::MIN)]);
+    }
+}
@@ -0,0 +1,196 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use super::ReactionNetwork;
+use super::reaction::{Reaction, term::{Term, solution::{Species, Solution}}};
+
+/// How the species of a network were reduced, used to map solutions of the reduced network back onto the original species.
+///
+/// A species `b` is folded into `c` when its only use is a unimolecular conversion `b => c`.
+/// Every reaction producing `b` then produces `c` directly and the conversion is dropped.
+/// Since `b` starts empty and no other reaction consumes it, every molecule of `b` the original network makes
+/// ends up as `c` before the trial is stable, so stable counts carry over with `b` at 0.
+/// Only the order reactions fire in changes. `reduce` folds `b` only when that order can not matter, 
+/// which is when no reaction consumes `c` or every reaction consuming `c` converts it alone into outputs no reaction consumes. 
+/// `reduce_approximate` also folds fast conversions into species other reactions compete for, 
+/// whose outcomes only approach those of the original network as the conversion gets fast relative to its competitors.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Reduction {
+    /// Each folded species mapped to the species of the reduced network it became
+    pub folded: BTreeMap<String, String>,
+    /// Species which no sequence of reactions can produce, they keep their initial count of 0
+    pub removed: BTreeSet<String>,
+}
+
+impl Reduction {
+    /// returns a solution of the original network given a solution of the reduced one, folded and removed species at 0
+    pub fn expand(&self, solution: &Solution) -> Solution {
+        let mut expanded = solution.clone();
+        for species in self.folded.keys().chain(&self.removed) {
+            expanded.species_counts.insert(Species::Name(species.clone()), Species::Count(0));
+        }
+        return expanded;
+    }
+}
+
+impl ReactionNetwork {
+    /// returns a smaller network without unreachable species and reactions and with the conversions collapsed which leave 
+    /// every outcome as it was, along with how the species were reduced. See `Reduction` for which conversions are collapsed.
+    pub fn reduce(&self) -> (ReactionNetwork, Reduction) {
+        return self.reduce_with(None);
+    }
+
+    /// returns a smaller network like `reduce` which also collapses unimolecular conversions at least as fast as `fast_rate` 
+    /// into species other reactions consume. The reduced network is only approximately equivalent to this one, see `Reduction`.
+    pub fn reduce_approximate(&self, fast_rate: f64) -> (ReactionNetwork, Reduction) {
+        return self.reduce_with(Some(fast_rate));
+    }
+
+    fn reduce_with(&self, fast_rate: Option<f64>) -> (ReactionNetwork, Reduction) {
+        let mut network = self.clone();
+        network.prune_dead();
+        let reachable = network.reachable_species();
+        let removed: BTreeSet<String> = self.solution.species_counts.keys()
+            .map(|name| name.to_string())
+            .filter(|name| !reachable.contains(name))
+            .collect();
+
+        let mut reactions: Vec<Reaction> = network.reactions.iter().cloned().collect();
+        let mut folded = BTreeMap::new();
+        while let Some((folded_reactions, from, into)) = self.fold_conversion(&reactions, fast_rate) {
+            reactions = folded_reactions;
+            // earlier folds into `from` now end at `into`
+            for target in folded.values_mut() {
+                if *target == from.to_string() {
+                    *target = into.to_string();
+                }
+            }
+            folded.insert(from.to_string(), into.to_string());
+        }
+
+        let solution = Solution { species_counts: network.solution.species_counts.iter()
+            .filter(|(name, _)| !removed.contains(&name.to_string()) && !folded.contains_key(&name.to_string()))
+            .map(|(name, count)| (name.clone(), count.clone()))
+            .collect() };
+        let mut reduced = ReactionNetwork::new(reactions.into_iter().collect(), solution);
+        reduced.clamped_species = self.clamped_species.clone();
+        return (reduced, Reduction { folded, removed });
+    }
+
+    // returns the reactions with a conversion `b => c` folded away, along with `b` and `c`. The conversion must be the only reaction 
+    // consuming `b`, `b` must start empty and neither species may be clamped. Without a fast rate it must also leave outcomes exact, 
+    // with one the conversion may instead be at least that fast. Conversions whose fold would overflow a coefficient are skipped.
+    fn fold_conversion(&self, reactions: &[Reaction], fast_rate: Option<f64>) -> Option<(Vec<Reaction>, Species, Species)> {
+        let mut consumers: HashMap<&Species, Vec<&Reaction>> = HashMap::new();
+        for reaction in reactions {
+            for reactant in reaction.get_reactants() {
+                consumers.entry(reactant.get_species_name()).or_default().push(reaction);
+            }
+        }
+        // every molecule of `c` then becomes an output on its own, regardless of when it was made
+        let exact = |product: &Species| consumers.get(product).is_none_or(|using| using.iter().all(|reaction| {
+            Self::single(reaction.get_reactants()) == Some(product)
+                && reaction.get_products().iter().all(|term| !consumers.contains_key(term.get_species_name()) && !self.clamped_species.contains(term.get_species_name()))
+        }));
+
+        return reactions.iter().enumerate().find_map(|(index, reaction)| {
+            let (reactant, product) = match (Self::single(reaction.get_reactants()), Self::single(reaction.get_products())) {
+                (Some(reactant), Some(product)) => (reactant, product),
+                _ => return None,
+            };
+            let starts_empty = matches!(self.solution.species_counts.get(reactant), Some(Species::Count(0)));
+            let equivalent = match fast_rate {
+                Some(fast_rate) => reaction.get_reaction_rate() >= fast_rate || exact(product),
+                None => exact(product),
+            };
+            let foldable = equivalent
+                && reactant != product
+                && consumers[reactant].len() == 1
+                && starts_empty
+                && !self.clamped_species.contains(reactant)
+                && !self.clamped_species.contains(product);
+            if !foldable {
+                return None;
+            }
+            let folded = reactions.iter().enumerate()
+                .filter(|(other, _)| *other != index)
+                .map(|(_, other)| Some(Reaction::new(other.get_reactants().clone(), Self::replace(other.get_products(), reactant, product)?, other.get_reaction_rate())))
+                .collect::<Option<Vec<Reaction>>>()?;
+            return Some((folded, reactant.clone(), product.clone()));
+        });
+    }
+
+    // returns the species of a side holding exactly one molecule
+    fn single(terms: &HashSet<Term>) -> Option<&Species> {
+        return match terms.iter().collect::<Vec<_>>().as_slice() {
+            [term] if term.get_coefficient() == 1 => Some(term.get_species_name()),
+            _ => None,
+        };
+    }
+
+    // returns the terms with `from` replaced by `into`, merging coefficients if both are present, 
+    // None if a merged coefficient does not fit in a term
+    fn replace(terms: &HashSet<Term>, from: &Species, into: &Species) -> Option<HashSet<Term>> {
+        let mut coefficients: BTreeMap<Species, u64> = BTreeMap::new();
+        for term in terms {
+            let name = match term.get_species_name() == from {
+                true => into,
+                false => term.get_species_name(),
+            };
+            *coefficients.entry(name.clone()).or_insert(0) += term.get_coefficient();
+        }
+        return coefficients.into_iter()
+            .map(|(name, coefficient)| u8::try_from(coefficient).ok().map(|coefficient| Term::new(name.to_string(), coefficient)))
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reduce_collapses_chain() {
+        let term = |name: &str| HashSet::from([Term::new(name.to_string(), 1)]);
+        let reactions = HashSet::from([
+            Reaction::new(term("a"), term("b"), 1.0),
+            Reaction::new(term("b"), term("c"), 100.0),
+            Reaction::new(term("c"), term("d"), 100.0),
+            Reaction::new(term("typo"), term("d"), 1.0),
+        ]);
+        let solution = Solution::from_pairs(&[("a", 5), ("b", 0), ("c", 0), ("d", 0), ("typo", 0)]);
+        let (reduced, reduction) = ReactionNetwork::new(reactions, solution).reduce();
+
+        let reactions: Vec<String> = reduced.get_reactions().iter().map(|reaction| reaction.to_string()).collect();
+        assert_eq!(reactions, vec!["a => d".to_string()]);
+        assert_eq!(reduction.folded, BTreeMap::from([("b".to_string(), "d".to_string()), ("c".to_string(), "d".to_string())]));
+        assert_eq!(reduction.removed, BTreeSet::from(["typo".to_string()]));
+        assert_eq!(reduction.expand(reduced.get_solution()).species_counts.len(), 5);
+    }
+
+    #[test]
+    fn test_reduce_keeps_competed_conversions() {
+        let term = |name: &str| HashSet::from([Term::new(name.to_string(), 1)]);
+        let reactants = |first: &str, second: &str| HashSet::from([Term::new(first.to_string(), 1), Term::new(second.to_string(), 1)]);
+        let reactions = HashSet::from([
+            Reaction::new(term("a"), term("b"), 1.0),
+            Reaction::new(term("b"), term("c"), 100.0),
+            Reaction::new(reactants("c", "e"), term("f"), 1.0),
+            Reaction::new(reactants("c", "g"), term("h"), 1.0),
+        ]);
+        let network = ReactionNetwork::new(reactions, Solution::from_pairs(&[("a", 5), ("b", 0), ("c", 0), ("e", 3), ("f", 0), ("g", 3), ("h", 0)]));
+
+        // when `c` arrives decides which of e and g it takes, so only the approximate reduction folds `b`
+        assert!(network.reduce().1.folded.is_empty());
+        assert_eq!(network.reduce_approximate(10.0).1.folded, BTreeMap::from([("b".to_string(), "c".to_string())]));
+    }
+
+    #[test]
+    fn test_reduce_refuses_coefficient_overflow() {
+        let reactions = HashSet::from([
+            Reaction::new(HashSet::from([Term::new("a".to_string(), 1)]), HashSet::from([Term::new("b".to_string(), 200), Term::new("c".to_string(), 100)]), 1.0),
+            Reaction::new(HashSet::from([Term::new("b".to_string(), 1)]), HashSet::from([Term::new("c".to_string(), 1)]), 100.0),
+        ]);
+        let (reduced, reduction) = ReactionNetwork::new(reactions, Solution::from_pairs(&[("a", 5), ("b", 0), ("c", 0)])).reduce();
+        assert!(reduction.folded.is_empty());
+        assert_eq!(reduced.get_reactions().len(), 2);
+    }
+}
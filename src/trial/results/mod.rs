@@ -1,8 +1,72 @@
+use std::{collections::HashMap, sync::Arc};
 use super::*;
+use reaction_network::reaction::term::solution::Species;
+use stability::Cycle;
+use reaction_network::CountOutOfRange;
 
 
 #[derive(Eq, PartialEq, Clone)]
 pub enum TrialResult {
-    StableSolution(Solution, i32), 
-    TimelineEntry(Solution, usize),
-}
\ No newline at end of file
+    /// The stable solution of a trial, the steps and reactions it took to get there, 
+    /// how often each reaction fired, how it ended, and the trial id
+    StableSolution(Solution, i32, u64, Firings, Termination, usize), 
+    TimelineEntry(Arc<Solution>, usize),
+    /// The counts of only the species which changed since the previous timeline message of the same trial
+    TimelineDelta(Vec<(Species, Species)>, usize),
+    /// Solutions sampled at a fixed step interval, starting with the initial solution
+    Trajectory(Vec<Solution>, usize),
+    /// The first step each passage target held at in order of the targets, None for targets never met
+    FirstPassage(Vec<Option<i32>>, usize),
+    /// The index of an alarm threshold and the step the trial first crossed it at, sent as the trial runs
+    Alarm(usize, i32, usize),
+    /// The limit cycle the trial settled into, sent before the solution
+    Cycle(Cycle, usize),
+    /// The trial stopped because a reaction would have taken a count out of range, sent instead of the solution
+    Failed(CountOutOfRange, usize),
+    /// The trial with the given id stopped early because the engine was interrupted
+    Interrupted(usize),
+}
+
+/// How often each reaction of a trial fired keyed by reaction fingerprint
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Firings {
+    pub counts: HashMap<u64, u64>,
+    /// The part of `counts` fired in the stable tail, the final steps in which the stability detector 
+    /// only fired more reactions to confirm the trial was stable
+    pub tail: HashMap<u64, u64>,
+    pub tail_steps: i32,
+}
+
+/// How a trial came to an end
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Termination {
+    /// The trial stabilized or met its stop target
+    #[default]
+    Stable,
+    /// The trial was cut off at the maximum number of steps, its solution is not stable
+    StepLimit,
+    /// The trial was abandoned once it used up its time budget, it is left out of every statistic
+    TimedOut,
+    /// The trial settled into a limit cycle, its solution is one point of the cycle
+    Oscillating,
+}
+
+/// What is known about a single trial once it has finished
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrialSummary {
+    pub id: usize,
+    /// The seed all random streams of the trial were derived from, rerunning a trial with it reproduces the solution
+    pub seed: u64,
+    /// Number of steps taken before the trial was considered stable
+    pub steps: i32,
+    /// Number of reactions which fired during the trial, more than one may fire in a semi stable step
+    pub reactions_fired: u64,
+    /// How often each reaction fired, written `a + 2 b => c` and sorted, reactions which never fired are left out
+    pub reaction_firings: Vec<(String, u64)>,
+    /// The part of `reaction_firings` fired during the stable tail of the trial, see `Firings`
+    pub tail_firings: Vec<(String, u64)>,
+    /// Number of steps the stable tail took
+    pub tail_steps: i32,
+    pub termination: Termination,
+    pub solution: Solution,
+}
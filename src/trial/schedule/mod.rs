@@ -0,0 +1,83 @@
+use super::reaction_network::{ReactionNetwork, reaction::term::solution::{Count, Species}};
+
+/// How a scheduled event changes the count of a species
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Perturbation {
+    /// Replaces the count
+    Set(Count),
+    /// Adds to the count, saturating at the largest count
+    Add(Count),
+    /// Takes from the count, stopping at 0
+    Remove(Count),
+}
+
+/// A change to a single species count applied at a fixed step in every trial,
+/// such as setting `index` to 10 or adding 100 `fuel` at step 5000, and optionally again after every period. 
+/// A species missing from the solution has a count of 0.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduledEvent {
+    step: i32,
+    period: Option<i32>,
+    species: Species,
+    perturbation: Perturbation,
+}
+
+impl ScheduledEvent {
+    pub fn new(step: i32, species: &str, perturbation: Perturbation) -> Self {
+        return Self { step, period: None, species: Species::Name(species.to_string()), perturbation };
+    }
+
+    /// an event first applied at `step` and then every `period` steps for as long as the trial runs, panics if the period is below 1
+    pub fn repeating(step: i32, period: i32, species: &str, perturbation: Perturbation) -> Self {
+        if period < 1 {
+            panic!("a repeating event needs a period of at least 1 step, found {}", period);
+        }
+        return Self { step, period: Some(period), species: Species::Name(species.to_string()), perturbation };
+    }
+
+    /// returns the first step the event is applied at
+    pub fn get_step(&self) -> i32 {
+        return self.step;
+    }
+
+    /// returns the number of steps between repeats, None if the event is only applied once
+    pub fn get_period(&self) -> Option<i32> {
+        return self.period;
+    }
+
+    pub fn get_species(&self) -> &Species {
+        return &self.species;
+    }
+
+    pub fn get_perturbation(&self) -> Perturbation {
+        return self.perturbation;
+    }
+
+    /// applies the change to the solution of the network, clamped species included
+    pub fn apply(&self, network: &mut ReactionNetwork) {
+        let current = match network.get_solution().species_counts.get(&self.species) {
+            Some(Species::Count(count)) => *count,
+            _ => 0,
+        };
+        let count = match self.perturbation {
+            Perturbation::Set(count) => count,
+            Perturbation::Add(count) => current.saturating_add(count),
+            Perturbation::Remove(count) => current.saturating_sub(count),
+        };
+        network.set_count(&self.species.to_string(), count);
+    }
+}
+
+impl std::fmt::Display for ScheduledEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.period {
+            Some(period) => write!(f, "every {} steps from step {} ", period, self.step)?,
+            None => write!(f, "at step {} ", self.step)?,
+        }
+        match self.perturbation {
+            Perturbation::Set(count) => write!(f, "set {} to {}", self.species, count),
+            Perturbation::Add(count) => write!(f, "add {} {}", count, self.species),
+            Perturbation::Remove(count) => write!(f, "remove {} {}", count, self.species),
+        }
+    }
+}
@@ -0,0 +1,292 @@
+use std::{collections::{HashMap, VecDeque}, hash::{DefaultHasher, Hash, Hasher}, sync::Arc};
+use super::reaction_network::{ReactionNetwork, reaction::{Reaction, term::solution::{Count, Species}}};
+
+/// What a detector decided after the reaction of a step fired
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// the trial keeps stepping
+    Continue,
+    /// the trial is stable and stops
+    Stable,
+    /// one more reaction fires within the same step, after which the trial stops if `stable` is true. 
+    /// The null adjacent heuristic uses this to keep null reactions feeding species while it waits
+    FireAgain { stable: bool },
+}
+
+/// Decides when a trial has settled.
+///
+/// Each trial gets its own detector, so implementations may keep whatever history they need between steps.
+pub trait StabilityDetector: Send {
+    /// called after every step with the network and the reaction which fired, None if no reaction was possible. 
+    /// The possible reactions of the network are those found before the reaction fired
+    fn check(&mut self, network: &ReactionNetwork, fired: Option<&Reaction>) -> Verdict;
+
+    /// returns the limit cycle the trial was found to settle into, if the detector looks for them
+    fn get_cycle(&self) -> Option<Cycle> {
+        return None;
+    }
+
+    /// called when the solution was changed from outside the network by a scheduled event, 
+    /// a detector keeping a history of earlier steps should start over
+    fn reset(&mut self) {}
+}
+
+/// A periodic solution a trial settled into
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cycle {
+    /// Number of steps after which the solution repeats
+    pub period: i32,
+    /// Difference between the highest and lowest count of each species over one period, sorted by name
+    pub amplitudes: Vec<(String, Count)>,
+}
+
+/// Creates a fresh detector for each trial of a run
+pub(crate) type DetectorFactory = Arc<dyn Fn() -> Box<dyn StabilityDetector> + Send + Sync>;
+
+/// The default detector. A trial is stable once no reaction is possible, 
+/// or once only null adjacent reactions have been possible for more than `max_semi_stable_steps` steps in a row.
+pub struct NullAdjacent {
+    max_semi_stable_steps: i32,
+    semi_stable_steps: Option<i32>,
+}
+
+impl NullAdjacent {
+    pub fn new(max_semi_stable_steps: i32) -> Self {
+        return Self { max_semi_stable_steps, semi_stable_steps: None };
+    }
+}
+
+impl StabilityDetector for NullAdjacent {
+    fn check(&mut self, network: &ReactionNetwork, _fired: Option<&Reaction>) -> Verdict {
+        if network.get_possible_reactions().is_empty() {
+            return Verdict::Stable;
+        }
+        if !network.get_possible_reactions().is_subset(network.get_null_adjacent_reactions()) {
+            self.semi_stable_steps = None;
+            return Verdict::Continue;
+        }
+        return match self.semi_stable_steps {
+            None => {
+                self.semi_stable_steps = Some(0);
+                Verdict::Continue
+            }
+            Some(count) if count < self.max_semi_stable_steps => {
+                self.semi_stable_steps = Some(count + 1);
+                Verdict::FireAgain { stable: false }
+            }
+            Some(_) => Verdict::FireAgain { stable: true },
+        };
+    }
+
+    fn reset(&mut self) {
+        self.semi_stable_steps = None;
+    }
+}
+
+/// A trial is stable once no reaction is possible, or once the solution has not changed for `steps` steps in a row 
+/// because only reactions without a net change, such as those made up of catalysts only, fired
+pub struct FixedPoint {
+    steps: i32,
+    unchanged_steps: i32,
+}
+
+impl FixedPoint {
+    pub fn new(steps: i32) -> Self {
+        if steps < 1 {
+            panic!("a fixed point needs to hold for at least 1 step, found {}", steps);
+        }
+        return Self { steps, unchanged_steps: 0 };
+    }
+}
+
+impl StabilityDetector for FixedPoint {
+    fn check(&mut self, _network: &ReactionNetwork, fired: Option<&Reaction>) -> Verdict {
+        let Some(reaction) = fired else {return Verdict::Stable};
+        match reaction.get_net_changes().is_empty() {
+            true => self.unchanged_steps += 1,
+            false => self.unchanged_steps = 0,
+        }
+        return match self.unchanged_steps >= self.steps {
+            true => Verdict::Stable,
+            false => Verdict::Continue,
+        };
+    }
+
+    fn reset(&mut self) {
+        self.unchanged_steps = 0;
+    }
+}
+
+/// A trial is stable once no reaction is possible, or once the mean count of every species over the last `window` steps 
+/// differs from its mean over the `window` steps before by at most `tolerance` relative to the larger of the two. 
+/// Suited to networks which settle into fluctuating around a steady state rather than halting.
+pub struct MovingWindow {
+    window: usize,
+    tolerance: f64,
+    history: VecDeque<Vec<Count>>,
+}
+
+impl MovingWindow {
+    pub fn new(window: usize, tolerance: f64) -> Self {
+        if window < 1 {
+            panic!("a moving window needs at least 1 step, found {}", window);
+        }
+        if tolerance.is_nan() || tolerance < 0.0 {
+            panic!("tolerance must not be negative, found {}", tolerance);
+        }
+        return Self { window, tolerance, history: VecDeque::with_capacity(2 * window) };
+    }
+}
+
+impl StabilityDetector for MovingWindow {
+    fn check(&mut self, network: &ReactionNetwork, fired: Option<&Reaction>) -> Verdict {
+        if fired.is_none() {
+            return Verdict::Stable;
+        }
+        let counts = network.get_solution().iter()
+            .map(|(_, count)| match count {
+                Species::Count(count) => *count,
+                Species::Name(_) => 0,
+            })
+            .collect();
+        if self.history.len() == 2 * self.window {
+            self.history.pop_front();
+        }
+        self.history.push_back(counts);
+        if self.history.len() < 2 * self.window {
+            return Verdict::Continue;
+        }
+
+        let species = self.history[0].len();
+        let mean = |samples: std::collections::vec_deque::Iter<Vec<Count>>, index: usize| -> f64 {
+            samples.map(|counts| counts[index] as f64).sum::<f64>() / self.window as f64
+        };
+        let settled = (0..species).all(|index| {
+            let older = mean(self.history.range(..self.window), index);
+            let newer = mean(self.history.range(self.window..), index);
+            (older - newer).abs() <= self.tolerance * older.max(newer)
+        });
+        return match settled {
+            true => Verdict::Stable,
+            false => Verdict::Continue,
+        };
+    }
+
+    fn reset(&mut self) {
+        self.history.clear();
+    }
+}
+
+/// A trial is stable once no reaction is possible, or once it repeats the same sequence of solutions 
+/// `repeats` times in a row, as oscillators settled into a limit cycle do. 
+/// Solutions are hashed and the last `window` of them kept, so only periods up to `window / repeats` steps are found. 
+/// The period and amplitude of the cycle are reported with the trial.
+pub struct Oscillation {
+    window: usize,
+    repeats: usize,
+    history: VecDeque<(u64, Vec<Count>)>,
+    // step each hash of the window was last seen at
+    last_seen: HashMap<u64, usize>,
+    steps: usize,
+    cycle: Option<Cycle>,
+}
+
+impl Oscillation {
+    pub fn new(window: usize, repeats: usize) -> Self {
+        if repeats < 2 {
+            panic!("a cycle needs to repeat at least 2 times to be recognized, found {}", repeats);
+        }
+        if window < repeats {
+            panic!("a window of {} steps can not hold {} repeats of a cycle", window, repeats);
+        }
+        return Self { window, repeats, history: VecDeque::with_capacity(window), last_seen: HashMap::new(), steps: 0, cycle: None };
+    }
+
+    // returns true if the last `repeats` periods of the window are identical
+    fn repeats_with(&self, period: usize) -> bool {
+        let checked = period * self.repeats;
+        if checked > self.history.len() {
+            return false;
+        }
+        let start = self.history.len() - checked;
+        return (start + period..self.history.len()).all(|index| self.history[index] == self.history[index - period]);
+    }
+}
+
+impl StabilityDetector for Oscillation {
+    fn check(&mut self, network: &ReactionNetwork, fired: Option<&Reaction>) -> Verdict {
+        if fired.is_none() {
+            return Verdict::Stable;
+        }
+        let mut hasher = DefaultHasher::new();
+        let counts: Vec<Count> = network.get_solution().iter()
+            .map(|(_, count)| match count {
+                Species::Count(count) => *count,
+                Species::Name(_) => 0,
+            })
+            .collect();
+        counts.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        if self.history.len() == self.window {
+            let (oldest, _) = self.history.pop_front().unwrap();
+            if self.last_seen.get(&oldest) == Some(&(self.steps - self.window)) {
+                self.last_seen.remove(&oldest);
+            }
+        }
+        self.history.push_back((hash, counts));
+        let previous = self.last_seen.insert(hash, self.steps);
+        self.steps += 1;
+
+        // a recurring solution is only a candidate, the sequence leading up to it has to repeat as well
+        let Some(previous) = previous else {return Verdict::Continue};
+        let period = self.steps - 1 - previous;
+        if !self.repeats_with(period) {
+            return Verdict::Continue;
+        }
+
+        let names: Vec<String> = network.get_solution().iter().map(|(name, _)| name.to_string()).collect();
+        let one_period = self.history.range(self.history.len() - period..);
+        let amplitudes = names.into_iter().enumerate()
+            .map(|(index, name)| {
+                let counts = one_period.clone().map(|(_, counts)| counts[index]);
+                (name, counts.clone().max().unwrap() - counts.min().unwrap())
+            })
+            .collect();
+        self.cycle = Some(Cycle { period: period as i32, amplitudes });
+        return Verdict::Stable;
+    }
+
+    fn get_cycle(&self) -> Option<Cycle> {
+        return self.cycle.clone();
+    }
+
+    fn reset(&mut self) {
+        self.history.clear();
+        self.last_seen.clear();
+        self.steps = 0;
+        self.cycle = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{HashMap, HashSet};
+    use super::super::reaction_network::reaction::term::{Term, solution::Solution};
+
+    #[test]
+    fn test_fixed_point_waits_for_unchanged_steps() {
+        let term = |name: &str| Term::new(name.to_string(), 1);
+        let catalytic = Reaction::new(HashSet::from([term("a")]), HashSet::from([term("a")]), 1.0);
+        let converting = Reaction::new(HashSet::from([term("a")]), HashSet::from([term("b")]), 1.0);
+        let network = ReactionNetwork::new(HashSet::new(), Solution { species_counts: HashMap::new() });
+
+        let mut detector = FixedPoint::new(2);
+        assert_eq!(detector.check(&network, Some(&catalytic)), Verdict::Continue);
+        assert_eq!(detector.check(&network, Some(&converting)), Verdict::Continue);
+        assert_eq!(detector.check(&network, Some(&catalytic)), Verdict::Continue);
+        assert_eq!(detector.check(&network, Some(&catalytic)), Verdict::Stable);
+        assert_eq!(FixedPoint::new(5).check(&network, None), Verdict::Stable);
+    }
+}
@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+use rand::{Rng, RngCore};
+use super::reaction_network::{ReactionNetwork, reaction::term::solution::Count};
+
+/// A region of initial condition space trials may be sampled from.
+/// 
+/// Each stratum holds a weight, its share of the probability mass of all initial conditions, 
+/// and an inclusive count range for each species that is randomized within it. 
+/// Species without a range keep the count they have in the prime network. 
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stratum {
+    weight: f64,
+    ranges: HashMap<String, (Count, Count)>,
+}
+
+impl Stratum {
+    pub fn new(weight: f64) -> Self {
+        if weight.is_nan() || weight <= 0.0 {
+            panic!("stratum weight must be positive, found {}", weight);
+        }
+        return Self { weight, ranges: HashMap::new() };
+    }
+
+    /// Randomizes the count of a species uniformly within `min..=max` for trials in this stratum
+    pub fn range(mut self, species: &str, min: Count, max: Count) -> Self {
+        if min > max {
+            panic!("invalid range {}..={} for species {}", min, max, species);
+        }
+        self.ranges.insert(species.to_string(), (min, max));
+        return self;
+    }
+
+    pub fn get_weight(&self) -> f64 {
+        return self.weight;
+    }
+
+    /// returns the count ranges of the stratum sorted by species name
+    pub fn get_ranges(&self) -> Vec<(&String, &(Count, Count))> {
+        let mut ranges: Vec<(&String, &(Count, Count))> = self.ranges.iter().collect();
+        ranges.sort();
+        return ranges;
+    }
+
+    /// Draws initial counts for every ranged species into the network's solution
+    pub fn sample(&self, network: &mut ReactionNetwork, rng: &mut dyn RngCore) {
+        // ranges are sorted so the draws happen in the same order for the same seed
+        for (species, (min, max)) in self.get_ranges() {
+            network.set_count(species, rng.gen_range(*min..=*max));
+        }
+    }
+}
+
+/// Splits a trial budget between strata in proportion to their weights using the largest remainder method.
+/// Every stratum gets at least one trial as long as the budget allows it.
+pub fn allocate(strata: &[Stratum], num_trials: usize) -> Vec<usize> {
+    let total_weight: f64 = strata.iter().map(|stratum| stratum.weight).sum();
+
+    let quotas: Vec<f64> = strata.iter()
+        .map(|stratum| num_trials as f64 * stratum.weight / total_weight)
+        .collect();
+    let mut allocation: Vec<usize> = quotas.iter()
+        .map(|quota| quota.floor() as usize)
+        .collect();
+
+    // hand the remaining trials to the strata with the largest fractional remainders
+    let mut by_remainder: Vec<usize> = (0..strata.len()).collect();
+    by_remainder.sort_by(|a, b| (quotas[*b] - quotas[*b].floor()).total_cmp(&(quotas[*a] - quotas[*a].floor())));
+    let mut remaining = num_trials - allocation.iter().sum::<usize>();
+    for index in by_remainder {
+        if remaining == 0 {
+            break;
+        }
+        allocation[index] += 1;
+        remaining -= 1;
+    }
+
+    // guarantee coverage by moving trials from the largest strata to any that were left empty
+    if num_trials >= strata.len() {
+        for index in 0..allocation.len() {
+            if allocation[index] == 0 {
+                let largest = (0..allocation.len()).max_by_key(|other| allocation[*other]).unwrap();
+                allocation[largest] -= 1;
+                allocation[index] += 1;
+            }
+        }
+    }
+
+    return allocation;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allocate() {
+        let strata = vec![Stratum::new(0.9), Stratum::new(0.1)];
+        assert_eq!(allocate(&strata, 10), vec![9, 1]);
+        assert_eq!(allocate(&strata, 2), vec![1, 1]);
+        assert_eq!(allocate(&strata, 1), vec![1, 0]);
+    }
+}